@@ -0,0 +1,319 @@
+use super::digest::DigestCache;
+use super::models::{
+    ApiKeyPlacement, AuthDetails, KeyValue, OAuth2Auth, OAuth2Grant, RequestDetails,
+};
+use super::oauth::TokenCache;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AuthFieldKind {
+    Text,
+    /// Masked unless password visibility is on.
+    Secret,
+    /// One of a few fixed values, changed with ←/→ rather than typed.
+    Choice,
+}
+
+/// One input of an auth form, as shown in the auth section.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuthField {
+    pub label: &'static str,
+    pub value: String,
+    pub kind: AuthFieldKind,
+}
+
+impl AuthField {
+    fn text(label: &'static str, value: &str) -> Self {
+        Self {
+            label,
+            value: value.to_string(),
+            kind: AuthFieldKind::Text,
+        }
+    }
+
+    fn secret(label: &'static str, value: &str) -> Self {
+        Self {
+            kind: AuthFieldKind::Secret,
+            ..Self::text(label, value)
+        }
+    }
+
+    fn choice(label: &'static str, value: &str) -> Self {
+        Self {
+            kind: AuthFieldKind::Choice,
+            ..Self::text(label, value)
+        }
+    }
+}
+
+/// What auth keeps between requests, shared by every request the sender runs.
+#[derive(Clone, Default)]
+pub struct AuthCache {
+    /// OAuth 2.0 access tokens, reused until they expire.
+    pub tokens: TokenCache,
+    /// Digest challenges, answered again with an increasing nonce count.
+    pub digest: DigestCache,
+}
+
+impl AuthDetails {
+    /// The form for these credentials, in display and Tab order.
+    pub fn fields(&self) -> Vec<AuthField> {
+        match self {
+            AuthDetails::None => Vec::new(),
+            AuthDetails::Basic(basic) => vec![
+                AuthField::text("Username", &basic.username),
+                AuthField::secret("Password", &basic.password),
+            ],
+            AuthDetails::Bearer(bearer) => vec![AuthField::secret("Token", &bearer.token)],
+            AuthDetails::ApiKey(api_key) => vec![
+                AuthField::text("Key", &api_key.key),
+                AuthField::secret("Value", &api_key.value),
+                AuthField::choice("Add to", api_key.placement.as_str()),
+            ],
+            AuthDetails::OAuth2(oauth2) => {
+                let mut fields = vec![AuthField::choice("Grant", oauth2.grant.as_str())];
+                if oauth2.grant == OAuth2Grant::AuthorizationCode {
+                    fields.push(AuthField::text("Authorize URL", &oauth2.authorize_url));
+                }
+                fields.extend([
+                    AuthField::text("Token URL", &oauth2.token_url),
+                    AuthField::text("Client ID", &oauth2.client_id),
+                    AuthField::secret("Client Secret", &oauth2.client_secret),
+                    AuthField::text("Scope", &oauth2.scope),
+                    AuthField::text("Audience", &oauth2.audience),
+                ]);
+                if oauth2.grant == OAuth2Grant::AuthorizationCode {
+                    fields.push(AuthField::text("Redirect port", &oauth2.redirect_port));
+                }
+                fields
+            }
+            AuthDetails::Digest(digest) => vec![
+                AuthField::text("Username", &digest.username),
+                AuthField::secret("Password", &digest.password),
+            ],
+            AuthDetails::AwsSigV4(aws) => vec![
+                AuthField::text("Access key", &aws.access_key),
+                AuthField::secret("Secret key", &aws.secret_key),
+                AuthField::secret("Session token", &aws.session_token),
+                AuthField::text("Region", &aws.region),
+                AuthField::text("Service", &aws.service),
+            ],
+            AuthDetails::Hmac(hmac) => vec![
+                AuthField::secret("Secret", &hmac.secret),
+                AuthField::choice("Algorithm", hmac.algorithm.as_str()),
+                AuthField::choice("Encoding", hmac.encoding.as_str()),
+                AuthField::text("Template", &hmac.template),
+                AuthField::text("Signature header", &hmac.signature_header),
+                AuthField::text("Timestamp header", &hmac.timestamp_header),
+                AuthField::text("Nonce header", &hmac.nonce_header),
+            ],
+        }
+    }
+
+    /// Every typed value, for expanding variables. Choices are left out.
+    fn text_values_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            AuthDetails::None => Vec::new(),
+            AuthDetails::Basic(basic) => vec![&mut basic.username, &mut basic.password],
+            AuthDetails::Bearer(bearer) => vec![&mut bearer.token],
+            AuthDetails::ApiKey(api_key) => vec![&mut api_key.key, &mut api_key.value],
+            AuthDetails::OAuth2(oauth2) => oauth2.text_values_mut(),
+            AuthDetails::Digest(digest) => vec![&mut digest.username, &mut digest.password],
+            AuthDetails::AwsSigV4(aws) => vec![
+                &mut aws.access_key,
+                &mut aws.secret_key,
+                &mut aws.session_token,
+                &mut aws.region,
+                &mut aws.service,
+            ],
+            AuthDetails::Hmac(hmac) => vec![
+                &mut hmac.secret,
+                &mut hmac.template,
+                &mut hmac.signature_header,
+                &mut hmac.timestamp_header,
+                &mut hmac.nonce_header,
+            ],
+        }
+    }
+
+    /// Stores `value` into the field at `index` of `fields`. Choices are ignored.
+    pub fn set_field(&mut self, index: usize, value: String) {
+        match (self, index) {
+            (AuthDetails::Basic(basic), 0) => basic.username = value,
+            (AuthDetails::Basic(basic), 1) => basic.password = value,
+            (AuthDetails::Bearer(bearer), 0) => bearer.token = value,
+            (AuthDetails::ApiKey(api_key), 0) => api_key.key = value,
+            (AuthDetails::ApiKey(api_key), 1) => api_key.value = value,
+            (AuthDetails::Digest(digest), 0) => digest.username = value,
+            (AuthDetails::Digest(digest), 1) => digest.password = value,
+            (AuthDetails::AwsSigV4(aws), 0) => aws.access_key = value,
+            (AuthDetails::AwsSigV4(aws), 1) => aws.secret_key = value,
+            (AuthDetails::AwsSigV4(aws), 2) => aws.session_token = value,
+            (AuthDetails::AwsSigV4(aws), 3) => aws.region = value,
+            (AuthDetails::AwsSigV4(aws), 4) => aws.service = value,
+            (AuthDetails::Hmac(hmac), 0) => hmac.secret = value,
+            (AuthDetails::Hmac(hmac), 3) => hmac.template = value,
+            (AuthDetails::Hmac(hmac), 4) => hmac.signature_header = value,
+            (AuthDetails::Hmac(hmac), 5) => hmac.timestamp_header = value,
+            (AuthDetails::Hmac(hmac), 6) => hmac.nonce_header = value,
+            // The grant comes first, the typed fields after it
+            (AuthDetails::OAuth2(oauth2), index) if index > 0 => {
+                if let Some(field) = oauth2.text_values_mut().into_iter().nth(index - 1) {
+                    *field = value;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Moves the choice at `index` of `fields` to its next value.
+    pub fn cycle_choice(&mut self, index: usize) {
+        match (self, index) {
+            (AuthDetails::ApiKey(api_key), 2) => api_key.placement = api_key.placement.toggle(),
+            (AuthDetails::OAuth2(oauth2), 0) => oauth2.grant = oauth2.grant.toggle(),
+            (AuthDetails::Hmac(hmac), 1) => hmac.algorithm = hmac.algorithm.next(),
+            (AuthDetails::Hmac(hmac), 2) => hmac.encoding = hmac.encoding.toggle(),
+            _ => {}
+        }
+    }
+
+    /// Rewrites every text field with `f`, e.g. to expand environment variables.
+    pub fn map_values(&mut self, mut f: impl FnMut(&str) -> String) {
+        for value in self.text_values_mut() {
+            *value = f(value);
+        }
+    }
+}
+
+impl OAuth2Auth {
+    /// The typed fields of the grant, in the order of `AuthDetails::fields`.
+    fn text_values_mut(&mut self) -> Vec<&mut String> {
+        match self.grant {
+            OAuth2Grant::ClientCredentials => vec![
+                &mut self.token_url,
+                &mut self.client_id,
+                &mut self.client_secret,
+                &mut self.scope,
+                &mut self.audience,
+            ],
+            OAuth2Grant::AuthorizationCode => vec![
+                &mut self.authorize_url,
+                &mut self.token_url,
+                &mut self.client_id,
+                &mut self.client_secret,
+                &mut self.scope,
+                &mut self.audience,
+                &mut self.redirect_port,
+            ],
+        }
+    }
+}
+
+/// Adds the credentials to resolved request details, as the header or query param the
+/// auth type calls for. Incomplete credentials are left out rather than sent half-filled.
+/// OAuth 2.0 and Digest need a round trip first and AWS and HMAC signatures cover the
+/// final request, so those are handled by `execute` instead.
+pub fn apply_auth(details: &mut RequestDetails) {
+    match details.auth_details.clone() {
+        AuthDetails::None
+        | AuthDetails::OAuth2(_)
+        | AuthDetails::Digest(_)
+        | AuthDetails::AwsSigV4(_)
+        | AuthDetails::Hmac(_) => {}
+        AuthDetails::Basic(basic) => {
+            // The stored header was encoded from the raw fields, so rebuild it
+            if !basic.username.is_empty() {
+                details.set_header("Authorization", basic.authorization_header());
+            }
+        }
+        AuthDetails::Bearer(bearer) => {
+            if !bearer.token.is_empty() {
+                details.set_header("Authorization", format!("Bearer {}", bearer.token));
+            }
+        }
+        AuthDetails::ApiKey(api_key) => {
+            if api_key.key.is_empty() {
+                return;
+            }
+            match api_key.placement {
+                ApiKeyPlacement::Header => details.set_header(&api_key.key, api_key.value),
+                ApiKeyPlacement::Query => {
+                    details.params.retain(|param| param.key != api_key.key);
+                    details
+                        .params
+                        .push(KeyValue::new(api_key.key, api_key.value));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::models::{ApiKeyAuth, AuthType, BearerAuth};
+
+    fn details(auth_details: AuthDetails) -> RequestDetails {
+        let mut details = RequestDetails::new();
+        details.params.push(KeyValue::new("page", "1"));
+        details.auth_details = auth_details;
+        details
+    }
+
+    #[test]
+    fn test_bearer_and_api_key_are_applied() {
+        let mut bearer = details(AuthDetails::Bearer(BearerAuth {
+            token: "abc".to_string(),
+        }));
+        apply_auth(&mut bearer);
+        assert_eq!(bearer.header("Authorization"), Some("Bearer abc"));
+
+        let api_key = ApiKeyAuth {
+            key: "X-API-Key".to_string(),
+            value: "secret".to_string(),
+            placement: ApiKeyPlacement::Header,
+        };
+        let mut in_header = details(AuthDetails::ApiKey(api_key.clone()));
+        apply_auth(&mut in_header);
+        assert_eq!(in_header.header("X-API-Key"), Some("secret"));
+        assert_eq!(in_header.params.len(), 1);
+
+        let mut in_query = details(AuthDetails::ApiKey(ApiKeyAuth {
+            key: "api_key".to_string(),
+            placement: ApiKeyPlacement::Query,
+            ..api_key
+        }));
+        apply_auth(&mut in_query);
+        assert_eq!(in_query.param("api_key"), Some("secret"));
+        assert!(in_query.headers.is_empty());
+
+        let mut empty = details(AuthType::Bearer.default_details());
+        apply_auth(&mut empty);
+        assert!(empty.header("Authorization").is_none());
+    }
+
+    #[test]
+    fn test_form_fields_round_trip() {
+        let mut auth = AuthType::ApiKey.default_details();
+        auth.set_field(0, "X-Key".to_string());
+        auth.set_field(1, "v".to_string());
+        auth.set_field(2, "ignored".to_string());
+        auth.cycle_choice(2);
+
+        let values: Vec<_> = auth.fields().into_iter().map(|field| field.value).collect();
+        assert_eq!(values, ["X-Key", "v", "Query param"]);
+
+        auth.map_values(|value| value.to_uppercase());
+        assert_eq!(auth.fields()[1].value, "V");
+
+        // The authorization code grant adds fields around the shared ones
+        let mut oauth2 = AuthType::OAuth2.default_details();
+        oauth2.set_field(1, "https://idp/token".to_string());
+        oauth2.cycle_choice(0);
+        let labels: Vec<_> = oauth2.fields().iter().map(|field| field.label).collect();
+        assert_eq!(labels.len(), 8);
+        assert_eq!(labels[1], "Authorize URL");
+        assert_eq!(oauth2.fields()[2].value, "https://idp/token");
+        oauth2.set_field(7, "8765".to_string());
+        assert_eq!(oauth2.fields()[7].value, "8765");
+    }
+}
@@ -0,0 +1,730 @@
+use std::error::Error;
+use std::fmt;
+use url::form_urlencoded;
+
+use super::auth::apply_auth;
+use super::models::{
+    ApiRequest, AuthDetails, AuthType, AwsSigV4Auth, BasicAuth, DigestAuth, KeyValue, RequestType,
+};
+use super::query;
+
+/// Builds a one-line `curl` command that sends the same request ratquest would. Disabled
+/// params and headers are left out, the rest keep their order. An OAuth 2.0 request carries
+/// its saved token while it is fresh. Auth that curl can't reproduce is named in a comment
+/// at the end instead of being dropped silently.
+pub fn to_curl(request: &ApiRequest) -> String {
+    // Bearer and API key credentials show up as the header or param they become
+    let mut details = request.details.clone();
+    apply_auth(&mut details);
+    let mut parts = vec!["curl".to_string()];
+
+    if !matches!(request.request_type, RequestType::GET) {
+        parts.push(format!("-X {}", request.request_type.as_str()));
+    }
+
+    parts.push(shell_quote(&details.url_with_query()));
+
+    // Basic auth goes out as -u in place of the stored Authorization header
+    let mut basic_auth = details
+        .get_basic_auth()
+        .filter(|basic| !basic.username.is_empty())
+        .map(|basic| {
+            format!(
+                "-u {}",
+                shell_quote(&format!("{}:{}", basic.username, basic.password))
+            )
+        });
+
+    for header in details.headers.iter().filter(|header| header.enabled) {
+        if basic_auth.is_some() && header.key.eq_ignore_ascii_case("authorization") {
+            parts.extend(basic_auth.take());
+            continue;
+        }
+        parts.push(format!(
+            "-H {}",
+            shell_quote(&format!("{}: {}", header.key, header.value))
+        ));
+    }
+    parts.extend(basic_auth);
+
+    // curl does the Digest handshake and AWS signing itself
+    let mut omitted = None;
+    match &details.auth_details {
+        AuthDetails::Digest(digest) if !digest.username.is_empty() => {
+            parts.push(format!(
+                "--digest -u {}",
+                shell_quote(&format!("{}:{}", digest.username, digest.password))
+            ));
+        }
+        AuthDetails::AwsSigV4(aws) if !aws.access_key.is_empty() => {
+            parts.push(format!(
+                "--aws-sigv4 {} -u {}",
+                shell_quote(&format!("aws:amz:{}:{}", aws.region, aws.service)),
+                shell_quote(&format!("{}:{}", aws.access_key, aws.secret_key))
+            ));
+            if !aws.session_token.is_empty() {
+                parts.push(format!(
+                    "-H {}",
+                    shell_quote(&format!("x-amz-security-token: {}", aws.session_token))
+                ));
+            }
+        }
+        AuthDetails::OAuth2(oauth2) => {
+            match oauth2.tokens.as_ref().filter(|tokens| tokens.is_fresh()) {
+                Some(tokens) => parts.push(format!(
+                    "-H {}",
+                    shell_quote(&format!("Authorization: Bearer {}", tokens.access_token))
+                )),
+                None => omitted = Some("OAuth 2.0 token omitted, send the request first"),
+            }
+        }
+        // The signature covers a timestamp and a one-time nonce, so a copy would go stale
+        AuthDetails::Hmac(_) => {
+            omitted = Some("HMAC signature omitted, ratquest signs each request as it is sent")
+        }
+        _ => {}
+    }
+
+    // Bodies are never sent with GET, see `execute`
+    if !matches!(request.request_type, RequestType::GET) && !details.body.is_empty() {
+        parts.push(format!("--data-raw {}", shell_quote(&details.body)));
+    }
+    if let Some(note) = omitted {
+        parts.push(format!("# {}", note));
+    }
+
+    parts.join(" ")
+}
+
+/// Quotes `value` for a POSIX shell. Plain words are left alone for readability.
+pub fn shell_quote(value: &str) -> String {
+    let is_plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:@%+=,".contains(c));
+    if is_plain {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CurlParseError(pub String);
+
+impl fmt::Display for CurlParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for CurlParseError {}
+
+fn parse_error(message: impl Into<String>) -> CurlParseError {
+    CurlParseError(message.into())
+}
+
+/// Splits a shell command line into words. Handles single quotes, double quotes, `$'...'`
+/// strings, backslash line continuations and `#` comments, which covers what browsers, docs
+/// and `to_curl` produce.
+fn tokenize(input: &str) -> Result<Vec<String>, CurlParseError> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            // A comment runs to the end of the line, but only where a word could start
+            '#' if !in_token => while chars.next_if(|&c| c != '\n').is_some() {},
+            '\\' => match chars.next() {
+                // Line continuation
+                Some('\n') => {}
+                Some('\r') if chars.peek() == Some(&'\n') => {
+                    chars.next();
+                }
+                Some(escaped) => {
+                    current.push(escaped);
+                    in_token = true;
+                }
+                None => {}
+            },
+            '\'' => {
+                in_token = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => return Err(parse_error("unterminated ' quote")),
+                    }
+                }
+            }
+            '"' => {
+                in_token = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => current.push(c),
+                            Some('\n') => {}
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                            None => return Err(parse_error("unterminated \" quote")),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err(parse_error("unterminated \" quote")),
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                in_token = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => current.push('\n'),
+                            Some('t') => current.push('\t'),
+                            Some('r') => current.push('\r'),
+                            Some(c @ ('\\' | '\'' | '"')) => current.push(c),
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                            None => return Err(parse_error("unterminated $' quote")),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err(parse_error("unterminated $' quote")),
+                    }
+                }
+            }
+            c => {
+                current.push(c);
+                in_token = true;
+            }
+        }
+    }
+
+    if in_token {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+/// Options that take no argument and don't change the request.
+const IGNORED_FLAGS: &[&str] = &[
+    "-s",
+    "--silent",
+    "-S",
+    "--show-error",
+    "-L",
+    "--location",
+    "-k",
+    "--insecure",
+    "-v",
+    "--verbose",
+    "-i",
+    "--include",
+    "-g",
+    "--globoff",
+    "-N",
+    "--no-buffer",
+    "-f",
+    "--fail",
+    "--compressed",
+    "--http1.1",
+    "--http2",
+];
+
+/// Options that take an argument we don't need.
+const IGNORED_OPTIONS: &[&str] = &[
+    "-o",
+    "--output",
+    "-m",
+    "--max-time",
+    "--connect-timeout",
+    "--retry",
+    "-w",
+    "--write-out",
+];
+
+/// Parses a `curl` command into a request. Understands `-X`, `-H`, `-d`/`--data-raw` and
+/// friends, `-u`, `--url`, `-G` and query strings in the URL.
+pub fn parse_curl(command: &str) -> Result<ApiRequest, CurlParseError> {
+    let tokens = tokenize(command.trim())?;
+    let mut tokens = tokens.into_iter();
+
+    match tokens.next() {
+        Some(first) if first == "curl" || first.ends_with("/curl") => {}
+        _ => return Err(parse_error("command must start with curl")),
+    }
+
+    let mut method: Option<String> = None;
+    let mut url: Option<String> = None;
+    let mut headers: Vec<KeyValue> = Vec::new();
+    let mut data: Vec<String> = Vec::new();
+    let mut basic_auth: Option<BasicAuth> = None;
+    let mut data_in_query = false;
+    let mut digest = false;
+    let mut aws_sigv4: Option<String> = None;
+
+    while let Some(token) = tokens.next() {
+        // Short options may carry their value attached, as in -XPOST
+        let (flag, attached) = match token.char_indices().nth(2) {
+            _ if !token.starts_with('-') => {
+                if url.is_some() {
+                    return Err(parse_error(format!("unexpected argument {}", token)));
+                }
+                url = Some(token);
+                continue;
+            }
+            Some((split, _)) if !token.starts_with("--") => {
+                (token[..split].to_string(), Some(token[split..].to_string()))
+            }
+            _ => (token.clone(), None),
+        };
+
+        let mut value = |name: &str| {
+            attached
+                .clone()
+                .or_else(|| tokens.next())
+                .ok_or_else(|| parse_error(format!("{} needs a value", name)))
+        };
+
+        match flag.as_str() {
+            "-X" | "--request" => method = Some(value(&flag)?.to_uppercase()),
+            "--url" => url = Some(value(&flag)?),
+            "-H" | "--header" => {
+                let header = value(&flag)?;
+                let (key, val) = header
+                    .split_once(':')
+                    .ok_or_else(|| parse_error(format!("malformed header {}", header)))?;
+                headers.push(KeyValue::new(key.trim(), val.trim()));
+            }
+            "-A" | "--user-agent" => headers.push(KeyValue::new("User-Agent", value(&flag)?)),
+            "-e" | "--referer" => headers.push(KeyValue::new("Referer", value(&flag)?)),
+            "-b" | "--cookie" => headers.push(KeyValue::new("Cookie", value(&flag)?)),
+            "-d" | "--data" | "--data-ascii" | "--data-binary" => {
+                let payload = value(&flag)?;
+                if payload.starts_with('@') {
+                    return Err(parse_error(format!(
+                        "{} {} reads from a file, which is not supported",
+                        flag, payload
+                    )));
+                }
+                data.push(payload);
+            }
+            "--data-raw" => data.push(value(&flag)?),
+            "--data-urlencode" => {
+                let payload = value(&flag)?;
+                let encoded = match payload.split_once('=') {
+                    Some((name, content)) => {
+                        let content: String =
+                            form_urlencoded::byte_serialize(content.as_bytes()).collect();
+                        format!("{}={}", name, content)
+                    }
+                    None => form_urlencoded::byte_serialize(payload.as_bytes()).collect(),
+                };
+                data.push(encoded);
+            }
+            "-u" | "--user" => {
+                let user = value(&flag)?;
+                let (username, password) = user.split_once(':').unwrap_or((user.as_str(), ""));
+                let basic = BasicAuth {
+                    username: username.to_string(),
+                    password: password.to_string(),
+                };
+                // The header goes where -u was, so exporting again keeps the order
+                headers.retain(|header| !header.key.eq_ignore_ascii_case("authorization"));
+                headers.push(KeyValue::new("Authorization", basic.authorization_header()));
+                basic_auth = Some(basic);
+            }
+            "--digest" => digest = true,
+            "--aws-sigv4" => aws_sigv4 = Some(value(&flag)?),
+            "-G" | "--get" => data_in_query = true,
+            "-I" | "--head" => return Err(parse_error("HEAD requests are not supported")),
+            flag if IGNORED_OPTIONS.contains(&flag) => {
+                value(flag)?;
+            }
+            flag if attached.is_none() && IGNORED_FLAGS.contains(&flag) => {}
+            // Bundled short flags such as -sSL
+            _ if attached.is_some()
+                && token[1..]
+                    .chars()
+                    .all(|c| IGNORED_FLAGS.contains(&format!("-{}", c).as_str())) => {}
+            _ => return Err(parse_error(format!("unsupported option {}", token))),
+        }
+    }
+
+    let url = url.ok_or_else(|| parse_error("no URL found"))?;
+
+    let method = method.unwrap_or_else(|| {
+        if !data.is_empty() && !data_in_query {
+            "POST".to_string()
+        } else {
+            "GET".to_string()
+        }
+    });
+    let request_type = match method.as_str() {
+        "GET" => RequestType::GET,
+        "POST" => RequestType::POST,
+        "PUT" => RequestType::PUT,
+        "DELETE" => RequestType::DELETE,
+        "PATCH" => RequestType::PATCH,
+        other => return Err(parse_error(format!("method {} is not supported", other))),
+    };
+
+    let mut request = ApiRequest::new(request_name(&method, &url), request_type);
+    let details = &mut request.details;
+
+    details.set_url_with_query(&url);
+    if data_in_query {
+        for (key, value) in data.drain(..).flat_map(|data| query::parse_query(&data)) {
+            details.params.push(KeyValue::new(key, value));
+        }
+    }
+
+    // curl joins repeated -d values with &
+    details.body = data.join("&");
+    if !details.body.is_empty()
+        && !headers
+            .iter()
+            .any(|header| header.key.eq_ignore_ascii_case("content-type"))
+    {
+        headers.push(KeyValue::new(
+            "Content-Type",
+            "application/x-www-form-urlencoded",
+        ));
+    }
+    details.headers = headers;
+
+    match basic_auth {
+        // With --aws-sigv4 the -u credentials are the access and secret key instead
+        Some(basic) if aws_sigv4.is_some() => {
+            let provider = aws_sigv4.unwrap_or_default();
+            let mut scope = provider.split(':').skip(2);
+            let region = scope.next().unwrap_or_default().to_string();
+            let service = scope.next().unwrap_or_default().to_string();
+            let mut session_token = String::new();
+            details.headers.retain(|header| {
+                if header.key.eq_ignore_ascii_case("x-amz-security-token") {
+                    session_token = header.value.clone();
+                    return false;
+                }
+                !header.key.eq_ignore_ascii_case("authorization")
+            });
+            details.auth_type = AuthType::AwsSigV4;
+            details.auth_details = AuthDetails::AwsSigV4(AwsSigV4Auth {
+                access_key: basic.username,
+                secret_key: basic.password,
+                session_token,
+                region,
+                service,
+            });
+        }
+        // With --digest they answer the server's challenge instead
+        Some(basic) if digest => {
+            details
+                .headers
+                .retain(|header| !header.key.eq_ignore_ascii_case("authorization"));
+            details.auth_type = AuthType::Digest;
+            details.auth_details = AuthDetails::Digest(DigestAuth {
+                username: basic.username,
+                password: basic.password,
+            });
+        }
+        Some(basic) => {
+            details.auth_type = AuthType::Basic;
+            details.auth_details = AuthDetails::Basic(basic);
+        }
+        None => {}
+    }
+
+    Ok(request)
+}
+
+/// Names an imported request after its method and path, e.g. `POST /users`.
+fn request_name(method: &str, url: &str) -> String {
+    let target = match url::Url::parse(url) {
+        Ok(parsed) if parsed.path() != "/" => parsed.path().to_string(),
+        Ok(parsed) => parsed.host_str().unwrap_or(url).to_string(),
+        Err(_) => url.split('?').next().unwrap_or(url).to_string(),
+    };
+    format!("{} {}", method, target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(request_type: RequestType, url: &str) -> ApiRequest {
+        let mut request = ApiRequest::new("test".to_string(), request_type);
+        request.details.url = url.to_string();
+        request
+    }
+
+    fn with_basic_auth(request: &mut ApiRequest, username: &str, password: &str) {
+        let basic = BasicAuth {
+            username: username.to_string(),
+            password: password.to_string(),
+        };
+        request
+            .details
+            .headers
+            .push(KeyValue::new("Authorization", basic.authorization_header()));
+        request.details.auth_type = AuthType::Basic;
+        request.details.auth_details = AuthDetails::Basic(basic);
+    }
+
+    fn round_trip(original: &ApiRequest) -> ApiRequest {
+        let command = to_curl(original);
+        parse_curl(&command).unwrap_or_else(|e| panic!("{}: {}", command, e))
+    }
+
+    fn assert_same(original: &ApiRequest, parsed: &ApiRequest) {
+        assert_eq!(original.request_type.as_str(), parsed.request_type.as_str());
+        assert_eq!(original.details.url, parsed.details.url);
+        assert_eq!(original.details.params, parsed.details.params);
+        assert_eq!(original.details.headers, parsed.details.headers);
+        assert_eq!(original.details.body, parsed.details.body);
+        assert_eq!(
+            original
+                .details
+                .get_basic_auth()
+                .map(|b| (&b.username, &b.password)),
+            parsed
+                .details
+                .get_basic_auth()
+                .map(|b| (&b.username, &b.password))
+        );
+    }
+
+    #[test]
+    fn test_to_curl() {
+        let mut post = request(RequestType::POST, "https://api.example.com/users");
+        post.details
+            .headers
+            .push(KeyValue::new("Content-Type", "application/json"));
+        post.details.body = "{\"name\": \"O'Brien\"}".to_string();
+
+        assert_eq!(
+            to_curl(&post),
+            "curl -X POST https://api.example.com/users -H 'Content-Type: application/json' \
+             --data-raw '{\"name\": \"O'\\''Brien\"}'"
+        );
+
+        let mut get = request(RequestType::GET, "https://api.example.com/search");
+        get.details.params.push(KeyValue::new("q", "rust & tui"));
+        get.details.params.push(KeyValue::new("tag", "b"));
+        get.details.params.push(KeyValue::new("tag", "a"));
+        let mut disabled = KeyValue::new("debug", "1");
+        disabled.enabled = false;
+        get.details.params.push(disabled);
+        get.details.body = "ignored".to_string();
+        assert_eq!(
+            to_curl(&get),
+            "curl 'https://api.example.com/search?q=rust+%26+tui&tag=b&tag=a'"
+        );
+    }
+
+    #[test]
+    fn test_to_curl_oauth2_and_hmac() {
+        use crate::app::models::{HmacAuth, OAuth2Auth, OAuth2Tokens};
+
+        let mut get = request(RequestType::GET, "https://api.example.com/me");
+        get.details.auth_type = AuthType::OAuth2;
+        get.details.auth_details = AuthDetails::OAuth2(OAuth2Auth::default());
+        assert_eq!(
+            to_curl(&get),
+            "curl https://api.example.com/me # OAuth 2.0 token omitted, send the request first"
+        );
+
+        get.details.auth_details = AuthDetails::OAuth2(OAuth2Auth {
+            tokens: Some(OAuth2Tokens {
+                access_token: "t0ken".to_string(),
+                refresh_token: None,
+                expires_at: None,
+            }),
+            ..OAuth2Auth::default()
+        });
+        assert_eq!(
+            to_curl(&get),
+            "curl https://api.example.com/me -H 'Authorization: Bearer t0ken'"
+        );
+
+        get.details.auth_type = AuthType::Hmac;
+        get.details.auth_details = AuthDetails::Hmac(HmacAuth::default());
+        let command = to_curl(&get);
+        assert!(command
+            .ends_with(" # HMAC signature omitted, ratquest signs each request as it is sent"));
+        // The comment doesn't get in the way of importing the command again
+        assert_eq!(
+            parse_curl(&command).unwrap().details.url,
+            "https://api.example.com/me"
+        );
+    }
+
+    #[test]
+    fn test_round_trip_get_with_params_and_headers() {
+        let mut original = request(RequestType::GET, "https://api.example.com/items");
+        let details = &mut original.details;
+        details.params.push(KeyValue::new("page", "2"));
+        details.params.push(KeyValue::new("filter", "name eq 'x'"));
+        details.params.push(KeyValue::new("page", "3"));
+        details.headers.push(KeyValue::new("X-Trace", "a\"b$c"));
+        details
+            .headers
+            .push(KeyValue::new("Accept", "application/json"));
+        details.headers.push(KeyValue::new("Accept", "text/plain"));
+
+        assert_same(&original, &round_trip(&original));
+    }
+
+    #[test]
+    fn test_round_trip_post_with_body() {
+        let mut original = request(RequestType::POST, "http://localhost:8080/users");
+        original
+            .details
+            .headers
+            .push(KeyValue::new("Content-Type", "application/json"));
+        original.details.body =
+            "{\n  \"name\": \"it's me\",\n  \"tags\": [\"a\", \"b\"]\n}".to_string();
+
+        assert_same(&original, &round_trip(&original));
+    }
+
+    #[test]
+    fn test_round_trip_basic_auth() {
+        for request_type in [RequestType::PUT, RequestType::DELETE, RequestType::PATCH] {
+            let mut original = request(request_type, "https://api.example.com/things/1");
+            with_basic_auth(&mut original, "admin", "p@ss:w'rd");
+            original
+                .details
+                .headers
+                .push(KeyValue::new("Content-Type", "text/plain"));
+            original.details.body = "hello world".to_string();
+
+            assert_same(&original, &round_trip(&original));
+        }
+    }
+
+    #[test]
+    fn test_round_trip_digest_auth() {
+        let mut original = request(RequestType::GET, "https://api.example.com/private");
+        original.details.auth_type = AuthType::Digest;
+        original.details.auth_details = AuthDetails::Digest(DigestAuth {
+            username: "Mufasa".to_string(),
+            password: "Circle of Life".to_string(),
+        });
+
+        let command = to_curl(&original);
+        assert_eq!(
+            command,
+            "curl https://api.example.com/private --digest -u 'Mufasa:Circle of Life'"
+        );
+        let parsed = round_trip(&original);
+        assert!(parsed.details.headers.is_empty());
+        match parsed.details.auth_details {
+            AuthDetails::Digest(digest) => assert_eq!(digest.password, "Circle of Life"),
+            _ => panic!("expected Digest auth"),
+        }
+    }
+
+    #[test]
+    fn test_round_trip_aws_sigv4() {
+        let mut original = request(RequestType::GET, "http://localhost:9000/bucket");
+        original.details.auth_type = AuthType::AwsSigV4;
+        original.details.auth_details = AuthDetails::AwsSigV4(AwsSigV4Auth {
+            access_key: "minio".to_string(),
+            secret_key: "minio123".to_string(),
+            session_token: "token".to_string(),
+            region: "us-east-1".to_string(),
+            service: "s3".to_string(),
+        });
+
+        let command = to_curl(&original);
+        assert_eq!(
+            command,
+            "curl http://localhost:9000/bucket --aws-sigv4 aws:amz:us-east-1:s3 \
+             -u minio:minio123 -H 'x-amz-security-token: token'"
+        );
+        let parsed = round_trip(&original);
+        assert!(parsed.details.headers.is_empty());
+        match parsed.details.auth_details {
+            AuthDetails::AwsSigV4(aws) => {
+                assert_eq!(aws.secret_key, "minio123");
+                assert_eq!(aws.session_token, "token");
+                assert_eq!(
+                    (aws.region.as_str(), aws.service.as_str()),
+                    ("us-east-1", "s3")
+                );
+            }
+            _ => panic!("expected AWS Signature auth"),
+        }
+    }
+
+    #[test]
+    fn test_parse_common_flags() {
+        let parsed = parse_curl(
+            "curl -XPUT --url 'https://api.example.com/v1/items?id=7' \\\n  \
+             -H 'Accept: application/json' \\\n  -u bob:secret \\\n  -d 'a=1' -d 'b=2' -sSL",
+        )
+        .unwrap();
+
+        assert!(matches!(parsed.request_type, RequestType::PUT));
+        assert_eq!(parsed.name, "PUT /v1/items");
+        assert_eq!(parsed.details.url, "https://api.example.com/v1/items");
+        assert_eq!(parsed.details.param("id"), Some("7"));
+        assert_eq!(parsed.details.header("Accept"), Some("application/json"));
+        assert_eq!(parsed.details.body, "a=1&b=2");
+        assert_eq!(
+            parsed.details.header("Content-Type"),
+            Some("application/x-www-form-urlencoded")
+        );
+        assert_eq!(parsed.details.get_basic_auth().unwrap().username, "bob");
+    }
+
+    #[test]
+    fn test_parse_bundled_flags() {
+        let err = parse_curl("curl -sSLXDELETE https://example.com/items/1").unwrap_err();
+        assert_eq!(err.to_string(), "unsupported option -sSLXDELETE");
+
+        let parsed = parse_curl("curl -sS -X DELETE https://example.com/items/1").unwrap();
+        assert!(matches!(parsed.request_type, RequestType::DELETE));
+    }
+
+    #[test]
+    fn test_parse_defaults_method_from_data() {
+        let parsed = parse_curl("curl https://example.com/login --data-raw '{\"a\":1}'").unwrap();
+        assert!(matches!(parsed.request_type, RequestType::POST));
+
+        let parsed = parse_curl("curl -G https://example.com/search -d q=rust").unwrap();
+        assert!(matches!(parsed.request_type, RequestType::GET));
+        assert_eq!(parsed.details.param("q"), Some("rust"));
+        assert!(parsed.details.body.is_empty());
+    }
+
+    #[test]
+    fn test_parse_ansi_c_quotes() {
+        let parsed =
+            parse_curl("curl 'https://example.com' --data-raw $'{\"line\":\"a\\nb\"}'").unwrap();
+        assert_eq!(parsed.details.body, "{\"line\":\"a\nb\"}");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_curl("wget https://example.com").is_err());
+        assert!(parse_curl("curl").is_err());
+        assert!(parse_curl("curl 'https://example.com").is_err());
+        assert!(parse_curl("curl -d @body.json https://example.com").is_err());
+        assert!(parse_curl("curl --frobnicate https://example.com").is_err());
+    }
+}
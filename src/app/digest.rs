@@ -0,0 +1,692 @@
+use openssl::hash::{hash, MessageDigest};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use url::Url;
+
+use super::error::{ErrorKind, RequestError};
+use super::models::DigestAuth;
+use super::oauth::random_token;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Algorithm {
+    Md5,
+    Md5Sess,
+    Sha256,
+    Sha256Sess,
+}
+
+impl Algorithm {
+    /// The algorithm a challenge names. Challenges without one mean MD5.
+    fn parse(name: Option<&str>) -> Option<Self> {
+        match name.map(str::to_ascii_uppercase).as_deref() {
+            None | Some("MD5") => Some(Algorithm::Md5),
+            Some("MD5-SESS") => Some(Algorithm::Md5Sess),
+            Some("SHA-256") => Some(Algorithm::Sha256),
+            Some("SHA-256-SESS") => Some(Algorithm::Sha256Sess),
+            _ => None,
+        }
+    }
+
+    /// The `-sess` variants hash the nonces into the credentials as well.
+    fn is_session(self) -> bool {
+        matches!(self, Algorithm::Md5Sess | Algorithm::Sha256Sess)
+    }
+
+    /// `data` hashed and written as lowercase hex.
+    fn hash(self, data: &str) -> Result<String, RequestError> {
+        let digest = match self {
+            Algorithm::Md5 | Algorithm::Md5Sess => MessageDigest::md5(),
+            Algorithm::Sha256 | Algorithm::Sha256Sess => MessageDigest::sha256(),
+        };
+        let bytes = hash(digest, data.as_bytes()).map_err(|e| {
+            RequestError::new(ErrorKind::Internal, format!("Digest hashing failed: {}", e))
+        })?;
+        Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+    }
+}
+
+/// A `WWW-Authenticate: Digest` challenge.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Challenge {
+    pub realm: String,
+    pub nonce: String,
+    pub opaque: Option<String>,
+    /// As the server spelled it, since it is echoed back.
+    pub algorithm: Option<String>,
+    pub qop: Vec<String>,
+    /// The nonce expired, the credentials were fine.
+    pub stale: bool,
+}
+
+impl Challenge {
+    /// The Digest challenge among response headers, preferring one with an algorithm we
+    /// can answer when the server offers several.
+    pub fn find(headers: &[(String, String)]) -> Option<Self> {
+        let challenges: Vec<Challenge> = headers
+            .iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case("www-authenticate"))
+            .flat_map(|(_, value)| parse_challenges(value))
+            .filter(|(scheme, _)| scheme == "digest")
+            .filter_map(|(_, params)| Self::from_params(params))
+            .collect();
+        let supported = challenges
+            .iter()
+            .position(|challenge| Algorithm::parse(challenge.algorithm.as_deref()).is_some());
+        challenges.into_iter().nth(supported.unwrap_or(0))
+    }
+
+    fn from_params(mut params: HashMap<String, String>) -> Option<Self> {
+        Some(Self {
+            nonce: params.remove("nonce")?,
+            realm: params.remove("realm").unwrap_or_default(),
+            opaque: params.remove("opaque"),
+            algorithm: params.remove("algorithm"),
+            qop: params
+                .remove("qop")
+                .map(|qop| {
+                    qop.split(',')
+                        .map(|value| value.trim().to_string())
+                        .filter(|value| !value.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            stale: params
+                .get("stale")
+                .is_some_and(|stale| stale.eq_ignore_ascii_case("true")),
+        })
+    }
+}
+
+/// Every challenge in a `WWW-Authenticate` value as its lowercased scheme and params.
+/// Several challenges can share one value, separated by commas just like their params.
+fn parse_challenges(header: &str) -> Vec<(String, HashMap<String, String>)> {
+    let mut challenges: Vec<(String, HashMap<String, String>)> = Vec::new();
+    let separators: &[char] = &[',', ' ', '\t'];
+    let mut rest = header.trim_start_matches(separators);
+    while !rest.is_empty() {
+        let end = rest
+            .find(|c: char| c == '=' || c == ',' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let token = &rest[..end];
+        let after = rest[end..].trim_start();
+        if let Some(value) = after.strip_prefix('=') {
+            let (value, remainder) = parse_value(value.trim_start());
+            if let Some((_, params)) = challenges.last_mut() {
+                params.insert(token.to_ascii_lowercase(), value);
+            }
+            rest = remainder;
+        } else {
+            challenges.push((token.to_ascii_lowercase(), HashMap::new()));
+            rest = after;
+        }
+        rest = rest.trim_start_matches(separators);
+    }
+    challenges
+}
+
+/// A quoted string with its escapes undone, or a bare token, and what follows it.
+fn parse_value(input: &str) -> (String, &str) {
+    let Some(quoted) = input.strip_prefix('"') else {
+        let end = input
+            .find(|c: char| c == ',' || c.is_whitespace())
+            .unwrap_or(input.len());
+        return (input[..end].to_string(), &input[end..]);
+    };
+
+    let mut value = String::new();
+    let mut chars = quoted.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => value.extend(chars.next().map(|(_, escaped)| escaped)),
+            '"' => return (value, &quoted[i + 1..]),
+            c => value.push(c),
+        }
+    }
+    (value, "")
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// The algorithm `challenge` asks for, if we can answer it.
+fn algorithm(challenge: &Challenge) -> Result<Algorithm, RequestError> {
+    Algorithm::parse(challenge.algorithm.as_deref()).ok_or_else(|| {
+        RequestError::new(
+            ErrorKind::Auth,
+            format!(
+                "The server asks for Digest algorithm {}, only MD5 and SHA-256 are supported",
+                challenge.algorithm.as_deref().unwrap_or_default()
+            ),
+        )
+    })
+}
+
+/// HA1, the hashed credentials. The `-sess` variants hash in the server's nonce and our
+/// `cnonce` too, so both have to stay the same while the server's nonce lasts.
+fn credentials_hash(
+    challenge: &Challenge,
+    auth: &DigestAuth,
+    cnonce: &str,
+) -> Result<String, RequestError> {
+    let algorithm = algorithm(challenge)?;
+    let ha1 = algorithm.hash(&format!(
+        "{}:{}:{}",
+        auth.username, challenge.realm, auth.password
+    ))?;
+    if algorithm.is_session() {
+        return algorithm.hash(&format!("{}:{}:{}", ha1, challenge.nonce, cnonce));
+    }
+    Ok(ha1)
+}
+
+/// The `Authorization` header answering `challenge` for `method` on `uri`, the path and
+/// query. `nc` counts the requests made under this nonce, `cnonce` is our own nonce and
+/// `ha1` the `credentials_hash` made with it.
+fn answer(
+    challenge: &Challenge,
+    auth: &DigestAuth,
+    ha1: &str,
+    method: &str,
+    uri: &str,
+    nc: u32,
+    cnonce: &str,
+) -> Result<String, RequestError> {
+    let algorithm = algorithm(challenge)?;
+    let qop_auth = challenge
+        .qop
+        .iter()
+        .any(|qop| qop.eq_ignore_ascii_case("auth"));
+    if !challenge.qop.is_empty() && !qop_auth {
+        return Err(RequestError::new(
+            ErrorKind::Auth,
+            format!(
+                "The server asks for Digest qop={}, only qop=auth is supported",
+                challenge.qop.join(",")
+            ),
+        ));
+    }
+
+    let ha2 = algorithm.hash(&format!("{}:{}", method, uri))?;
+    let nc = format!("{:08x}", nc);
+    let response = if qop_auth {
+        algorithm.hash(&format!(
+            "{}:{}:{}:{}:auth:{}",
+            ha1, challenge.nonce, nc, cnonce, ha2
+        ))?
+    } else {
+        // RFC 2069, for servers that predate qop
+        algorithm.hash(&format!("{}:{}:{}", ha1, challenge.nonce, ha2))?
+    };
+
+    let mut params = vec![
+        ("username", quote(&auth.username)),
+        ("realm", quote(&challenge.realm)),
+        ("nonce", quote(&challenge.nonce)),
+        ("uri", quote(uri)),
+    ];
+    if let Some(algorithm) = &challenge.algorithm {
+        params.push(("algorithm", algorithm.clone()));
+    }
+    params.push(("response", quote(&response)));
+    if let Some(opaque) = &challenge.opaque {
+        params.push(("opaque", quote(opaque)));
+    }
+    if qop_auth {
+        params.push(("qop", "auth".to_string()));
+        params.push(("nc", nc));
+        params.push(("cnonce", quote(cnonce)));
+    }
+    let params: Vec<String> = params
+        .into_iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect();
+    Ok(format!("Digest {}", params.join(", ")))
+}
+
+/// The origin a challenge applies to and the `uri` to answer it for, with `params`
+/// appended to the query the same way the request sends them.
+fn request_target(url: &str, params: &[(String, String)]) -> Option<(String, String)> {
+    let mut url = Url::parse(url).ok()?;
+    if !params.is_empty() {
+        url.query_pairs_mut().extend_pairs(params);
+    }
+    let uri = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+    Some((url.origin().ascii_serialization(), uri))
+}
+
+/// The `Authorization` header of one Digest attempt, and the nonce it answered.
+pub struct Attempt {
+    pub header: String,
+    pub nonce: String,
+}
+
+struct Session {
+    challenge: Challenge,
+    /// Requests made under the challenge's nonce so far.
+    nonce_count: u32,
+    /// Our nonce and the HA1 made with it, from the first answer to the challenge.
+    key: Option<SessionKey>,
+}
+
+struct SessionKey {
+    cnonce: String,
+    ha1: String,
+    /// The password `ha1` was made from. The key is made afresh once it changes.
+    password: String,
+}
+
+/// The latest challenge per origin and username, shared by every request the sender
+/// runs. Later requests answer it straight away instead of waiting for another 401.
+#[derive(Clone, Default)]
+pub struct DigestCache {
+    sessions: Arc<Mutex<HashMap<(String, String), Session>>>,
+}
+
+impl DigestCache {
+    fn lock(&self) -> MutexGuard<'_, HashMap<(String, String), Session>> {
+        self.sessions.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Remembers `challenge` for the origin of `url`. A new nonce starts counting afresh,
+    /// with a new client nonce.
+    pub fn insert(&self, url: &str, auth: &DigestAuth, challenge: Challenge) {
+        let Some((origin, _)) = request_target(url, &[]) else {
+            return;
+        };
+        self.lock().insert(
+            (origin, auth.username.clone()),
+            Session {
+                challenge,
+                nonce_count: 0,
+                key: None,
+            },
+        );
+    }
+
+    /// Forgets the challenge for the origin of `url`, after the server turned down our
+    /// answer to it. The next request goes without `Authorization` to get a new one.
+    pub fn remove(&self, url: &str, auth: &DigestAuth) {
+        let Some((origin, _)) = request_target(url, &[]) else {
+            return;
+        };
+        self.lock().remove(&(origin, auth.username.clone()));
+    }
+
+    /// The next attempt for `method` on `url` under the challenge cached for its origin,
+    /// counting it against the nonce. `None` until the server has sent a challenge.
+    pub fn authorization(
+        &self,
+        auth: &DigestAuth,
+        method: &str,
+        url: &str,
+        params: &[(String, String)],
+    ) -> Result<Option<Attempt>, RequestError> {
+        let Some((origin, uri)) = request_target(url, params) else {
+            return Ok(None);
+        };
+        let mut sessions = self.lock();
+        let Some(session) = sessions.get_mut(&(origin, auth.username.clone())) else {
+            return Ok(None);
+        };
+        let key = match &mut session.key {
+            Some(key) if key.password == auth.password => key,
+            key => {
+                let cnonce = random_token(16)?;
+                let ha1 = credentials_hash(&session.challenge, auth, &cnonce)?;
+                key.insert(SessionKey {
+                    cnonce,
+                    ha1,
+                    password: auth.password.clone(),
+                })
+            }
+        };
+        session.nonce_count += 1;
+        let header = answer(
+            &session.challenge,
+            auth,
+            &key.ha1,
+            method,
+            &uri,
+            session.nonce_count,
+            &key.cnonce,
+        )?;
+        Ok(Some(Attempt {
+            header,
+            nonce: session.challenge.nonce.clone(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::auth::AuthCache;
+    use crate::app::models::{RequestType, TlsSettings};
+    use crate::app::proxy::ProxyConfig;
+    use crate::app::requests::{execute, PreparedRequest};
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+
+    fn mufasa(password: &str) -> DigestAuth {
+        DigestAuth {
+            username: "Mufasa".to_string(),
+            password: password.to_string(),
+        }
+    }
+
+    fn param<'a>(header: &'a str, name: &str) -> &'a str {
+        header
+            .split(", ")
+            .find_map(|pair| {
+                pair.trim_start_matches("Digest ")
+                    .strip_prefix(name)?
+                    .strip_prefix('=')
+            })
+            .unwrap()
+            .trim_matches('"')
+    }
+
+    /// The first answer to `challenge`, with our nonce fixed to `cnonce`.
+    fn first_answer(
+        challenge: &Challenge,
+        auth: &DigestAuth,
+        method: &str,
+        uri: &str,
+        cnonce: &str,
+    ) -> Result<String, RequestError> {
+        let ha1 = credentials_hash(challenge, auth, cnonce)?;
+        answer(challenge, auth, &ha1, method, uri, 1, cnonce)
+    }
+
+    #[test]
+    fn test_rfc_2617_example() {
+        let headers = vec![(
+            "WWW-Authenticate".to_string(),
+            r#"Digest realm="testrealm@host.com", qop="auth,auth-int", nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093", opaque="5ccc069c403ebaf9f0171e9517f40e41""#.to_string(),
+        )];
+        let challenge = Challenge::find(&headers).unwrap();
+        assert_eq!(challenge.qop, ["auth", "auth-int"]);
+
+        let header = first_answer(
+            &challenge,
+            &mufasa("Circle Of Life"),
+            "GET",
+            "/dir/index.html",
+            "0a4f113b",
+        )
+        .unwrap();
+        assert_eq!(
+            param(&header, "response"),
+            "6629fae49393a05397450978507c4ef1"
+        );
+        assert_eq!(param(&header, "nc"), "00000001");
+        assert_eq!(param(&header, "opaque"), "5ccc069c403ebaf9f0171e9517f40e41");
+        assert!(!header.contains("algorithm="));
+    }
+
+    #[test]
+    fn test_rfc_7616_examples() {
+        // The server offers SHA-256 first and MD5 for older clients
+        let headers = vec![
+            (
+                "www-authenticate".to_string(),
+                r#"Digest realm="http-auth@example.org", qop="auth, auth-int", algorithm=SHA-256, nonce="7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v", opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#.to_string(),
+            ),
+            (
+                "www-authenticate".to_string(),
+                r#"Digest realm="http-auth@example.org", qop="auth, auth-int", algorithm=MD5, nonce="7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v", opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#.to_string(),
+            ),
+        ];
+        let sha256 = Challenge::find(&headers).unwrap();
+        let md5 = Challenge::find(&headers[1..]).unwrap();
+        assert_eq!(sha256.algorithm.as_deref(), Some("SHA-256"));
+
+        let cnonce = "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ";
+        let auth = mufasa("Circle of Life");
+        let header = first_answer(&sha256, &auth, "GET", "/dir/index.html", cnonce).unwrap();
+        assert_eq!(
+            param(&header, "response"),
+            "753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1"
+        );
+        assert_eq!(param(&header, "algorithm"), "SHA-256");
+        let header = first_answer(&md5, &auth, "GET", "/dir/index.html", cnonce).unwrap();
+        assert_eq!(
+            param(&header, "response"),
+            "8ca523f5e9506fed4657c9700eebdbec"
+        );
+    }
+
+    #[test]
+    fn test_challenge_next_to_other_schemes() {
+        let headers = vec![(
+            "WWW-Authenticate".to_string(),
+            r#"Basic realm="api", Digest realm="api", nonce="n\"1", stale=TRUE, Bearer"#
+                .to_string(),
+        )];
+        let challenge = Challenge::find(&headers).unwrap();
+        assert_eq!(challenge.realm, "api");
+        assert_eq!(challenge.nonce, "n\"1");
+        assert!(challenge.stale);
+        assert!(challenge.qop.is_empty());
+
+        let unsupported = Challenge {
+            algorithm: Some("SHA-512-256".to_string()),
+            ..challenge.clone()
+        };
+        let error = first_answer(&unsupported, &mufasa("x"), "GET", "/", "c").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Auth);
+
+        let header = first_answer(&challenge, &mufasa("x"), "GET", "/", "c").unwrap();
+        assert!(header.contains(r#"nonce="n\"1""#));
+        assert!(!header.contains("qop="));
+    }
+
+    #[test]
+    fn test_session_key_is_kept_per_nonce() {
+        let challenge = Challenge {
+            realm: "api".to_string(),
+            nonce: "abc".to_string(),
+            opaque: None,
+            algorithm: Some("MD5-sess".to_string()),
+            qop: vec!["auth".to_string()],
+            stale: false,
+        };
+        let auth = mufasa("Circle of Life");
+        let cache = DigestCache::default();
+        let url = "http://localhost/items";
+        cache.insert(url, &auth, challenge.clone());
+
+        let attempt = || {
+            cache
+                .authorization(&auth, "GET", url, &[])
+                .unwrap()
+                .unwrap()
+                .header
+        };
+        let first = attempt();
+        let second = attempt();
+        let cnonce = param(&first, "cnonce");
+        assert_eq!(param(&second, "cnonce"), cnonce);
+        assert_eq!(param(&first, "nc"), "00000001");
+        assert_eq!(param(&second, "nc"), "00000002");
+        let ha1 = credentials_hash(&challenge, &auth, cnonce).unwrap();
+        assert_eq!(
+            second,
+            answer(&challenge, &auth, &ha1, "GET", "/items", 2, cnonce).unwrap()
+        );
+
+        // A new nonce gets a new client nonce and starts counting again
+        let renewed = Challenge {
+            nonce: "def".to_string(),
+            ..challenge
+        };
+        cache.insert(url, &auth, renewed);
+        let third = attempt();
+        assert_ne!(param(&third, "cnonce"), cnonce);
+        assert_eq!(param(&third, "nc"), "00000001");
+    }
+
+    #[test]
+    fn test_corrected_password_is_answered_with() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/items", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let challenge = "HTTP/1.1 401 Unauthorized\r\n\
+                 WWW-Authenticate: Digest realm=\"api\", qop=\"auth\", nonce=\"abc\"\r\n\
+                 Content-Length: 0\r\nConnection: close\r\n\r\n";
+            let ok = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok";
+            for response in [challenge, challenge, challenge, ok] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0; 4096];
+                let n = stream.read(&mut buf).unwrap();
+                let _ = tx.send(String::from_utf8_lossy(&buf[..n]).to_string());
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+
+        let request = |password: &str| PreparedRequest {
+            request_type: RequestType::GET,
+            url: url.clone(),
+            body: String::new(),
+            headers: Vec::new(),
+            params: Vec::new(),
+            oauth2: None,
+            digest: Some(mufasa(password)),
+            aws_sigv4: None,
+            hmac: None,
+            tls: TlsSettings::default(),
+            proxy: ProxyConfig::default(),
+        };
+        let cache = AuthCache::default();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let response = runtime
+            .block_on(execute(request("Circle of Lie"), &cache))
+            .unwrap();
+        assert_eq!(response.status, 401);
+        let response = runtime
+            .block_on(execute(request("Circle of Life"), &cache))
+            .unwrap();
+        assert_eq!(response.status, 200);
+
+        // The turned down challenge was forgotten, so the second send started over
+        let sent: Vec<String> = rx.iter().take(4).collect();
+        assert!(!sent[2].to_lowercase().contains("authorization:"));
+        let header = sent[3]
+            .lines()
+            .find_map(|line| {
+                let (name, value) = line.split_once(": ")?;
+                name.eq_ignore_ascii_case("authorization").then_some(value)
+            })
+            .unwrap();
+        let challenge = Challenge {
+            realm: "api".to_string(),
+            nonce: "abc".to_string(),
+            opaque: None,
+            algorithm: None,
+            qop: vec!["auth".to_string()],
+            stale: false,
+        };
+        let expected = first_answer(
+            &challenge,
+            &mufasa("Circle of Life"),
+            "GET",
+            "/items",
+            param(header, "cnonce"),
+        )
+        .unwrap();
+        assert_eq!(header, expected);
+    }
+
+    #[test]
+    fn test_key_is_made_afresh_when_the_password_changes() {
+        let challenge = Challenge {
+            realm: "api".to_string(),
+            nonce: "abc".to_string(),
+            opaque: None,
+            algorithm: Some("MD5-sess".to_string()),
+            qop: vec!["auth".to_string()],
+            stale: false,
+        };
+        let cache = DigestCache::default();
+        let url = "http://localhost/items";
+        cache.insert(url, &mufasa("Circle of Lie"), challenge.clone());
+        cache
+            .authorization(&mufasa("Circle of Lie"), "GET", url, &[])
+            .unwrap()
+            .unwrap();
+
+        let auth = mufasa("Circle of Life");
+        let header = cache
+            .authorization(&auth, "GET", url, &[])
+            .unwrap()
+            .unwrap()
+            .header;
+        let cnonce = param(&header, "cnonce");
+        let ha1 = credentials_hash(&challenge, &auth, cnonce).unwrap();
+        assert_eq!(
+            header,
+            answer(&challenge, &auth, &ha1, "GET", "/items", 2, cnonce).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_nonce_count_carries_over_to_later_requests() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let challenge = "HTTP/1.1 401 Unauthorized\r\n\
+                 WWW-Authenticate: Digest realm=\"api\", qop=\"auth\", nonce=\"abc\"\r\n\
+                 Content-Length: 0\r\nConnection: close\r\n\r\n";
+            let ok = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok";
+            for response in [challenge, ok, ok] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0; 4096];
+                let n = stream.read(&mut buf).unwrap();
+                let _ = tx.send(String::from_utf8_lossy(&buf[..n]).to_string());
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+
+        let request = PreparedRequest {
+            request_type: RequestType::GET,
+            url: format!("{}/items", base),
+            body: String::new(),
+            headers: Vec::new(),
+            params: vec![("page".to_string(), "1".to_string())],
+            oauth2: None,
+            digest: Some(mufasa("Circle of Life")),
+            aws_sigv4: None,
+            hmac: None,
+            tls: TlsSettings::default(),
+            proxy: ProxyConfig::default(),
+        };
+        let cache = AuthCache::default();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        for _ in 0..2 {
+            let response = runtime.block_on(execute(request.clone(), &cache)).unwrap();
+            assert_eq!(response.status, 200);
+        }
+
+        let sent: Vec<String> = rx.iter().take(3).collect();
+        assert!(!sent[0].to_lowercase().contains("authorization:"));
+        for (raw, nc) in sent[1..].iter().zip(["00000001", "00000002"]) {
+            let header = raw
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(": ")?;
+                    name.eq_ignore_ascii_case("authorization").then_some(value)
+                })
+                .unwrap();
+            assert_eq!(param(header, "uri"), "/items?page=1");
+            assert_eq!(param(header, "nc"), nc);
+        }
+    }
+}
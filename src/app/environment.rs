@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use super::models::{KeyValue, RequestDetails};
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Environment {
+    pub name: String,
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+}
+
+impl Environment {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            variables: HashMap::new(),
+        }
+    }
+
+    /// Variables sorted by name, for display.
+    pub fn sorted_variables(&self) -> Vec<(&String, &String)> {
+        let mut variables: Vec<_> = self.variables.iter().collect();
+        variables.sort();
+        variables
+    }
+}
+
+/// Placeholders that had no value in the active environment.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UndefinedVariables(pub Vec<String>);
+
+impl fmt::Display for UndefinedVariables {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = self
+            .0
+            .iter()
+            .map(|name| format!("{{{{{}}}}}", name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if self.0.len() == 1 {
+            write!(f, "Undefined variable {}", names)
+        } else {
+            write!(f, "Undefined variables {}", names)
+        }
+    }
+}
+
+impl Error for UndefinedVariables {}
+
+/// Replaces every `{{name}}` in `input` with its value from `variables`. Whitespace inside the
+/// braces is ignored and an unterminated `{{` is kept as-is. Unknown names are collected into
+/// `missing` rather than left in the output.
+fn substitute_into(
+    input: &str,
+    variables: &HashMap<String, String>,
+    missing: &mut Vec<String>,
+) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+
+        output.push_str(&rest[..start]);
+        let name = rest[start + 2..start + 2 + len].trim();
+        match variables.get(name) {
+            Some(value) => output.push_str(value),
+            None => {
+                if !missing.iter().any(|m| m == name) {
+                    missing.push(name.to_string());
+                }
+            }
+        }
+        rest = &rest[start + 2 + len + 2..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+pub fn substitute(
+    input: &str,
+    variables: &HashMap<String, String>,
+) -> Result<String, UndefinedVariables> {
+    let mut missing = Vec::new();
+    let output = substitute_into(input, variables, &mut missing);
+    if missing.is_empty() {
+        Ok(output)
+    } else {
+        Err(UndefinedVariables(missing))
+    }
+}
+
+/// Expands placeholders in the URL, params, headers, body and auth fields of a request.
+/// Every undefined name across all fields is reported at once.
+pub fn resolve_details(
+    details: &RequestDetails,
+    variables: &HashMap<String, String>,
+) -> Result<RequestDetails, UndefinedVariables> {
+    let mut missing = Vec::new();
+    let mut expand = |value: &str| substitute_into(value, variables, &mut missing);
+
+    let mut resolved = details.clone();
+    resolved.url = expand(&details.url);
+    resolved.body = expand(&details.body);
+    let mut expand_entry = |entry: &KeyValue| KeyValue {
+        key: expand(&entry.key),
+        value: expand(&entry.value),
+        enabled: entry.enabled,
+    };
+    resolved.params = details.params.iter().map(&mut expand_entry).collect();
+    resolved.headers = details.headers.iter().map(&mut expand_entry).collect();
+
+    resolved.auth_details.map_values(&mut expand);
+
+    if missing.is_empty() {
+        Ok(resolved)
+    } else {
+        Err(UndefinedVariables(missing))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::models::{AuthDetails, AuthType, BasicAuth};
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_substitute_variables() {
+        let variables = vars(&[("host", "localhost:8080"), ("version", "v2")]);
+        assert_eq!(
+            substitute("http://{{host}}/{{ version }}/users", &variables).unwrap(),
+            "http://localhost:8080/v2/users"
+        );
+        assert_eq!(
+            substitute("no placeholders", &variables).unwrap(),
+            "no placeholders"
+        );
+        assert_eq!(substitute("{{host", &variables).unwrap(), "{{host");
+    }
+
+    #[test]
+    fn test_substitute_reports_undefined() {
+        let variables = vars(&[("host", "localhost")]);
+        let err = substitute("{{scheme}}://{{host}}/{{path}}/{{scheme}}", &variables).unwrap_err();
+        assert_eq!(err.0, vec!["scheme".to_string(), "path".to_string()]);
+        assert_eq!(err.to_string(), "Undefined variables {{scheme}}, {{path}}");
+    }
+
+    #[test]
+    fn test_resolve_details() {
+        let mut details = RequestDetails::new();
+        details.url = "{{base}}/login".to_string();
+        details.body = "{\"user\": \"{{user}}\"}".to_string();
+        details.params.push(KeyValue::new("key", "{{api_key}}"));
+        details
+            .headers
+            .push(KeyValue::new("X-Tenant", "{{tenant}}"));
+        details.auth_type = AuthType::Basic;
+        details.auth_details = AuthDetails::Basic(BasicAuth {
+            username: "{{user}}".to_string(),
+            password: "{{password}}".to_string(),
+        });
+
+        let variables = vars(&[
+            ("base", "https://staging.example.com"),
+            ("user", "alice"),
+            ("password", "s3cret"),
+            ("api_key", "abc"),
+            ("tenant", "acme"),
+        ]);
+
+        let resolved = resolve_details(&details, &variables).unwrap();
+        assert_eq!(resolved.url, "https://staging.example.com/login");
+        assert_eq!(resolved.body, "{\"user\": \"alice\"}");
+        assert_eq!(resolved.param("key"), Some("abc"));
+        assert_eq!(resolved.header("X-Tenant"), Some("acme"));
+        let basic = resolved.get_basic_auth().unwrap();
+        assert_eq!(basic.username, "alice");
+        assert_eq!(basic.password, "s3cret");
+
+        let err = resolve_details(&details, &vars(&[("base", "x")])).unwrap_err();
+        assert_eq!(err.0.len(), 4);
+    }
+}
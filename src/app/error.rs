@@ -0,0 +1,307 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+use super::tls::TlsInfo;
+
+/// What went wrong with a request, coarse enough to tell "server down" from "bad URL".
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ErrorKind {
+    InvalidUrl,
+    InvalidRequest,
+    UndefinedVariable,
+    /// The OAuth 2.0 access token could not be fetched.
+    Auth,
+    Dns,
+    ConnectionRefused,
+    Connection,
+    /// The proxy couldn't be reached or wouldn't connect to the server.
+    Proxy,
+    Tls,
+    Timeout,
+    Redirect,
+    Body,
+    Request,
+    Cancelled,
+    Internal,
+}
+
+impl ErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::InvalidUrl => "Invalid URL",
+            ErrorKind::InvalidRequest => "Invalid request",
+            ErrorKind::UndefinedVariable => "Undefined variable",
+            ErrorKind::Auth => "Authorization failed",
+            ErrorKind::Dns => "DNS lookup failed",
+            ErrorKind::ConnectionRefused => "Connection refused",
+            ErrorKind::Connection => "Connection failed",
+            ErrorKind::Proxy => "Proxy error",
+            ErrorKind::Tls => "TLS error",
+            ErrorKind::Timeout => "Timed out",
+            ErrorKind::Redirect => "Redirect error",
+            ErrorKind::Body => "Response body error",
+            ErrorKind::Request => "Request failed",
+            ErrorKind::Cancelled => "Cancelled",
+            ErrorKind::Internal => "Internal error",
+        }
+    }
+
+    /// One-line suggestion shown under the error.
+    pub fn hint(&self) -> &'static str {
+        match self {
+            ErrorKind::InvalidUrl => "Check the URL, including the scheme (http:// or https://)",
+            ErrorKind::InvalidRequest => {
+                "A header name or value contains characters HTTP doesn't allow"
+            }
+            ErrorKind::UndefinedVariable => "Define the variable in the active environment (v)",
+            ErrorKind::Auth => "No access token, check the token URL and client credentials",
+            ErrorKind::Dns => "The host name could not be resolved",
+            ErrorKind::ConnectionRefused => {
+                "Nothing is listening on that host and port, is the server running?"
+            }
+            ErrorKind::Connection => "The connection could not be established or was dropped",
+            ErrorKind::Proxy => "The proxy could not be reached, check the proxy settings (g or t)",
+            ErrorKind::Tls => "The TLS handshake failed, check the certificate and protocol",
+            ErrorKind::Timeout => "The server took too long to answer",
+            ErrorKind::Redirect => "The server redirected too many times",
+            ErrorKind::Body => "The response body could not be read or decoded",
+            ErrorKind::Request => "The request could not be completed",
+            ErrorKind::Cancelled => "The request was cancelled before it finished",
+            ErrorKind::Internal => "Something went wrong inside ratquest",
+        }
+    }
+}
+
+/// A failed request, kept next to `App::last_response`.
+#[derive(Clone, Debug)]
+pub struct RequestError {
+    pub kind: ErrorKind,
+    pub message: String,
+    /// Underlying errors, outermost first.
+    pub causes: Vec<String>,
+    /// Time from dispatch until the failure, if the request got that far.
+    pub elapsed: Option<Duration>,
+    /// The server's certificates, when the connection got as far as a TLS handshake.
+    pub tls: Option<TlsInfo>,
+    /// The proxy the request was sent through, without credentials.
+    pub proxy: Option<String>,
+}
+
+impl RequestError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            causes: Vec::new(),
+            elapsed: None,
+            tls: None,
+            proxy: None,
+        }
+    }
+
+    pub fn with_elapsed(mut self, elapsed: Duration) -> Self {
+        self.elapsed = Some(elapsed);
+        self
+    }
+
+    /// Notes that the request went through `proxy`. A connection that failed then never
+    /// got past the proxy, so it is the proxy that is reported.
+    pub fn through_proxy(mut self, proxy: Option<String>) -> Self {
+        if proxy.is_some()
+            && matches!(
+                self.kind,
+                ErrorKind::ConnectionRefused | ErrorKind::Connection
+            )
+        {
+            self.kind = ErrorKind::Proxy;
+        }
+        self.proxy = proxy;
+        self
+    }
+
+    /// One-line suggestion shown under the error, naming the proxy for proxy errors.
+    pub fn hint(&self) -> String {
+        match (&self.kind, &self.proxy) {
+            (ErrorKind::Proxy, Some(proxy)) => format!(
+                "The proxy at {} could not be reached or refused to connect",
+                proxy
+            ),
+            (kind, _) => kind.hint().to_string(),
+        }
+    }
+
+    pub fn from_reqwest(err: &reqwest::Error, elapsed: Duration) -> Self {
+        let causes = cause_chain(err);
+        Self {
+            kind: classify(err, &causes),
+            message: err.to_string(),
+            causes,
+            elapsed: Some(elapsed),
+            tls: None,
+            proxy: None,
+        }
+    }
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind.as_str(), self.message)
+    }
+}
+
+impl Error for RequestError {}
+
+/// Messages of every `source()` below `err`. hyper tends to repeat itself, so consecutive
+/// duplicates are dropped.
+fn cause_chain(err: &(dyn Error + 'static)) -> Vec<String> {
+    let mut causes: Vec<String> = Vec::new();
+    let mut source = err.source();
+    while let Some(cause) = source {
+        let message = cause.to_string();
+        if causes.last() != Some(&message) && message != err.to_string() {
+            causes.push(message);
+        }
+        source = cause.source();
+    }
+    causes
+}
+
+fn has_io_error(err: &(dyn Error + 'static), kind: io::ErrorKind) -> bool {
+    let mut source = err.source();
+    while let Some(cause) = source {
+        if cause
+            .downcast_ref::<io::Error>()
+            .is_some_and(|io| io.kind() == kind)
+        {
+            return true;
+        }
+        source = cause.source();
+    }
+    false
+}
+
+fn classify(err: &reqwest::Error, causes: &[String]) -> ErrorKind {
+    let mentions = |needles: &[&str]| {
+        causes.iter().any(|cause| {
+            let cause = cause.to_lowercase();
+            needles.iter().any(|needle| cause.contains(needle))
+        })
+    };
+    let is_tls = || mentions(&["ssl", "tls", "certificate", "handshake"]);
+
+    if err.is_builder() {
+        return if mentions(&["header"]) {
+            ErrorKind::InvalidRequest
+        } else {
+            ErrorKind::InvalidUrl
+        };
+    }
+    if err.is_timeout() {
+        return ErrorKind::Timeout;
+    }
+    if err.is_redirect() {
+        return ErrorKind::Redirect;
+    }
+    if err.is_connect() {
+        if has_io_error(err, io::ErrorKind::ConnectionRefused) || mentions(&["connection refused"])
+        {
+            return ErrorKind::ConnectionRefused;
+        }
+        if mentions(&[
+            "dns error",
+            "failed to lookup address",
+            "name or service not known",
+            "no such host",
+            "nodename nor servname",
+        ]) {
+            return ErrorKind::Dns;
+        }
+        if is_tls() {
+            return ErrorKind::Tls;
+        }
+        return ErrorKind::Connection;
+    }
+    if err.is_body() || err.is_decode() {
+        return ErrorKind::Body;
+    }
+    if is_tls() {
+        return ErrorKind::Tls;
+    }
+    if err.is_request() {
+        return ErrorKind::Request;
+    }
+    ErrorKind::Internal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::auth::AuthCache;
+    use crate::app::models::{RequestType, TlsSettings};
+    use crate::app::proxy::ProxyConfig;
+    use crate::app::requests::{execute, PreparedRequest};
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn send(url: &str) -> RequestError {
+        let request = PreparedRequest {
+            request_type: RequestType::GET,
+            url: url.to_string(),
+            body: String::new(),
+            headers: Vec::new(),
+            params: Vec::new(),
+            oauth2: None,
+            digest: None,
+            aws_sigv4: None,
+            hmac: None,
+            tls: TlsSettings::default(),
+            proxy: ProxyConfig::default(),
+        };
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        match runtime.block_on(execute(request, &AuthCache::default())) {
+            Ok(_) => panic!("expected {} to fail", url),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn test_invalid_url() {
+        let err = send("localhost:8080/users");
+        assert_eq!(err.kind, ErrorKind::InvalidUrl);
+        assert!(err.elapsed.is_some());
+
+        assert_eq!(send("").kind, ErrorKind::InvalidUrl);
+    }
+
+    #[test]
+    fn test_connection_refused() {
+        // Grab a free port and close it again so nothing is listening there
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let err = send(&format!("http://127.0.0.1:{}/", port));
+        assert_eq!(err.kind, ErrorKind::ConnectionRefused);
+        assert!(!err.causes.is_empty());
+    }
+
+    #[test]
+    fn test_truncated_body() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 1024];
+            let _ = stream.read(&mut buf);
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\npartial");
+        });
+
+        let err = send(&format!("http://{}/", addr));
+        assert_eq!(err.kind, ErrorKind::Body);
+    }
+}
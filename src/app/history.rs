@@ -0,0 +1,430 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::json::FormattedBody;
+use super::models::KeyValue;
+use super::query;
+use super::requests::{PreparedRequest, RequestResponse};
+use super::state::{App, CurrentScreen};
+
+/// Responses kept per request, older ones are dropped.
+pub const HISTORY_LIMIT: usize = 20;
+
+/// Unchanged lines shown around each change in a diff.
+const DIFF_CONTEXT: usize = 3;
+
+/// Above this many line pairs the diff gives up on aligning lines and shows the changed
+/// part as removed then added.
+const DIFF_MAX_CELLS: usize = 4_000_000;
+
+/// A response as kept in the history of its request.
+#[derive(Clone)]
+pub struct HistoryEntry {
+    pub sent_at: SystemTime,
+    /// The request as sent, with variables expanded and auth applied.
+    pub request: PreparedRequest,
+    pub response: RequestResponse,
+}
+
+impl HistoryEntry {
+    /// Size of the body in bytes.
+    pub fn size(&self) -> usize {
+        self.response.body.len()
+    }
+
+    /// Method and URL as sent, query included.
+    pub fn request_line(&self) -> String {
+        let params: Vec<KeyValue> = self
+            .request
+            .params
+            .iter()
+            .map(|(key, value)| KeyValue::new(key, value))
+            .collect();
+        format!(
+            "{} {}",
+            self.request.request_type.as_str(),
+            query::with_query(&self.request.url, &params)
+        )
+    }
+
+    /// One line for the history list, e.g. `14:02:11 UTC  200  35ms  1.2 KB  GET https://…`.
+    pub fn summary(&self) -> String {
+        format!(
+            "{}  {}  {}ms  {}  {}",
+            format_time(self.sent_at),
+            self.response.status,
+            self.response.time_taken.as_millis(),
+            format_size(self.size()),
+            self.request_line()
+        )
+    }
+
+    /// The lines a diff compares: request line, status, headers and body, without timings.
+    fn diff_text(&self) -> Vec<String> {
+        let response = &self.response;
+        let mut lines = vec![
+            self.request_line(),
+            format!("Status: {} {}", response.status, response.status_text),
+            String::new(),
+        ];
+        lines.extend(
+            response
+                .headers
+                .iter()
+                .map(|(k, v)| format!("{}: {}", k, v)),
+        );
+        lines.push(String::new());
+        let body = match &response.formatted_body {
+            FormattedBody::Json(pretty) => pretty,
+            FormattedBody::Invalid(_) | FormattedBody::Raw => &response.body,
+        };
+        lines.extend(body.lines().map(str::to_string));
+        lines
+    }
+}
+
+/// Time of day in UTC, e.g. `14:02:11 UTC`.
+pub fn format_time(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let day = secs % 86_400;
+    format!(
+        "{:02}:{:02}:{:02} UTC",
+        day / 3600,
+        day % 3600 / 60,
+        day % 60
+    )
+}
+
+/// Human-readable byte count, e.g. `512 B` or `1.2 KB`.
+pub fn format_size(bytes: usize) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        1024..=1_048_575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+/// The request in flight, so its response is filed under the request that sent it even if
+/// the selection changes meanwhile.
+pub struct PendingRequest {
+    pub request_id: String,
+    pub request: PreparedRequest,
+    pub sent_at: SystemTime,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiffLine {
+    Same(String),
+    Removed(String),
+    Added(String),
+    /// A run of unchanged lines left out of the output.
+    Skipped(usize),
+}
+
+/// Line diff of `old` and `new`, with long unchanged runs collapsed to `Skipped`.
+pub fn diff_lines(old: &[String], new: &[String]) -> Vec<DiffLine> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    let mut lines: Vec<DiffLine> = old[..prefix].iter().cloned().map(DiffLine::Same).collect();
+    if a.len() * b.len() > DIFF_MAX_CELLS {
+        lines.extend(a.iter().cloned().map(DiffLine::Removed));
+        lines.extend(b.iter().cloned().map(DiffLine::Added));
+    } else {
+        // lcs[i][j] is the longest common subsequence of a[i..] and b[j..]
+        let width = b.len() + 1;
+        let mut lcs = vec![0u32; (a.len() + 1) * width];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lcs[i * width + j] = if a[i] == b[j] {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() || j < b.len() {
+            if i < a.len() && j < b.len() && a[i] == b[j] {
+                lines.push(DiffLine::Same(a[i].clone()));
+                i += 1;
+                j += 1;
+            } else if i < a.len()
+                && (j == b.len() || lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])
+            {
+                lines.push(DiffLine::Removed(a[i].clone()));
+                i += 1;
+            } else {
+                lines.push(DiffLine::Added(b[j].clone()));
+                j += 1;
+            }
+        }
+    }
+    lines.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .cloned()
+            .map(DiffLine::Same),
+    );
+    collapse_unchanged(lines)
+}
+
+/// Keeps `DIFF_CONTEXT` unchanged lines around each change and replaces the rest.
+fn collapse_unchanged(lines: Vec<DiffLine>) -> Vec<DiffLine> {
+    let mut output = Vec::with_capacity(lines.len());
+    let mut run: Vec<DiffLine> = Vec::new();
+    let flush = |run: &mut Vec<DiffLine>, output: &mut Vec<DiffLine>, at_end: bool| {
+        let at_start = output.is_empty();
+        let keep_before = if at_start { 0 } else { DIFF_CONTEXT };
+        let keep_after = if at_end { 0 } else { DIFF_CONTEXT };
+        if run.len() > keep_before + keep_after + 1 {
+            let skipped = run.len() - keep_before - keep_after;
+            let tail = run.split_off(run.len() - keep_after);
+            run.truncate(keep_before);
+            output.append(run);
+            output.push(DiffLine::Skipped(skipped));
+            output.extend(tail);
+        } else {
+            output.append(run);
+        }
+    };
+
+    for line in lines {
+        if matches!(line, DiffLine::Same(_)) {
+            run.push(line);
+        } else {
+            flush(&mut run, &mut output, false);
+            output.push(line);
+        }
+    }
+    flush(&mut run, &mut output, true);
+    output
+}
+
+/// A diff between two history entries, shown in a popup.
+pub struct ResponseDiff {
+    pub title: String,
+    pub lines: Vec<DiffLine>,
+    pub scroll: u16,
+}
+
+/// State of the history list in the response pane.
+#[derive(Default)]
+pub struct HistoryView {
+    /// True while the list replaces the response in the response pane.
+    pub open: bool,
+    /// Cursor in the list, newest entry first.
+    pub selected: usize,
+    /// Entry marked as the older side of a diff.
+    pub marked: Option<usize>,
+    /// Entry shown in the response pane, `None` for the latest.
+    pub shown: Option<usize>,
+    pub diff: Option<ResponseDiff>,
+}
+
+impl App {
+    /// History of the current request, newest first.
+    pub fn current_history(&self) -> &[HistoryEntry] {
+        self.get_current_request()
+            .and_then(|request| self.history.get(&request.id))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Files `response` under the request that sent it and shows it if that request is
+    /// still the current one.
+    pub fn record_response(&mut self, pending: PendingRequest, response: RequestResponse) {
+        let is_current = self.is_current_request(&pending.request_id);
+
+        let entries = self.history.entry(pending.request_id).or_default();
+        entries.insert(
+            0,
+            HistoryEntry {
+                sent_at: pending.sent_at,
+                request: pending.request,
+                response: response.clone(),
+            },
+        );
+        entries.truncate(HISTORY_LIMIT);
+
+        if is_current {
+            // Everything moved down by one
+            let view = &mut self.history_view;
+            view.shown = None;
+            view.selected = 0;
+            view.marked = view
+                .marked
+                .map(|idx| idx + 1)
+                .filter(|&idx| idx < HISTORY_LIMIT);
+            self.last_error = None;
+            self.last_response = Some(response);
+        }
+    }
+
+    /// Shows the latest response of the current request, called when the selection
+    /// changes so the pane never shows another request's response.
+    pub fn show_current_history(&mut self) {
+        self.history_view = Default::default();
+        self.last_error = None;
+        self.last_response = self
+            .current_history()
+            .first()
+            .map(|entry| entry.response.clone());
+        self.reset_response_view();
+    }
+
+    pub fn open_history(&mut self) {
+        if !self.current_history().is_empty() {
+            self.history_view.open = true;
+            self.history_view.selected = self.history_view.shown.unwrap_or(0);
+        }
+    }
+
+    pub fn close_history(&mut self) {
+        self.history_view.open = false;
+    }
+
+    pub fn history_next(&mut self) {
+        let len = self.current_history().len();
+        if self.history_view.selected + 1 < len {
+            self.history_view.selected += 1;
+        }
+    }
+
+    pub fn history_previous(&mut self) {
+        self.history_view.selected = self.history_view.selected.saturating_sub(1);
+    }
+
+    /// Shows the entry under the cursor in the response pane.
+    pub fn show_history_entry(&mut self) {
+        let selected = self.history_view.selected;
+        let Some(response) = self
+            .current_history()
+            .get(selected)
+            .map(|entry| entry.response.clone())
+        else {
+            return;
+        };
+        self.last_response = Some(response);
+        self.last_error = None;
+        self.history_view.shown = (selected > 0).then_some(selected);
+        self.history_view.open = false;
+        self.reset_response_view();
+    }
+
+    /// Marks the entry under the cursor as the base of the next diff, or unmarks it.
+    pub fn mark_history_entry(&mut self) {
+        let selected = self.history_view.selected;
+        self.history_view.marked = match self.history_view.marked {
+            Some(marked) if marked == selected => None,
+            _ => Some(selected),
+        };
+    }
+
+    /// Diffs the entry under the cursor against the marked one, or against the entry
+    /// before it when nothing is marked.
+    pub fn diff_history(&mut self) {
+        let view = &self.history_view;
+        let base = view.marked.unwrap_or(view.selected + 1);
+        let other = view.selected;
+        let history = self.current_history();
+        let (Some(base_entry), Some(other_entry)) = (history.get(base), history.get(other)) else {
+            return;
+        };
+        if base == other {
+            return;
+        }
+
+        // Older entry on the left, so additions are what changed since
+        let (old, new) = if base > other {
+            (base_entry, other_entry)
+        } else {
+            (other_entry, base_entry)
+        };
+        let diff = ResponseDiff {
+            title: format!(
+                "{} ({}) → {} ({})",
+                format_time(old.sent_at),
+                old.response.status,
+                format_time(new.sent_at),
+                new.response.status
+            ),
+            lines: diff_lines(&old.diff_text(), &new.diff_text()),
+            scroll: 0,
+        };
+        self.history_view.diff = Some(diff);
+        self.current_screen = CurrentScreen::ResponseDiff;
+    }
+
+    pub fn scroll_history_diff(&mut self, rows: i32) {
+        if let Some(diff) = &mut self.history_view.diff {
+            let max = diff.lines.len().saturating_sub(1) as i32;
+            diff.scroll = (diff.scroll as i32 + rows).clamp(0, max) as u16;
+        }
+    }
+
+    pub fn close_history_diff(&mut self) {
+        self.history_view.diff = None;
+        self.current_screen = CurrentScreen::Main;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_diff_lines_aligns_changes() {
+        let old = lines("a\nb\nc\nd");
+        let new = lines("a\nc\nx\nd");
+        assert_eq!(
+            diff_lines(&old, &new),
+            [
+                DiffLine::Same("a".to_string()),
+                DiffLine::Removed("b".to_string()),
+                DiffLine::Same("c".to_string()),
+                DiffLine::Added("x".to_string()),
+                DiffLine::Same("d".to_string()),
+            ]
+        );
+        assert!(diff_lines(&old, &old)
+            .iter()
+            .all(|line| !matches!(line, DiffLine::Removed(_) | DiffLine::Added(_))));
+    }
+
+    #[test]
+    fn test_diff_lines_collapses_unchanged_runs() {
+        let old: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+        let mut new = old.clone();
+        new[10] = "ten".to_string();
+
+        let diff = diff_lines(&old, &new);
+        assert_eq!(diff[0], DiffLine::Skipped(7));
+        assert_eq!(diff[1], DiffLine::Same("7".to_string()));
+        assert_eq!(diff[4], DiffLine::Removed("10".to_string()));
+        assert_eq!(diff[5], DiffLine::Added("ten".to_string()));
+        assert_eq!(diff[8], DiffLine::Same("13".to_string()));
+        assert_eq!(diff[9], DiffLine::Skipped(6));
+        assert_eq!(diff.len(), 10);
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(3 * 1_048_576), "3.0 MB");
+    }
+}
@@ -0,0 +1,277 @@
+use base64::prelude::*;
+use openssl::error::ErrorStack;
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+use std::time::{SystemTime, UNIX_EPOCH};
+use url::Url;
+
+use super::error::{ErrorKind, RequestError};
+use super::models::{HmacAlgorithm, HmacAuth, RequestType, SignatureEncoding};
+use super::oauth::random_token;
+use super::requests::PreparedRequest;
+
+/// HMAC of `data` under `key`, shared with AWS signing.
+pub fn hmac(digest: MessageDigest, key: &[u8], data: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+    let key = PKey::hmac(key)?;
+    let mut signer = Signer::new(digest, &key)?;
+    signer.update(data)?;
+    signer.sign_to_vec()
+}
+
+/// `bytes` as lowercase hex.
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+impl HmacAlgorithm {
+    fn digest(self) -> MessageDigest {
+        match self {
+            HmacAlgorithm::Sha1 => MessageDigest::sha1(),
+            HmacAlgorithm::Sha256 => MessageDigest::sha256(),
+            HmacAlgorithm::Sha512 => MessageDigest::sha512(),
+        }
+    }
+}
+
+/// What the template placeholders stand for on one send.
+struct Values<'a> {
+    method: &'a str,
+    url: &'a Url,
+    body: &'a str,
+    timestamp: &'a str,
+    nonce: &'a str,
+}
+
+impl Values<'_> {
+    fn get(&self, name: &str) -> Option<String> {
+        let value = match name {
+            "method" => self.method.to_string(),
+            "path" => match self.url.query() {
+                Some(query) => format!("{}?{}", self.url.path(), query),
+                None => self.url.path().to_string(),
+            },
+            "query" => self.url.query().unwrap_or_default().to_string(),
+            "host" => match self.url.port() {
+                Some(port) => format!("{}:{}", self.url.host_str()?, port),
+                None => self.url.host_str()?.to_string(),
+            },
+            "timestamp" => self.timestamp.to_string(),
+            "nonce" => self.nonce.to_string(),
+            "body" => self.body.to_string(),
+            "body_sha256" => hex(&openssl::sha::sha256(self.body.as_bytes())),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+/// `template` with its placeholders filled in and `\n`, `\t` and `\\` unescaped. Unknown
+/// placeholders are kept as written.
+fn signed_string(template: &str, values: &Values) -> String {
+    let mut signed = String::new();
+    let mut rest = template;
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '\\' => {
+                let (unescaped, len) = match rest[1..].chars().next() {
+                    Some('n') => ('\n', 2),
+                    Some('t') => ('\t', 2),
+                    Some('\\') => ('\\', 2),
+                    _ => ('\\', 1),
+                };
+                signed.push(unescaped);
+                rest = &rest[len..];
+            }
+            '{' => {
+                let value = rest
+                    .find('}')
+                    .and_then(|end| Some((values.get(&rest[1..end])?, end)));
+                match value {
+                    Some((value, end)) => {
+                        signed.push_str(&value);
+                        rest = &rest[end + 1..];
+                    }
+                    None => {
+                        signed.push('{');
+                        rest = &rest[1..];
+                    }
+                }
+            }
+            c => {
+                signed.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    signed
+}
+
+/// The encoded signature of `signed` under `auth`'s secret.
+fn signature(auth: &HmacAuth, signed: &str) -> Result<String, RequestError> {
+    let mac = hmac(
+        auth.algorithm.digest(),
+        auth.secret.as_bytes(),
+        signed.as_bytes(),
+    )
+    .map_err(|e| RequestError::new(ErrorKind::Internal, format!("HMAC signing failed: {}", e)))?;
+    Ok(match auth.encoding {
+        SignatureEncoding::Hex => hex(&mac),
+        SignatureEncoding::Base64 => BASE64_STANDARD.encode(mac),
+    })
+}
+
+/// Signs `request` as of `now` with a fresh nonce, appending the timestamp, nonce and
+/// signature headers after every other header.
+pub fn sign(
+    request: &mut PreparedRequest,
+    auth: &HmacAuth,
+    now: SystemTime,
+) -> Result<(), RequestError> {
+    let mut url = Url::parse(&request.url).map_err(|e| {
+        RequestError::new(
+            ErrorKind::InvalidUrl,
+            format!("Cannot sign {}: {}", request.url, e),
+        )
+    })?;
+    if !request.params.is_empty() {
+        url.query_pairs_mut().extend_pairs(&request.params);
+    }
+
+    let timestamp = now
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+        .to_string();
+    let nonce = random_token(16)?;
+    let values = Values {
+        method: request.request_type.as_str(),
+        url: &url,
+        // GET requests go without their body, see `send`
+        body: match request.request_type {
+            RequestType::GET => "",
+            _ => &request.body,
+        },
+        timestamp: &timestamp,
+        nonce: &nonce,
+    };
+    let signature = signature(auth, &signed_string(&auth.template, &values))?;
+
+    let added = [
+        (&auth.timestamp_header, timestamp),
+        (&auth.nonce_header, nonce),
+        (&auth.signature_header, signature),
+    ];
+    let added: Vec<(String, String)> = added
+        .into_iter()
+        .filter(|(name, _)| !name.is_empty())
+        .map(|(name, value)| (name.clone(), value))
+        .collect();
+    request
+        .headers
+        .retain(|(key, _)| !added.iter().any(|(name, _)| name.eq_ignore_ascii_case(key)));
+    request.headers.extend(added);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::models::TlsSettings;
+    use crate::app::proxy::ProxyConfig;
+    use std::time::Duration;
+
+    fn jefe(algorithm: HmacAlgorithm, encoding: SignatureEncoding) -> String {
+        let auth = HmacAuth {
+            secret: "Jefe".to_string(),
+            algorithm,
+            encoding,
+            ..HmacAuth::default()
+        };
+        signature(&auth, "what do ya want for nothing?").unwrap()
+    }
+
+    #[test]
+    fn test_rfc_4231_and_2202_vectors() {
+        assert_eq!(
+            jefe(HmacAlgorithm::Sha1, SignatureEncoding::Hex),
+            "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79"
+        );
+        assert_eq!(
+            jefe(HmacAlgorithm::Sha256, SignatureEncoding::Hex),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            jefe(HmacAlgorithm::Sha256, SignatureEncoding::Base64),
+            "W9zBRr9gdU5qBCQmCJV1x1oAPwidJzmDnexYuWTsOEM="
+        );
+        assert_eq!(
+            jefe(HmacAlgorithm::Sha512, SignatureEncoding::Hex),
+            "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554\
+             9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737"
+        );
+    }
+
+    #[test]
+    fn test_template_placeholders() {
+        let url = Url::parse("http://localhost:8080/hooks?id=7").unwrap();
+        let values = Values {
+            method: "POST",
+            url: &url,
+            body: "",
+            timestamp: "1700000000",
+            nonce: "abc",
+        };
+        assert_eq!(
+            signed_string(&HmacAuth::default().template, &values),
+            "POST\n/hooks?id=7\n1700000000\n\
+             e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            signed_string(r"{host}|{query}|{nonce}|{unknown}|{|\\n", &values),
+            r"localhost:8080|id=7|abc|{unknown}|{|\n"
+        );
+    }
+
+    #[test]
+    fn test_sign_appends_headers_last() {
+        let mut request = PreparedRequest {
+            request_type: RequestType::POST,
+            url: "https://partner.example.com/webhook".to_string(),
+            body: r#"{"event":"ping"}"#.to_string(),
+            headers: vec![
+                ("x-signature".to_string(), "stale".to_string()),
+                ("Content-Type".to_string(), "application/json".to_string()),
+            ],
+            params: Vec::new(),
+            oauth2: None,
+            digest: None,
+            aws_sigv4: None,
+            hmac: None,
+            tls: TlsSettings::default(),
+            proxy: ProxyConfig::default(),
+        };
+        let auth = HmacAuth {
+            secret: "s3cret".to_string(),
+            template: "{timestamp}.{body}".to_string(),
+            nonce_header: "X-Nonce".to_string(),
+            ..HmacAuth::default()
+        };
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        sign(&mut request, &auth, now).unwrap();
+
+        let names: Vec<&str> = request
+            .headers
+            .iter()
+            .map(|(key, _)| key.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            ["Content-Type", "X-Timestamp", "X-Nonce", "X-Signature"]
+        );
+        assert_eq!(request.headers[1].1, "1700000000");
+        assert_eq!(
+            request.headers[3].1,
+            signature(&auth, r#"1700000000.{"event":"ping"}"#).unwrap()
+        );
+    }
+}
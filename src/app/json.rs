@@ -0,0 +1,242 @@
+use serde::de::IgnoredAny;
+
+/// How a response body is displayed, worked out once when the response arrives.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FormattedBody {
+    /// Not JSON, shown as is.
+    Raw,
+    /// JSON, re-indented.
+    Json(String),
+    /// Declared as JSON but doesn't parse. Holds the parse error, the body is shown as is.
+    Invalid(String),
+}
+
+impl FormattedBody {
+    pub fn new(headers: &[(String, String)], body: &str) -> Self {
+        if !is_json_content_type(headers) {
+            return FormattedBody::Raw;
+        }
+        match pretty_print(body) {
+            Ok(pretty) => FormattedBody::Json(pretty),
+            Err(e) => FormattedBody::Invalid(e.to_string()),
+        }
+    }
+}
+
+/// True when the `Content-Type` header is `application/json` or a `+json` type such as
+/// `application/problem+json`.
+pub fn is_json_content_type(headers: &[(String, String)]) -> bool {
+    headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+        .filter_map(|(_, value)| value.parse::<mime::Mime>().ok())
+        .any(|mime| mime.subtype() == mime::JSON || mime.suffix() == Some(mime::JSON))
+}
+
+/// Re-indents a JSON document with two spaces. Unlike a round trip through
+/// `serde_json::Value`, keys keep their order and numbers keep their exact text.
+pub fn pretty_print(text: &str) -> Result<String, serde_json::Error> {
+    serde_json::from_str::<IgnoredAny>(text)?;
+
+    let mut output = String::with_capacity(text.len() * 2);
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = text.chars().peekable();
+
+    let newline = |output: &mut String, depth: usize| {
+        output.push('\n');
+        output.push_str(&"  ".repeat(depth));
+    };
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            output.push(c);
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_string = true;
+                output.push(c);
+            }
+            '{' | '[' => {
+                output.push(c);
+                // Keep empty objects and arrays on one line
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+                if matches!(chars.peek(), Some('}' | ']')) {
+                    output.push(chars.next().unwrap());
+                } else {
+                    depth += 1;
+                    newline(&mut output, depth);
+                }
+            }
+            '}' | ']' => {
+                depth -= 1;
+                newline(&mut output, depth);
+                output.push(c);
+            }
+            ',' => {
+                output.push(c);
+                newline(&mut output, depth);
+            }
+            ':' => output.push_str(": "),
+            c if c.is_whitespace() => {}
+            c => output.push(c),
+        }
+    }
+
+    Ok(output)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JsonToken {
+    Key,
+    String,
+    Number,
+    Boolean,
+    Null,
+    /// Brackets, commas, colons and whitespace.
+    Punctuation,
+}
+
+/// Splits one line of `pretty_print` output into tokens for highlighting. Strings in JSON
+/// can't span lines, so lines can be tokenized on their own.
+pub fn tokenize_line(line: &str) -> Vec<(JsonToken, &str)> {
+    let mut tokens = Vec::new();
+    let bytes = line.as_bytes();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let start = pos;
+        let token = match bytes[pos] {
+            b'"' => {
+                pos += 1;
+                while pos < bytes.len() && bytes[pos] != b'"' {
+                    pos += if bytes[pos] == b'\\' { 2 } else { 1 };
+                }
+                pos = (pos + 1).min(bytes.len());
+                // A string followed by a colon is an object key
+                if line[pos..].trim_start().starts_with(':') {
+                    JsonToken::Key
+                } else {
+                    JsonToken::String
+                }
+            }
+            b'-' | b'0'..=b'9' => {
+                while pos < bytes.len()
+                    && matches!(bytes[pos], b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+                {
+                    pos += 1;
+                }
+                JsonToken::Number
+            }
+            b't' | b'f' | b'n' => {
+                while pos < bytes.len() && bytes[pos].is_ascii_alphabetic() {
+                    pos += 1;
+                }
+                if &line[start..pos] == "null" {
+                    JsonToken::Null
+                } else {
+                    JsonToken::Boolean
+                }
+            }
+            _ => {
+                while pos < bytes.len()
+                    && !matches!(bytes[pos], b'"' | b'-' | b'0'..=b'9' | b't' | b'f' | b'n')
+                {
+                    pos += 1;
+                }
+                JsonToken::Punctuation
+            }
+        };
+        tokens.push((token, &line[start..pos]));
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json_headers() -> Vec<(String, String)> {
+        vec![(
+            "content-type".to_string(),
+            "application/json; charset=utf-8".to_string(),
+        )]
+    }
+
+    #[test]
+    fn test_pretty_print_keeps_order_and_numbers() {
+        let pretty =
+            pretty_print(r#"{"z":1.50,"a":[true,null,{}],"s":"a,\"b\":[c]","e":[ ]}"#).unwrap();
+        assert_eq!(
+            pretty,
+            "{\n  \"z\": 1.50,\n  \"a\": [\n    true,\n    null,\n    {}\n  ],\n  \
+             \"s\": \"a,\\\"b\\\":[c]\",\n  \"e\": []\n}"
+        );
+        assert!(pretty_print("{\"a\":").is_err());
+    }
+
+    #[test]
+    fn test_content_type_detection() {
+        let body = r#"{"ok":true}"#;
+        assert!(matches!(
+            FormattedBody::new(&json_headers(), body),
+            FormattedBody::Json(_)
+        ));
+
+        let problem = vec![(
+            "Content-Type".to_string(),
+            "application/problem+json".to_string(),
+        )];
+        assert!(is_json_content_type(&problem));
+
+        let html = vec![("Content-Type".to_string(), "text/html".to_string())];
+        assert_eq!(FormattedBody::new(&html, body), FormattedBody::Raw);
+
+        assert!(matches!(
+            FormattedBody::new(&json_headers(), "<html>oops</html>"),
+            FormattedBody::Invalid(_)
+        ));
+    }
+
+    #[test]
+    fn test_tokenize_line() {
+        assert_eq!(
+            tokenize_line(r#"  "id": -1.5e3,"#),
+            vec![
+                (JsonToken::Punctuation, "  "),
+                (JsonToken::Key, r#""id""#),
+                (JsonToken::Punctuation, ": "),
+                (JsonToken::Number, "-1.5e3"),
+                (JsonToken::Punctuation, ","),
+            ]
+        );
+        assert_eq!(
+            tokenize_line(r#"  "say \"hi\"": [true, false, null, "x"]"#),
+            vec![
+                (JsonToken::Punctuation, "  "),
+                (JsonToken::Key, r#""say \"hi\"""#),
+                (JsonToken::Punctuation, ": ["),
+                (JsonToken::Boolean, "true"),
+                (JsonToken::Punctuation, ", "),
+                (JsonToken::Boolean, "false"),
+                (JsonToken::Punctuation, ", "),
+                (JsonToken::Null, "null"),
+                (JsonToken::Punctuation, ", "),
+                (JsonToken::String, r#""x""#),
+                (JsonToken::Punctuation, "]"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,309 @@
+use serde_json::Value;
+use std::error::Error;
+use std::fmt;
+
+/// One step of a path expression.
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    /// `.name` or `['name']`
+    Key(String),
+    /// `[2]`, negative indexes count from the end
+    Index(i64),
+    /// `[1:3]`, either bound may be left out
+    Slice(Option<i64>, Option<i64>),
+    /// `.*`, `[*]` or jq's `[]`
+    Wildcard,
+    /// `..`, the following segment applies to the value and everything below it
+    Descendants,
+    /// `[0,2]` or `['a','b']`
+    Union(Vec<Segment>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FilterError(pub String);
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for FilterError {}
+
+fn filter_error(message: impl Into<String>) -> FilterError {
+    FilterError(message.into())
+}
+
+/// A parsed filter. Accepts JSONPath without filter expressions (`$.data[*].id`,
+/// `$..name`, `$.items[-1]`, `$.a[0:2]`) and the matching jq-style paths (`.data[].id`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+impl JsonPath {
+    pub fn parse(expression: &str) -> Result<Self, FilterError> {
+        let expression = expression.trim();
+        let rest = match expression.strip_prefix('$') {
+            Some(rest) => rest,
+            None if expression.starts_with('.') => expression,
+            None => return Err(filter_error("a filter starts with $ or .")),
+        };
+        // jq writes the root itself as a lone dot
+        let rest = if rest == "." { "" } else { rest };
+
+        let mut segments = Vec::new();
+        let mut chars = rest.char_indices().peekable();
+        while let Some((pos, c)) = chars.next() {
+            match c {
+                '.' => {
+                    if chars.next_if(|&(_, c)| c == '.').is_some() {
+                        segments.push(Segment::Descendants);
+                    }
+                    match chars.peek() {
+                        Some(&(_, '[')) => {}
+                        Some(&(_, '*')) => {
+                            chars.next();
+                            segments.push(Segment::Wildcard);
+                        }
+                        _ => {
+                            let mut name = String::new();
+                            while let Some((_, c)) = chars.next_if(|&(_, c)| c != '.' && c != '[') {
+                                name.push(c);
+                            }
+                            if name.is_empty() {
+                                return Err(filter_error(format!(
+                                    "expected a key after the dot at {}",
+                                    pos + 1
+                                )));
+                            }
+                            segments.push(Segment::Key(name));
+                        }
+                    }
+                }
+                '[' => {
+                    let mut content = String::new();
+                    let mut quote = None;
+                    let mut closed = false;
+                    for (_, c) in chars.by_ref() {
+                        match (quote, c) {
+                            (None, ']') => {
+                                closed = true;
+                                break;
+                            }
+                            (None, '\'' | '"') => quote = Some(c),
+                            (Some(q), c) if c == q && !content.ends_with('\\') => quote = None,
+                            _ => {}
+                        }
+                        content.push(c);
+                    }
+                    if !closed {
+                        return Err(filter_error(format!("unclosed [ at {}", pos + 1)));
+                    }
+                    segments.push(parse_bracket(&content)?);
+                }
+                c => return Err(filter_error(format!("unexpected '{}' at {}", c, pos + 1))),
+            }
+        }
+
+        if segments.last() == Some(&Segment::Descendants) {
+            return Err(filter_error("expected a key after .."));
+        }
+        Ok(Self { segments })
+    }
+
+    /// Every value the path selects, in document order.
+    pub fn select<'a>(&self, root: &'a Value) -> Vec<&'a Value> {
+        let mut current = vec![root];
+        for segment in &self.segments {
+            let mut next = Vec::new();
+            for value in current {
+                match segment {
+                    Segment::Descendants => collect_descendants(value, &mut next),
+                    segment => select_segment(segment, value, &mut next),
+                }
+            }
+            current = next;
+        }
+        current
+    }
+}
+
+/// Parses the inside of `[...]`.
+fn parse_bracket(content: &str) -> Result<Segment, FilterError> {
+    let content = content.trim();
+    if content.is_empty() || content == "*" {
+        return Ok(Segment::Wildcard);
+    }
+
+    let mut items = split_union(content)
+        .into_iter()
+        .map(|item| parse_bracket_item(item.trim()))
+        .collect::<Result<Vec<_>, _>>()?;
+    if items.len() == 1 {
+        Ok(items.remove(0))
+    } else {
+        Ok(Segment::Union(items))
+    }
+}
+
+/// Splits `a,b` at commas outside quotes.
+fn split_union(content: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+    let mut escaped = false;
+    for (idx, c) in content.char_indices() {
+        match (quote, c) {
+            _ if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (None, '\'' | '"') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, ',') => {
+                items.push(&content[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&content[start..]);
+    items
+}
+
+fn parse_bracket_item(item: &str) -> Result<Segment, FilterError> {
+    let quoted = ['\'', '"']
+        .iter()
+        .find_map(|&q| item.strip_prefix(q).and_then(|rest| rest.strip_suffix(q)));
+    if let Some(key) = quoted {
+        let mut unescaped = String::with_capacity(key.len());
+        let mut chars = key.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => unescaped.extend(chars.next()),
+                c => unescaped.push(c),
+            }
+        }
+        return Ok(Segment::Key(unescaped));
+    }
+
+    let number = |text: &str| -> Result<Option<i64>, FilterError> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(None);
+        }
+        text.parse()
+            .map(Some)
+            .map_err(|_| filter_error(format!("'{}' is not an index", text)))
+    };
+    match item.split_once(':') {
+        Some((start, end)) => Ok(Segment::Slice(number(start)?, number(end)?)),
+        None => number(item)?
+            .map(Segment::Index)
+            .ok_or_else(|| filter_error("empty index")),
+    }
+}
+
+/// Resolves a possibly negative index against an array of `len` items.
+fn resolve_index(index: i64, len: usize) -> Option<usize> {
+    let len = len as i64;
+    let index = if index < 0 { len + index } else { index };
+    (0..len).contains(&index).then_some(index as usize)
+}
+
+fn select_segment<'a>(segment: &Segment, value: &'a Value, out: &mut Vec<&'a Value>) {
+    match (segment, value) {
+        (Segment::Key(key), Value::Object(map)) => out.extend(map.get(key)),
+        (Segment::Index(index), Value::Array(items)) => {
+            out.extend(resolve_index(*index, items.len()).map(|idx| &items[idx]));
+        }
+        (Segment::Slice(start, end), Value::Array(items)) => {
+            let len = items.len() as i64;
+            let clamp = |bound: i64| {
+                let bound = if bound < 0 { len + bound } else { bound };
+                bound.clamp(0, len) as usize
+            };
+            let start = start.map_or(0, clamp);
+            let end = end.map_or(items.len(), clamp);
+            if start < end {
+                out.extend(&items[start..end]);
+            }
+        }
+        (Segment::Wildcard, Value::Object(map)) => out.extend(map.values()),
+        (Segment::Wildcard, Value::Array(items)) => out.extend(items),
+        (Segment::Union(segments), value) => {
+            for segment in segments {
+                select_segment(segment, value, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `value` followed by everything below it, depth first.
+fn collect_descendants<'a>(value: &'a Value, out: &mut Vec<&'a Value>) {
+    out.push(value);
+    match value {
+        Value::Object(map) => map
+            .values()
+            .for_each(|child| collect_descendants(child, out)),
+        Value::Array(items) => items
+            .iter()
+            .for_each(|child| collect_descendants(child, out)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn select(expression: &str, value: &Value) -> Vec<Value> {
+        JsonPath::parse(expression)
+            .unwrap()
+            .select(value)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn test_jsonpath_and_jq_paths() {
+        let value = json!({
+            "data": [
+                {"id": 1, "name": "a", "tags": ["x"]},
+                {"id": 2, "name": "b", "tags": []},
+                {"id": 3, "name": "c"}
+            ],
+            "meta": {"content-type": "json", "name": "top"}
+        });
+
+        assert_eq!(
+            select("$.data[*].id", &value),
+            [json!(1), json!(2), json!(3)]
+        );
+        assert_eq!(select(".data[].id", &value), [json!(1), json!(2), json!(3)]);
+        assert_eq!(select("$.data[-1].name", &value), [json!("c")]);
+        assert_eq!(select("$.data[0:2].id", &value), [json!(1), json!(2)]);
+        assert_eq!(select("$.data[0,2].id", &value), [json!(1), json!(3)]);
+        assert_eq!(select("$.meta['content-type']", &value), [json!("json")]);
+        assert_eq!(
+            select("$..name", &value),
+            [json!("a"), json!("b"), json!("c"), json!("top")]
+        );
+        assert_eq!(select("$", &value), [value.clone()]);
+        assert_eq!(select(".", &value), [value.clone()]);
+        assert!(select("$.missing.id", &value).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        for expression in ["data", "$.", "$.data[0", "$.data[x]", "$..", "$ data"] {
+            assert!(
+                JsonPath::parse(expression).is_err(),
+                "{} should not parse",
+                expression
+            );
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use rat_tree_view::{Node, NodeValue, Tree, TreeWidget};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    widgets::Block,
+    Frame,
+};
+use serde_json::Value;
+
+use super::json::FormattedBody;
+use super::state::{App, CurrentScreen};
+
+/// A node of the response explorer: the key followed by the value, or a summary such as
+/// `[120 items]` for arrays and objects.
+#[derive(Default, Clone)]
+pub struct JsonNode {
+    parts: Vec<(String, Option<Style>)>,
+}
+
+impl NodeValue for JsonNode {
+    fn render_parts_iter(&self) -> impl Iterator<Item = (&str, Option<Style>)> {
+        self.parts
+            .iter()
+            .map(|(text, style)| (text.as_str(), *style))
+    }
+}
+
+impl JsonNode {
+    fn new(label: String, value: &Value) -> Self {
+        let summary_style = Some(Style::default().fg(Color::DarkGray));
+        let (text, style) = match value {
+            Value::Object(map) => (count(map.len(), "key", "keys", '{', '}'), summary_style),
+            Value::Array(items) => (count(items.len(), "item", "items", '[', ']'), summary_style),
+            Value::String(_) => (value.to_string(), Some(Style::default().fg(Color::Green))),
+            Value::Number(_) => (value.to_string(), Some(Style::default().fg(Color::Yellow))),
+            Value::Bool(_) => (value.to_string(), Some(Style::default().fg(Color::Magenta))),
+            Value::Null => (value.to_string(), summary_style),
+        };
+
+        Self {
+            parts: vec![
+                (label, Some(Style::default().fg(Color::Cyan))),
+                (": ".to_string(), None),
+                (text, style),
+            ],
+        }
+    }
+}
+
+fn count(len: usize, one: &str, many: &str, open: char, close: char) -> String {
+    format!(
+        "{}{} {}{}",
+        open,
+        len,
+        if len == 1 { one } else { many },
+        close
+    )
+}
+
+/// JSONPath of `key` inside the node at `parent`. Keys that aren't plain identifiers use
+/// the bracket form, e.g. `$['content-type']`.
+fn child_path(parent: &str, key: &str) -> String {
+    let mut chars = key.chars();
+    let is_identifier = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    if is_identifier {
+        format!("{}.{}", parent, key)
+    } else {
+        let escaped = key.replace('\\', "\\\\").replace('\'', "\\'");
+        format!("{}['{}']", parent, escaped)
+    }
+}
+
+fn build_node(path: String, label: String, value: &Value) -> Node<JsonNode> {
+    let mut node = Node::new(path.clone(), JsonNode::new(label, value));
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                node.add_child(build_node(child_path(&path, key), key.clone(), child));
+            }
+        }
+        Value::Array(items) => {
+            for (idx, child) in items.iter().enumerate() {
+                let label = format!("[{}]", idx);
+                node.add_child(build_node(format!("{}[{}]", path, idx), label, child));
+            }
+        }
+        _ => {}
+    }
+    node
+}
+
+/// Builds the explorer tree for a JSON document. Node IDs are the JSONPath of the value,
+/// e.g. `$.data[3].id`.
+pub fn build_json_tree(value: &Value) -> Tree<JsonNode> {
+    Tree::new(build_node("$".to_string(), "$".to_string(), value))
+}
+
+impl App {
+    /// Opens the tree explorer on the last response, if its body is JSON.
+    pub fn open_response_tree(&mut self) {
+        let Some(response) = &self.last_response else {
+            return;
+        };
+        if !matches!(response.formatted_body, FormattedBody::Json(_)) {
+            return;
+        }
+        let Ok(value) = serde_json::from_str::<Value>(&response.body) else {
+            return;
+        };
+
+        let tree = build_json_tree(&value);
+        self.json_tree_state = Default::default();
+        self.json_tree_state.select(&tree, tree.root());
+        self.json_tree_state.open(&tree, tree.root());
+        self.json_tree = Some(tree);
+        self.current_screen = CurrentScreen::ResponseTree;
+    }
+
+    pub fn close_response_tree(&mut self) {
+        self.json_tree = None;
+        self.current_screen = CurrentScreen::Main;
+    }
+
+    pub fn response_tree_next(&mut self) {
+        if let Some(tree) = &self.json_tree {
+            self.json_tree_state.move_down(tree);
+        }
+    }
+
+    pub fn response_tree_previous(&mut self) {
+        if let Some(tree) = &self.json_tree {
+            self.json_tree_state.move_up(tree);
+        }
+    }
+
+    /// Expands or collapses the node under the cursor.
+    pub fn response_tree_toggle(&mut self) {
+        let Some(tree) = &self.json_tree else {
+            return;
+        };
+        if let Some(id) = self.json_tree_state.selected() {
+            if let Some(node) = tree.root().query(&id.to_string()) {
+                if self.json_tree_state.is_open(node) {
+                    self.json_tree_state.close(tree, node);
+                } else {
+                    self.json_tree_state.open(tree, node);
+                }
+            }
+        }
+    }
+
+    /// JSONPath of the node under the cursor.
+    pub fn response_tree_path(&self) -> Option<String> {
+        self.json_tree.as_ref()?;
+        self.json_tree_state.selected().map(|id| id.to_string())
+    }
+
+    /// Copies the JSONPath of the node under the cursor to the clipboard.
+    pub fn copy_response_tree_path(&mut self) {
+        if let Some(path) = self.response_tree_path() {
+            self.clipboard = Some(path);
+        }
+    }
+
+    pub fn render_response_tree(&mut self, frame: &mut Frame, area: Rect) {
+        let Some(tree) = &self.json_tree else {
+            return;
+        };
+        let widget = TreeWidget::new(tree)
+            .block(Block::default())
+            .style(Style::default())
+            .highlight_style(Style::default().fg(Color::Yellow))
+            .highlight_symbol("→ ".to_string());
+
+        frame.render_stateful_widget(widget, area, &mut self.json_tree_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_ids_are_json_paths() {
+        let value: Value =
+            serde_json::from_str(r#"{"data":[{"id":1},{"id":2}],"content-type":"x","it's":null}"#)
+                .unwrap();
+        let tree = build_json_tree(&value);
+        let root = tree.root();
+
+        assert_eq!(root.id(), "$");
+        for path in [
+            "$.data",
+            "$.data[1]",
+            "$.data[1].id",
+            "$['content-type']",
+            "$['it\\'s']",
+        ] {
+            assert!(root.query(&path.to_string()).is_some(), "missing {}", path);
+        }
+
+        // Keys keep the order of the response
+        let children: Vec<_> = root.iter().map(|node| node.id().to_string()).collect();
+        assert_eq!(children, ["$.data", "$['content-type']", "$['it\\'s']"]);
+    }
+
+    #[test]
+    fn test_collapsed_nodes_show_counts() {
+        let value: Value = serde_json::from_str(r#"{"a":[1,2,3],"o":{"k":true}}"#).unwrap();
+        let node = |value: &Value| {
+            JsonNode::new("x".to_string(), value)
+                .render_parts_iter()
+                .map(|(text, _)| text.to_string())
+                .collect::<String>()
+        };
+
+        assert_eq!(node(&value["a"]), "x: [3 items]");
+        assert_eq!(node(&value["o"]), "x: {1 key}");
+        assert_eq!(node(&value["o"]["k"]), "x: true");
+        assert_eq!(node(&Value::String("hi".to_string())), "x: \"hi\"");
+    }
+}
@@ -1,5 +1,24 @@
+pub mod auth;
+pub mod aws;
+pub mod curl;
+pub mod digest;
+pub mod environment;
+pub mod error;
+pub mod history;
+pub mod hmac;
+pub mod json;
+pub mod json_path;
+pub mod json_tree;
 pub mod models;
+pub mod oauth;
+pub mod postman;
+pub mod proxy;
+pub mod query;
 pub mod requests;
+pub mod response_view;
+pub mod settings;
 pub mod state;
+pub mod storage;
+pub mod tls;
 pub mod tree;
 pub mod ui_state;
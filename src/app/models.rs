@@ -1,15 +1,100 @@
+use base64::{prelude::BASE64_STANDARD, Engine};
 use rat_tree_view::NodeValue;
 use ratatui::style::{Color, Style};
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-#[derive(Clone, Debug)]
+use super::query;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ApiRequest {
+    /// Persistent unique ID. Names are only for display and may repeat.
+    #[serde(default = "new_id")]
+    pub id: String,
     pub name: String,
     pub request_type: RequestType,
     pub details: RequestDetails,
+    /// JSONPath applied to the response body, empty to show the whole body.
+    #[serde(default)]
+    pub response_filter: String,
+    /// Combined with the group's, see `TlsSettings::over`.
+    #[serde(default)]
+    pub tls: TlsSettings,
+    /// Replaces the group's unless left to inherit it.
+    #[serde(default)]
+    pub proxy: ProxySettings,
+}
+
+/// The oldest TLS version a connection may use. TLS 1.0 and 1.1 are never offered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum TlsVersion {
+    /// Whatever the TLS library allows, TLS 1.2 and up.
+    #[default]
+    Default,
+    Tls12,
+    Tls13,
+}
+
+/// How connections are secured: a client certificate for mutual TLS, extra CAs to trust
+/// and how strict to be. Set per request and per group.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TlsSettings {
+    /// A PEM file with the certificate chain, and the key unless `client_key` is set, or a
+    /// PKCS#12 archive (`.p12`/`.pfx`).
+    pub client_cert: String,
+    /// A PEM file with the key, when it is kept apart from the certificate.
+    pub client_key: String,
+    /// Unlocks a PKCS#12 archive or an encrypted PEM key.
+    pub client_cert_password: String,
+    /// PEM or DER files with CA certificates, trusted next to the system ones.
+    pub ca_files: Vec<String>,
+    /// Skips certificate and host name checks. Only ever for local testing. `None` leaves
+    /// it to the group, and means no for the group itself.
+    pub accept_invalid_certs: Option<bool>,
+    pub min_version: TlsVersion,
 }
 
-#[derive(Clone, Debug)]
+/// Where the proxy settings of a request come from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum ProxyMode {
+    /// The settings one level up: the group's for a request, the global ones for a group.
+    /// Globally this is the same as `System`.
+    #[default]
+    Inherit,
+    /// `HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY` and `NO_PROXY` from the environment.
+    System,
+    /// No proxy, whatever the environment says.
+    Direct,
+    /// The proxy in `ProxySettings::url`.
+    Custom,
+}
+
+/// Which proxy requests go through. Set globally, per group and per request.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProxySettings {
+    pub mode: ProxyMode,
+    /// An `http://`, `https://`, `socks5://` or `socks5h://` URL, for `Custom`. Without a
+    /// scheme it is taken as an HTTP proxy.
+    pub url: String,
+    pub username: String,
+    pub password: String,
+    /// Hosts to reach directly, comma separated: names (covering their subdomains), IP
+    /// addresses, CIDR ranges or `*`.
+    pub no_proxy: String,
+}
+
+/// Settings that apply to every request in a group.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GroupSettings {
+    pub tls: TlsSettings,
+    pub proxy: ProxySettings,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum RequestType {
     GET,
     POST,
@@ -18,47 +103,226 @@ pub enum RequestType {
     PATCH,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum AuthType {
     None,
     Basic,
+    Bearer,
+    ApiKey,
+    OAuth2,
+    Digest,
+    AwsSigV4,
+    Hmac,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct BasicAuth {
     pub username: String,
     pub password: String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BearerAuth {
+    pub token: String,
+}
+
+/// Where an API key is sent.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum ApiKeyPlacement {
+    #[default]
+    Header,
+    Query,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ApiKeyAuth {
+    /// Header or query param name, e.g. `X-API-Key`.
+    pub key: String,
+    pub value: String,
+    pub placement: ApiKeyPlacement,
+}
+
+/// HTTP Digest credentials (RFC 7616). The challenge comes from the server, so they are
+/// only used when the request is sent.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DigestAuth {
+    pub username: String,
+    pub password: String,
+}
+
+/// AWS Signature Version 4 credentials. Requests are signed just before they are sent, once
+/// every header and param is final.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AwsSigV4Auth {
+    pub access_key: String,
+    pub secret_key: String,
+    /// Only for temporary credentials, e.g. from an assumed role.
+    pub session_token: String,
+    pub region: String,
+    /// The signing name, e.g. `execute-api` for API Gateway or `s3`.
+    pub service: String,
+}
+
+/// The hash an HMAC signature is computed with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum HmacAlgorithm {
+    Sha1,
+    #[default]
+    Sha256,
+    Sha512,
+}
+
+/// How an HMAC signature is written into its header.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum SignatureEncoding {
+    #[default]
+    Hex,
+    Base64,
+}
+
+/// A shared secret signing each request over a string built from `template`, for APIs and
+/// webhooks with their own HMAC scheme. Headers with an empty name are not sent.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HmacAuth {
+    pub secret: String,
+    pub algorithm: HmacAlgorithm,
+    pub encoding: SignatureEncoding,
+    /// The signed string, with placeholders such as `{method}` filled in per send.
+    pub template: String,
+    pub signature_header: String,
+    pub timestamp_header: String,
+    pub nonce_header: String,
+}
+
+impl Default for HmacAuth {
+    fn default() -> Self {
+        Self {
+            secret: String::new(),
+            algorithm: HmacAlgorithm::default(),
+            encoding: SignatureEncoding::default(),
+            // Typed on one line, so line breaks are written as `\n`
+            template: r"{method}\n{path}\n{timestamp}\n{body_sha256}".to_string(),
+            signature_header: "X-Signature".to_string(),
+            timestamp_header: "X-Timestamp".to_string(),
+            nonce_header: String::new(),
+        }
+    }
+}
+
+/// How an OAuth 2.0 access token is obtained.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OAuth2Grant {
+    /// The client's own credentials, for service-to-service calls.
+    #[default]
+    ClientCredentials,
+    /// A user signs in through the browser, see `oauth::Authorization`.
+    AuthorizationCode,
+}
+
+/// Tokens issued by the token endpoint, kept on the request so they survive a restart.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct OAuth2Tokens {
+    pub access_token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    /// Seconds since the Unix epoch, `None` when the server didn't say.
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+/// OAuth 2.0 credentials. Access tokens are fetched when the request is sent and cached by
+/// `TokenCache`, keyed on everything but `tokens`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct OAuth2Auth {
+    #[serde(default)]
+    pub grant: OAuth2Grant,
+    /// Where the browser signs in, for the authorization code grant.
+    #[serde(default)]
+    pub authorize_url: String,
+    pub token_url: String,
+    pub client_id: String,
+    /// Left out of token requests when empty, for public clients.
+    pub client_secret: String,
+    /// Space separated, left out when empty.
+    pub scope: String,
+    /// Required by some providers (e.g. Auth0), left out when empty.
+    pub audience: String,
+    /// Port of the loopback listener the browser is redirected to. Any free port when
+    /// empty, some providers only accept the one that was registered.
+    #[serde(default)]
+    pub redirect_port: String,
+    /// The latest tokens, once there are any.
+    #[serde(default)]
+    pub tokens: Option<OAuth2Tokens>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum AuthDetails {
     None,
     Basic(BasicAuth),
+    Bearer(BearerAuth),
+    ApiKey(ApiKeyAuth),
+    OAuth2(OAuth2Auth),
+    Digest(DigestAuth),
+    AwsSigV4(AwsSigV4Auth),
+    Hmac(HmacAuth),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RequestDetails {
     pub url: String,
     pub body: String,
-    pub params: HashMap<String, String>,
-    pub headers: HashMap<String, String>,
+    pub params: Vec<KeyValue>,
+    pub headers: Vec<KeyValue>,
     pub auth_type: AuthType,
     pub auth_details: AuthDetails,
 }
 
+/// A query param or header. Entries keep their order and keys may repeat, as in
+/// `?tag=a&tag=b`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeyValue {
+    pub key: String,
+    pub value: String,
+    /// Disabled entries are kept but not sent.
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+/// Generates an ID for a group or request. It only contains hex digits and `-`, and is
+/// unique across runs since it starts with the creation time.
+pub fn new_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos());
+    format!("{:x}-{:x}", nanos, COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
+impl KeyValue {
+    pub fn new(key: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            value: value.into(),
+            enabled: true,
+        }
+    }
+}
+
 impl ApiRequest {
     pub fn new(name: String, request_type: RequestType) -> Self {
         Self {
+            id: new_id(),
             name,
             request_type,
-            details: RequestDetails {
-                url: String::new(),
-                body: String::new(),
-                params: HashMap::new(),
-                headers: HashMap::new(),
-                auth_type: AuthType::None,
-                auth_details: AuthDetails::None,
-            },
+            details: RequestDetails::new(),
+            response_filter: String::new(),
+            tls: TlsSettings::default(),
+            proxy: ProxySettings::default(),
         }
     }
 }
@@ -66,16 +330,13 @@ impl ApiRequest {
 impl Default for ApiRequest {
     fn default() -> Self {
         Self {
+            id: new_id(),
             name: String::new(),
             request_type: RequestType::GET,
-            details: RequestDetails {
-                url: String::new(),
-                body: String::new(),
-                params: HashMap::new(),
-                headers: HashMap::new(),
-                auth_type: AuthType::None,
-                auth_details: AuthDetails::None,
-            },
+            details: RequestDetails::new(),
+            response_filter: String::new(),
+            tls: TlsSettings::default(),
+            proxy: ProxySettings::default(),
         }
     }
 }
@@ -126,41 +387,266 @@ impl RequestType {
     }
 }
 
+impl BasicAuth {
+    /// Value for the `Authorization` header, e.g. `Basic dXNlcjpwYXNz`.
+    pub fn authorization_header(&self) -> String {
+        let auth_string = format!("{}:{}", self.username, self.password);
+        format!("Basic {}", BASE64_STANDARD.encode(auth_string.as_bytes()))
+    }
+}
+
 impl AuthType {
+    /// Every auth type, in the order the auth type field cycles through them.
+    pub const ALL: [AuthType; 8] = [
+        AuthType::None,
+        AuthType::Basic,
+        AuthType::Bearer,
+        AuthType::ApiKey,
+        AuthType::OAuth2,
+        AuthType::Digest,
+        AuthType::AwsSigV4,
+        AuthType::Hmac,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             AuthType::None => "None",
             AuthType::Basic => "Basic",
+            AuthType::Bearer => "Bearer Token",
+            AuthType::ApiKey => "API Key",
+            AuthType::OAuth2 => "OAuth 2.0",
+            AuthType::Digest => "Digest",
+            AuthType::AwsSigV4 => "AWS Signature",
+            AuthType::Hmac => "HMAC",
         }
     }
 
+    fn position(&self) -> usize {
+        Self::ALL
+            .iter()
+            .position(|auth_type| auth_type == self)
+            .unwrap_or(0)
+    }
+
     pub fn next(&self) -> Self {
+        Self::ALL[(self.position() + 1) % Self::ALL.len()]
+    }
+
+    pub fn previous(&self) -> Self {
+        Self::ALL[(self.position() + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+
+    /// Empty credentials for this auth type.
+    pub fn default_details(&self) -> AuthDetails {
         match self {
-            AuthType::None => AuthType::Basic,
-            AuthType::Basic => AuthType::None,
+            AuthType::None => AuthDetails::None,
+            AuthType::Basic => AuthDetails::Basic(BasicAuth {
+                username: String::new(),
+                password: String::new(),
+            }),
+            AuthType::Bearer => AuthDetails::Bearer(BearerAuth::default()),
+            AuthType::ApiKey => AuthDetails::ApiKey(ApiKeyAuth::default()),
+            AuthType::OAuth2 => AuthDetails::OAuth2(OAuth2Auth::default()),
+            AuthType::Digest => AuthDetails::Digest(DigestAuth::default()),
+            AuthType::AwsSigV4 => AuthDetails::AwsSigV4(AwsSigV4Auth::default()),
+            AuthType::Hmac => AuthDetails::Hmac(HmacAuth::default()),
         }
     }
+}
 
-    pub fn previous(&self) -> Self {
+impl OAuth2Grant {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OAuth2Grant::ClientCredentials => "Client credentials",
+            OAuth2Grant::AuthorizationCode => "Authorization code",
+        }
+    }
+
+    pub fn toggle(&self) -> Self {
         match self {
-            AuthType::None => AuthType::Basic,
-            AuthType::Basic => AuthType::None,
+            OAuth2Grant::ClientCredentials => OAuth2Grant::AuthorizationCode,
+            OAuth2Grant::AuthorizationCode => OAuth2Grant::ClientCredentials,
         }
     }
 }
 
+impl TlsVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TlsVersion::Default => "Default",
+            TlsVersion::Tls12 => "TLS 1.2",
+            TlsVersion::Tls13 => "TLS 1.3",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            TlsVersion::Default => TlsVersion::Tls12,
+            TlsVersion::Tls12 => TlsVersion::Tls13,
+            TlsVersion::Tls13 => TlsVersion::Default,
+        }
+    }
+}
+
+impl ProxyMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProxyMode::Inherit => "Inherit",
+            ProxyMode::System => "System (environment)",
+            ProxyMode::Direct => "None",
+            ProxyMode::Custom => "Custom",
+        }
+    }
+
+    /// The next mode, leaving out `Inherit` for the global settings, which have nothing to
+    /// inherit from.
+    pub fn next(&self, global: bool) -> Self {
+        match self {
+            ProxyMode::Inherit => ProxyMode::System,
+            ProxyMode::System => ProxyMode::Direct,
+            ProxyMode::Direct => ProxyMode::Custom,
+            ProxyMode::Custom if global => ProxyMode::System,
+            ProxyMode::Custom => ProxyMode::Inherit,
+        }
+    }
+}
+
+impl HmacAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HmacAlgorithm::Sha1 => "SHA-1",
+            HmacAlgorithm::Sha256 => "SHA-256",
+            HmacAlgorithm::Sha512 => "SHA-512",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            HmacAlgorithm::Sha1 => HmacAlgorithm::Sha256,
+            HmacAlgorithm::Sha256 => HmacAlgorithm::Sha512,
+            HmacAlgorithm::Sha512 => HmacAlgorithm::Sha1,
+        }
+    }
+}
+
+impl SignatureEncoding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignatureEncoding::Hex => "Hex",
+            SignatureEncoding::Base64 => "Base64",
+        }
+    }
+
+    pub fn toggle(&self) -> Self {
+        match self {
+            SignatureEncoding::Hex => SignatureEncoding::Base64,
+            SignatureEncoding::Base64 => SignatureEncoding::Hex,
+        }
+    }
+}
+
+impl ApiKeyPlacement {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiKeyPlacement::Header => "Header",
+            ApiKeyPlacement::Query => "Query param",
+        }
+    }
+
+    pub fn toggle(&self) -> Self {
+        match self {
+            ApiKeyPlacement::Header => ApiKeyPlacement::Query,
+            ApiKeyPlacement::Query => ApiKeyPlacement::Header,
+        }
+    }
+}
+
+impl Default for RequestDetails {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl RequestDetails {
     pub fn new() -> Self {
         Self {
             url: String::new(),
             body: String::new(),
-            params: HashMap::new(),
-            headers: HashMap::new(),
+            params: Vec::new(),
+            headers: Vec::new(),
             auth_type: AuthType::None,
             auth_details: AuthDetails::None,
         }
     }
 
+    /// The URL as shown in the URL field: the stored URL with the enabled params encoded
+    /// into its query string.
+    pub fn url_with_query(&self) -> String {
+        query::with_query(&self.url, &self.params)
+    }
+
+    /// Stores a URL typed into the URL field. Its query string replaces the enabled params,
+    /// in order, while disabled params keep their place in the list.
+    pub fn set_url_with_query(&mut self, full_url: &str) {
+        let (url, query) = query::split_query(full_url);
+        self.url = url;
+
+        let mut parsed = query::parse_query(&query).into_iter();
+        let mut params = Vec::with_capacity(self.params.len());
+        for param in self.params.drain(..) {
+            if !param.enabled {
+                params.push(param);
+            } else if let Some((key, value)) = parsed.next() {
+                params.push(KeyValue::new(key, value));
+            }
+        }
+        params.extend(parsed.map(|(key, value)| KeyValue::new(key, value)));
+        self.params = params;
+    }
+
+    /// Value of the first param called `key`.
+    pub fn param(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|param| param.key == key)
+            .map(|param| param.value.as_str())
+    }
+
+    /// Value of the first header called `name`, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|header| header.key.eq_ignore_ascii_case(name))
+            .map(|header| header.value.as_str())
+    }
+
+    /// Replaces the value of the first header called `name` (ignoring case) in place, or
+    /// appends the header if there is none.
+    pub fn set_header(&mut self, name: &str, value: String) {
+        match self
+            .headers
+            .iter_mut()
+            .find(|header| header.key.eq_ignore_ascii_case(name))
+        {
+            Some(header) => header.value = value,
+            None => self.headers.push(KeyValue::new(name, value)),
+        }
+    }
+
+    /// Removes every header called `name`, ignoring case.
+    pub fn remove_header(&mut self, name: &str) {
+        self.headers
+            .retain(|header| !header.key.eq_ignore_ascii_case(name));
+    }
+
+    /// A copy without the disabled params and headers, i.e. what actually gets sent.
+    pub fn enabled_only(&self) -> Self {
+        let mut details = self.clone();
+        details.params.retain(|param| param.enabled);
+        details.headers.retain(|header| header.enabled);
+        details
+    }
+
     pub fn get_basic_auth(&self) -> Option<&BasicAuth> {
         if let AuthDetails::Basic(basic) = &self.auth_details {
             Some(basic)
@@ -0,0 +1,908 @@
+use base64::prelude::*;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use url::Url;
+
+use super::error::{ErrorKind, RequestError};
+use super::models::{AuthDetails, OAuth2Auth, OAuth2Grant, OAuth2Tokens};
+use super::state::App;
+
+/// Tokens this many seconds from expiring are renewed before use rather than risk a 401.
+const EXPIRY_MARGIN: u64 = 30;
+
+/// How long the loopback listener waits for the browser to come back.
+const AUTHORIZATION_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Path on the loopback listener that the browser is redirected to.
+const REDIRECT_PATH: &str = "/callback";
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+impl OAuth2Tokens {
+    pub(super) fn is_fresh(&self) -> bool {
+        match self.expires_at {
+            Some(expires_at) => unix_now() + EXPIRY_MARGIN < expires_at,
+            // Tokens without a lifetime are used until they are rejected
+            None => true,
+        }
+    }
+}
+
+/// `auth` without its saved tokens, which change while the credentials stay the same.
+fn cache_key(auth: &OAuth2Auth) -> OAuth2Auth {
+    OAuth2Auth {
+        tokens: None,
+        ..auth.clone()
+    }
+}
+
+/// Access tokens by the credentials they were issued for, shared by every request the
+/// sender runs. Tokens saved on a request stand in until the cache has its own.
+#[derive(Clone, Default)]
+pub struct TokenCache {
+    tokens: Arc<Mutex<HashMap<OAuth2Auth, OAuth2Tokens>>>,
+}
+
+impl TokenCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<OAuth2Auth, OAuth2Tokens>> {
+        self.tokens.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The latest tokens for `auth`, from the cache or else saved on the request.
+    pub fn get(&self, auth: &OAuth2Auth) -> Option<OAuth2Tokens> {
+        let cached = self.lock().get(&cache_key(auth)).cloned();
+        cached.or_else(|| auth.tokens.clone())
+    }
+
+    pub fn insert(&self, auth: &OAuth2Auth, tokens: OAuth2Tokens) {
+        self.lock().insert(cache_key(auth), tokens);
+    }
+
+    /// Returns an access token for `auth`, the current one while it is fresh. Stale tokens
+    /// are renewed with their refresh token if they came with one, or else fetched again
+    /// with the client credentials. The authorization code grant needs the user to sign in
+    /// again instead. `force` renews even a fresh token, e.g. after a 401.
+    pub async fn access_token(
+        &self,
+        client: &reqwest::Client,
+        auth: &OAuth2Auth,
+        force: bool,
+    ) -> Result<String, RequestError> {
+        let current = self.get(auth);
+        if let Some(tokens) = &current {
+            if !force && tokens.is_fresh() {
+                return Ok(tokens.access_token.clone());
+            }
+        }
+
+        let signed_in = current.is_some();
+        let refresh_token = current.and_then(|tokens| tokens.refresh_token);
+        let refreshed = match &refresh_token {
+            // A rejected refresh token has usually just expired as well
+            Some(refresh_token) => fetch_token(
+                client,
+                auth,
+                &[
+                    ("grant_type", "refresh_token"),
+                    ("refresh_token", refresh_token.as_str()),
+                ],
+            )
+            .await
+            .ok(),
+            None => None,
+        };
+        let mut tokens = match (refreshed, auth.grant) {
+            (Some(tokens), _) => tokens,
+            (None, OAuth2Grant::ClientCredentials) => {
+                let mut grant = vec![("grant_type", "client_credentials")];
+                if !auth.scope.is_empty() {
+                    grant.push(("scope", auth.scope.as_str()));
+                }
+                if !auth.audience.is_empty() {
+                    grant.push(("audience", auth.audience.as_str()));
+                }
+                fetch_token(client, auth, &grant).await?
+            }
+            (None, OAuth2Grant::AuthorizationCode) => {
+                let message = if signed_in {
+                    "The access token could not be renewed, press Ctrl+O to sign in again"
+                } else {
+                    "Not signed in yet, press Ctrl+O to sign in"
+                };
+                return Err(RequestError::new(ErrorKind::Auth, message));
+            }
+        };
+
+        // Servers may keep the refresh token as it is and leave it out of the response
+        if tokens.refresh_token.is_none() {
+            tokens.refresh_token = refresh_token;
+        }
+        let access_token = tokens.access_token.clone();
+        self.insert(auth, tokens);
+        Ok(access_token)
+    }
+}
+
+fn token_error(message: impl Into<String>, causes: Vec<String>, start: Instant) -> RequestError {
+    let mut error = RequestError::new(ErrorKind::Auth, message).with_elapsed(start.elapsed());
+    error.causes = causes;
+    error
+}
+
+/// Posts `grant` with the client credentials to the token endpoint, as a form.
+async fn fetch_token(
+    client: &reqwest::Client,
+    auth: &OAuth2Auth,
+    grant: &[(&str, &str)],
+) -> Result<OAuth2Tokens, RequestError> {
+    let start = Instant::now();
+    if auth.token_url.is_empty() {
+        return Err(token_error("No token URL set", Vec::new(), start));
+    }
+
+    let mut form = grant.to_vec();
+    form.push(("client_id", auth.client_id.as_str()));
+    if !auth.client_secret.is_empty() {
+        form.push(("client_secret", auth.client_secret.as_str()));
+    }
+
+    let fail = |e: reqwest::Error| {
+        let error = RequestError::from_reqwest(&e, start.elapsed());
+        let mut causes = vec![error.to_string()];
+        causes.extend(error.causes);
+        token_error(
+            format!("Token request to {} failed", auth.token_url),
+            causes,
+            start,
+        )
+    };
+    let response = client
+        .post(&auth.token_url)
+        .header("Accept", "application/json")
+        .form(&form)
+        .send()
+        .await
+        .map_err(fail)?;
+    let status = response.status();
+    let body = response.text().await.map_err(fail)?;
+    let json: Option<Value> = serde_json::from_str(&body).ok();
+    let field = |name: &str| {
+        json.as_ref()
+            .and_then(|json| json.get(name))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+
+    if !status.is_success() {
+        // RFC 6749 error responses carry a code and maybe a description
+        let detail = match (field("error"), field("error_description")) {
+            (Some(error), Some(description)) => format!("{}: {}", error, description),
+            (Some(error), None) => error,
+            _ => body.trim().to_string(),
+        };
+        let causes = if detail.is_empty() {
+            Vec::new()
+        } else {
+            vec![detail]
+        };
+        return Err(token_error(
+            format!("Token endpoint answered {}", status),
+            causes,
+            start,
+        ));
+    }
+
+    let Some(access_token) = field("access_token") else {
+        return Err(token_error(
+            "Token response has no access_token",
+            vec![body.trim().to_string()],
+            start,
+        ));
+    };
+    // Some servers send the lifetime as a string
+    let expires_in = json
+        .as_ref()
+        .and_then(|json| json.get("expires_in"))
+        .and_then(|value| value.as_u64().or_else(|| value.as_str()?.parse().ok()));
+
+    Ok(OAuth2Tokens {
+        access_token,
+        refresh_token: field("refresh_token"),
+        expires_at: expires_in.map(|seconds| unix_now() + seconds),
+    })
+}
+
+/// `len` random bytes as URL safe base64, for the PKCE verifier, the state and Digest
+/// client nonces.
+pub fn random_token(len: usize) -> Result<String, RequestError> {
+    let mut bytes = vec![0; len];
+    openssl::rand::rand_bytes(&mut bytes).map_err(|e| {
+        RequestError::new(
+            ErrorKind::Internal,
+            format!("No random numbers available: {}", e),
+        )
+    })?;
+    Ok(BASE64_URL_SAFE_NO_PAD.encode(bytes))
+}
+
+/// An authorization code sign-in in progress: the loopback listener the browser is sent
+/// back to (RFC 8252) and the PKCE verifier the code is exchanged with (RFC 7636).
+pub struct Authorization {
+    /// Where the user signs in, opened in the browser.
+    pub authorize_url: String,
+    auth: OAuth2Auth,
+    listener: TcpListener,
+    redirect_uri: String,
+    state: String,
+    verifier: String,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Authorization {
+    /// Starts listening on the loopback interface and builds the authorize URL that sends
+    /// the browser back to it.
+    pub fn start(auth: &OAuth2Auth) -> Result<Self, RequestError> {
+        let error = |message: String, cause: String| {
+            let mut error = RequestError::new(ErrorKind::Auth, message);
+            error.causes.push(cause);
+            error
+        };
+
+        let mut authorize_url = Url::parse(&auth.authorize_url).map_err(|e| {
+            error(
+                format!("Invalid authorize URL '{}'", auth.authorize_url),
+                e.to_string(),
+            )
+        })?;
+        let port = match auth.redirect_port.trim() {
+            "" => 0,
+            port => port
+                .parse::<u16>()
+                .map_err(|e| error(format!("Invalid redirect port '{}'", port), e.to_string()))?,
+        };
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .and_then(|listener| {
+                // Polled, so that waiting can be cancelled
+                listener.set_nonblocking(true)?;
+                Ok(listener)
+            })
+            .map_err(|e| error(format!("Could not listen on port {}", port), e.to_string()))?;
+        let port = listener.local_addr().map_or(port, |addr| addr.port());
+        let redirect_uri = format!("http://127.0.0.1:{}{}", port, REDIRECT_PATH);
+
+        let verifier = random_token(32)?;
+        let state = random_token(16)?;
+        let challenge = BASE64_URL_SAFE_NO_PAD.encode(openssl::sha::sha256(verifier.as_bytes()));
+
+        {
+            let mut query = authorize_url.query_pairs_mut();
+            query
+                .append_pair("response_type", "code")
+                .append_pair("client_id", &auth.client_id)
+                .append_pair("redirect_uri", &redirect_uri)
+                .append_pair("code_challenge", &challenge)
+                .append_pair("code_challenge_method", "S256")
+                .append_pair("state", &state);
+            if !auth.scope.is_empty() {
+                query.append_pair("scope", &auth.scope);
+            }
+            if !auth.audience.is_empty() {
+                query.append_pair("audience", &auth.audience);
+            }
+        }
+
+        Ok(Self {
+            authorize_url: authorize_url.to_string(),
+            auth: auth.clone(),
+            listener,
+            redirect_uri,
+            state,
+            verifier,
+            cancelled: Arc::default(),
+        })
+    }
+
+    /// Setting this stops `finish` from waiting for the browser.
+    pub fn cancel_flag(&self) -> Arc<AtomicBool> {
+        self.cancelled.clone()
+    }
+
+    /// Waits for the browser to come back with a code, exchanges it for tokens and caches
+    /// them in `tokens`.
+    pub async fn finish(
+        self,
+        client: &reqwest::Client,
+        tokens: &TokenCache,
+    ) -> Result<OAuth2Tokens, RequestError> {
+        // Waiting sleeps, so it is kept off the runtime's worker threads
+        let (authorization, code) = tokio::task::spawn_blocking(move || {
+            let code = self.wait_for_code();
+            (self, code)
+        })
+        .await
+        .map_err(|e| RequestError::new(ErrorKind::Internal, e.to_string()))?;
+
+        let issued = fetch_token(
+            client,
+            &authorization.auth,
+            &[
+                ("grant_type", "authorization_code"),
+                ("code", code?.as_str()),
+                ("redirect_uri", authorization.redirect_uri.as_str()),
+                ("code_verifier", authorization.verifier.as_str()),
+            ],
+        )
+        .await?;
+        tokens.insert(&authorization.auth, issued.clone());
+        Ok(issued)
+    }
+
+    fn wait_for_code(&self) -> Result<String, RequestError> {
+        let deadline = Instant::now() + AUTHORIZATION_TIMEOUT;
+        loop {
+            if self.cancelled.load(Ordering::Relaxed) {
+                return Err(RequestError::new(ErrorKind::Cancelled, "Sign-in cancelled"));
+            }
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    if let Some(result) = self.handle_redirect(stream) {
+                        return result;
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    if Instant::now() > deadline {
+                        return Err(RequestError::new(
+                            ErrorKind::Auth,
+                            "Timed out waiting for the sign-in in the browser",
+                        ));
+                    }
+                    thread::sleep(Duration::from_millis(100));
+                }
+                Err(e) => {
+                    return Err(RequestError::new(
+                        ErrorKind::Auth,
+                        format!("The redirect listener failed: {}", e),
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Reads the redirect and answers the browser. `None` for requests that aren't the
+    /// redirect of this sign-in, like the favicon or one with the wrong state.
+    fn handle_redirect(&self, mut stream: TcpStream) -> Option<Result<String, RequestError>> {
+        // Accepted sockets inherit non-blocking mode on some platforms
+        let _ = stream.set_nonblocking(false);
+        let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+        let mut request_line = String::new();
+        BufReader::new(&stream).read_line(&mut request_line).ok()?;
+
+        // e.g. "GET /callback?code=...&state=... HTTP/1.1"
+        let target = request_line.split_whitespace().nth(1)?;
+        let url = Url::parse(&format!("http://127.0.0.1{}", target)).ok()?;
+        if url.path() != REDIRECT_PATH {
+            let _ = stream.write_all(
+                b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            );
+            return None;
+        }
+
+        let param = |name: &str| {
+            url.query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned())
+        };
+        if param("state").as_deref() != Some(self.state.as_str()) {
+            // Not the sign-in that was started here, e.g. an old tab or a forged link. The
+            // real one may still come back.
+            let page = "Unknown sign-in, start it again from ratquest.";
+            let _ = write!(
+                stream,
+                "HTTP/1.1 400 Bad Request\r\nContent-Type: text/plain; charset=utf-8\r\n\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                page.len(),
+                page
+            );
+            return None;
+        }
+
+        let result = if let Some(error) = param("error") {
+            let mut error =
+                RequestError::new(ErrorKind::Auth, format!("Sign-in failed: {}", error));
+            error.causes.extend(param("error_description"));
+            Err(error)
+        } else {
+            param("code").ok_or_else(|| {
+                RequestError::new(ErrorKind::Auth, "The sign-in came back without a code")
+            })
+        };
+
+        let page = match &result {
+            Ok(_) => "Signed in, you can close this tab and go back to ratquest.",
+            Err(_) => "Sign-in failed, ratquest shows the details.",
+        };
+        let _ = write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            page.len(),
+            page
+        );
+        Some(result)
+    }
+}
+
+/// A sign-in waiting for the browser, see `App::start_authorization`.
+pub struct PendingAuthorization {
+    pub request_id: String,
+    pub authorize_url: String,
+}
+
+impl App {
+    /// Starts the sign-in for the selected request if it uses the authorization code
+    /// grant. The authorize URL is opened in the browser and copied to the clipboard, and
+    /// the tokens are saved on the request once the browser comes back.
+    pub fn start_authorization(&mut self) {
+        let Some(prepared) = self.prepare_request() else {
+            return;
+        };
+        let Some(request_id) = self
+            .get_selected_request()
+            .map(|request| request.id.clone())
+        else {
+            return;
+        };
+        let auth = match prepared.oauth2 {
+            Some(auth) if auth.grant == OAuth2Grant::AuthorizationCode => auth,
+            _ => return,
+        };
+
+        let started = Authorization::start(&auth).and_then(|authorization| {
+            let authorize_url = authorization.authorize_url.clone();
+            self.sender
+                .authorize(authorization, prepared.tls, prepared.proxy)
+                .map_err(|e| {
+                    RequestError::new(
+                        ErrorKind::Internal,
+                        format!("Could not start the request runtime: {}", e),
+                    )
+                })?;
+            Ok(authorize_url)
+        });
+        match started {
+            Ok(authorize_url) => {
+                self.clipboard = Some(authorize_url.clone());
+                self.browser_url = Some(authorize_url.clone());
+                self.authorization = Some(PendingAuthorization {
+                    request_id,
+                    authorize_url,
+                });
+            }
+            Err(e) => {
+                self.last_response = None;
+                self.last_error = Some(e);
+            }
+        }
+    }
+
+    pub fn is_authorizing(&self) -> bool {
+        self.authorization.is_some()
+    }
+
+    /// Saves the tokens of the sign-in once it has finished. Returns true if it did.
+    pub fn poll_authorization(&mut self) -> bool {
+        let Some(result) = self.sender.try_recv_authorization() else {
+            return false;
+        };
+        let Some(pending) = self.authorization.take() else {
+            return true;
+        };
+
+        let is_current = self.is_current_request(&pending.request_id);
+        match result {
+            Ok(tokens) => {
+                self.save_oauth2_tokens(&pending.request_id, tokens);
+                if is_current {
+                    self.last_error = None;
+                }
+            }
+            Err(e) => {
+                if is_current {
+                    self.last_response = None;
+                    self.last_error = Some(e);
+                }
+            }
+        }
+        true
+    }
+
+    pub fn cancel_authorization(&mut self) {
+        self.sender.cancel_authorization();
+        self.authorization = None;
+    }
+
+    /// The authorize URL while the current request is waiting for its sign-in.
+    pub fn current_authorize_url(&self) -> Option<&str> {
+        self.authorization
+            .as_ref()
+            .filter(|pending| self.is_current_request(&pending.request_id))
+            .map(|pending| pending.authorize_url.as_str())
+    }
+
+    /// For a current request with the authorization code grant, whether it has tokens.
+    pub fn is_signed_in(&self) -> Option<bool> {
+        match &self.get_current_request()?.details.auth_details {
+            AuthDetails::OAuth2(oauth2) if oauth2.grant == OAuth2Grant::AuthorizationCode => {
+                Some(oauth2.tokens.is_some())
+            }
+            _ => None,
+        }
+    }
+
+    /// Saves the tokens the sender holds for `auth` on the request, so they are kept with
+    /// the workspace.
+    pub fn sync_oauth2_tokens(&mut self, request_id: &str, auth: &OAuth2Auth) {
+        if let Some(tokens) = self.sender.auth_cache.tokens.get(auth) {
+            self.save_oauth2_tokens(request_id, tokens);
+        }
+    }
+
+    fn save_oauth2_tokens(&mut self, request_id: &str, tokens: OAuth2Tokens) {
+        let Some((group, index)) = self.find_request(request_id) else {
+            return;
+        };
+        let Some(request) = self
+            .list
+            .get_mut(&group)
+            .and_then(|requests| requests.get_mut(index))
+        else {
+            return;
+        };
+        if let AuthDetails::OAuth2(oauth2) = &mut request.details.auth_details {
+            if oauth2.tokens.as_ref() != Some(&tokens) {
+                oauth2.tokens = Some(tokens);
+                self.persist_workspace();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::auth::AuthCache;
+    use crate::app::models::{RequestType, TlsSettings};
+    use crate::app::proxy::ProxyConfig;
+    use crate::app::requests::{execute, PreparedRequest};
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::mpsc;
+    use std::thread;
+
+    /// Answers one connection with each of `responses` in turn and passes on what was
+    /// sent, standing in for both the token endpoint and the API.
+    fn serve(responses: Vec<String>) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let _ = tx.send(read_request(&mut stream));
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        (base, rx)
+    }
+
+    fn read_request(stream: &mut TcpStream) -> String {
+        let mut raw = Vec::new();
+        let mut buf = [0; 1024];
+        loop {
+            let n = stream.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            raw.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&raw).to_lowercase();
+            if let Some(end) = text.find("\r\n\r\n") {
+                let length = text[..end]
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length:"))
+                    .and_then(|value| value.trim().parse().ok())
+                    .unwrap_or(0);
+                if raw.len() >= end + 4 + length {
+                    break;
+                }
+            }
+        }
+        String::from_utf8_lossy(&raw).to_string()
+    }
+
+    fn reply(status: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+             Connection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        )
+    }
+
+    fn credentials(base: &str) -> OAuth2Auth {
+        OAuth2Auth {
+            token_url: format!("{}/token", base),
+            client_id: "ratquest".to_string(),
+            client_secret: "s3cret".to_string(),
+            scope: "read write".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn sign_in_credentials(base: &str) -> OAuth2Auth {
+        OAuth2Auth {
+            grant: OAuth2Grant::AuthorizationCode,
+            authorize_url: format!("{}/authorize?prompt=login", base),
+            token_url: format!("{}/token", base),
+            client_id: "ratquest".to_string(),
+            scope: "openid".to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Plays the browser coming back from the identity provider to `redirect_uri`, after
+    /// a stray favicon request. Returns the page shown for the redirect.
+    fn come_back(redirect_uri: &str, query: &str) -> thread::JoinHandle<String> {
+        let redirect = Url::parse(redirect_uri).unwrap();
+        let addr = format!("127.0.0.1:{}", redirect.port().unwrap());
+        let target = format!("{}?{}", redirect.path(), query);
+        thread::spawn(move || {
+            let mut page = String::new();
+            for target in ["/favicon.ico", target.as_str()] {
+                let mut stream = TcpStream::connect(&addr).unwrap();
+                write!(stream, "GET {} HTTP/1.1\r\nHost: {}\r\n\r\n", target, addr).unwrap();
+                page.clear();
+                stream.read_to_string(&mut page).unwrap();
+            }
+            page
+        })
+    }
+
+    fn query_param(url: &str, name: &str) -> String {
+        Url::parse(url)
+            .unwrap()
+            .query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_token_is_cached_until_it_expires() {
+        let (base, requests) = serve(vec![
+            reply("200 OK", r#"{"access_token":"t1","expires_in":3600}"#),
+            reply("200 OK", r#"{"access_token":"t2","expires_in":3600}"#),
+        ]);
+        let auth = credentials(&base);
+        let cache = TokenCache::new();
+        let client = reqwest::Client::new();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let token = |force| runtime.block_on(cache.access_token(&client, &auth, force));
+
+        assert_eq!(token(false).unwrap(), "t1");
+        let sent = requests.recv().unwrap();
+        assert!(sent.starts_with("POST /token "));
+        assert!(sent.ends_with(
+            "grant_type=client_credentials&scope=read+write&client_id=ratquest\
+             &client_secret=s3cret"
+        ));
+
+        assert_eq!(token(false).unwrap(), "t1");
+        assert!(requests.try_recv().is_err());
+
+        assert_eq!(token(true).unwrap(), "t2");
+    }
+
+    #[test]
+    fn test_stale_token_is_refreshed() {
+        // Expiring within the margin counts as expired already
+        let (base, requests) = serve(vec![
+            reply(
+                "200 OK",
+                r#"{"access_token":"t1","expires_in":"10","refresh_token":"r1"}"#,
+            ),
+            reply("200 OK", r#"{"access_token":"t2","expires_in":3600}"#),
+        ]);
+        let auth = credentials(&base);
+        let cache = TokenCache::new();
+        let client = reqwest::Client::new();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let token = || runtime.block_on(cache.access_token(&client, &auth, false));
+
+        assert_eq!(token().unwrap(), "t1");
+        assert_eq!(token().unwrap(), "t2");
+        let _ = requests.recv().unwrap();
+        assert!(requests
+            .recv()
+            .unwrap()
+            .contains("grant_type=refresh_token&refresh_token=r1&client_id=ratquest"));
+        assert_eq!(token().unwrap(), "t2");
+    }
+
+    #[test]
+    fn test_token_errors() {
+        let (base, _requests) = serve(vec![reply(
+            "401 Unauthorized",
+            r#"{"error":"invalid_client","error_description":"Bad secret"}"#,
+        )]);
+        let cache = TokenCache::new();
+        let client = reqwest::Client::new();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        let error = runtime
+            .block_on(cache.access_token(&client, &credentials(&base), false))
+            .unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Auth);
+        assert_eq!(error.message, "Token endpoint answered 401 Unauthorized");
+        assert_eq!(error.causes, ["invalid_client: Bad secret"]);
+
+        let error = runtime
+            .block_on(cache.access_token(&client, &OAuth2Auth::default(), false))
+            .unwrap_err();
+        assert_eq!(error.message, "No token URL set");
+    }
+
+    #[test]
+    fn test_request_is_retried_with_a_new_token_after_401() {
+        let (base, requests) = serve(vec![
+            reply("200 OK", r#"{"access_token":"t1"}"#),
+            reply("401 Unauthorized", ""),
+            reply("200 OK", r#"{"access_token":"t2"}"#),
+            reply("200 OK", "ok"),
+        ]);
+        let request = PreparedRequest {
+            request_type: RequestType::GET,
+            url: format!("{}/items", base),
+            body: String::new(),
+            headers: Vec::new(),
+            params: Vec::new(),
+            oauth2: Some(credentials(&base)),
+            digest: None,
+            aws_sigv4: None,
+            hmac: None,
+            tls: TlsSettings::default(),
+            proxy: ProxyConfig::default(),
+        };
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        let response = runtime
+            .block_on(execute(request, &AuthCache::default()))
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, "ok");
+
+        let sent: Vec<String> = requests.iter().map(|raw| raw.to_lowercase()).collect();
+        assert!(sent[1].contains("authorization: bearer t1"));
+        assert!(sent[3].starts_with("get /items "));
+        assert!(sent[3].contains("authorization: bearer t2"));
+    }
+
+    #[test]
+    fn test_authorization_code_flow_with_pkce() {
+        let (base, requests) = serve(vec![reply(
+            "200 OK",
+            r#"{"access_token":"a1","refresh_token":"r1","expires_in":3600}"#,
+        )]);
+        let auth = sign_in_credentials(&base);
+        let authorization = Authorization::start(&auth).unwrap();
+        let authorize_url = authorization.authorize_url.clone();
+        assert!(authorize_url.starts_with(&format!("{}/authorize?prompt=login&", base)));
+        assert_eq!(query_param(&authorize_url, "response_type"), "code");
+        assert_eq!(query_param(&authorize_url, "scope"), "openid");
+        assert_eq!(query_param(&authorize_url, "code_challenge_method"), "S256");
+
+        let redirect_uri = query_param(&authorize_url, "redirect_uri");
+        let state = query_param(&authorize_url, "state");
+        let browser = come_back(&redirect_uri, &format!("code=c0de&state={}", state));
+
+        let cache = TokenCache::new();
+        let client = reqwest::Client::new();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let tokens = runtime
+            .block_on(authorization.finish(&client, &cache))
+            .unwrap();
+        assert_eq!(tokens.access_token, "a1");
+        assert_eq!(tokens.refresh_token.as_deref(), Some("r1"));
+        assert!(browser.join().unwrap().contains("Signed in"));
+
+        // The code goes back with the verifier the challenge was made from
+        let sent = requests.recv().unwrap();
+        let body = sent.split("\r\n\r\n").nth(1).unwrap();
+        let form: HashMap<String, String> = url::form_urlencoded::parse(body.as_bytes())
+            .into_owned()
+            .collect();
+        assert_eq!(form["grant_type"], "authorization_code");
+        assert_eq!(form["code"], "c0de");
+        assert_eq!(form["redirect_uri"], redirect_uri);
+        assert!(!form.contains_key("client_secret"));
+        let challenge =
+            BASE64_URL_SAFE_NO_PAD.encode(openssl::sha::sha256(form["code_verifier"].as_bytes()));
+        assert_eq!(challenge, query_param(&authorize_url, "code_challenge"));
+
+        // Later requests use the token without signing in again
+        let token = runtime.block_on(cache.access_token(&client, &auth, false));
+        assert_eq!(token.unwrap(), "a1");
+    }
+
+    #[test]
+    fn test_redirect_with_the_wrong_state_is_ignored() {
+        let (base, _requests) = serve(vec![reply("200 OK", r#"{"access_token":"a1"}"#)]);
+        let authorization = Authorization::start(&sign_in_credentials(&base)).unwrap();
+        let redirect_uri = query_param(&authorization.authorize_url, "redirect_uri");
+        let state = query_param(&authorization.authorize_url, "state");
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let client = reqwest::Client::new();
+        let cache = TokenCache::new();
+        let finished = runtime.spawn(async move { authorization.finish(&client, &cache).await });
+
+        // A forged redirect is turned away and the listener keeps waiting for the real one
+        let forged = come_back(&redirect_uri, "code=evil&state=forged");
+        let page = forged.join().unwrap();
+        assert!(page.starts_with("HTTP/1.1 400"));
+        assert!(!finished.is_finished());
+
+        let browser = come_back(&redirect_uri, &format!("code=c0de&state={}", state));
+        let tokens = runtime.block_on(finished).unwrap().unwrap();
+        assert_eq!(tokens.access_token, "a1");
+        assert!(browser.join().unwrap().contains("Signed in"));
+    }
+
+    #[test]
+    fn test_saved_tokens_are_refreshed() {
+        let (base, requests) = serve(vec![reply(
+            "200 OK",
+            r#"{"access_token":"a2","expires_in":3600}"#,
+        )]);
+        let cache = TokenCache::new();
+        let client = reqwest::Client::new();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+
+        let mut auth = sign_in_credentials(&base);
+        let error = runtime
+            .block_on(cache.access_token(&client, &auth, false))
+            .unwrap_err();
+        assert!(error.message.contains("Ctrl+O"));
+
+        // Tokens saved in the workspace by an earlier run, long expired
+        auth.tokens = Some(OAuth2Tokens {
+            access_token: "a1".to_string(),
+            refresh_token: Some("r1".to_string()),
+            expires_at: Some(0),
+        });
+        let token = runtime.block_on(cache.access_token(&client, &auth, false));
+        assert_eq!(token.unwrap(), "a2");
+        assert!(requests
+            .recv()
+            .unwrap()
+            .ends_with("grant_type=refresh_token&refresh_token=r1&client_id=ratquest"));
+
+        // The refresh token is kept for next time
+        let tokens = cache.get(&auth).unwrap();
+        assert_eq!(tokens.refresh_token.as_deref(), Some("r1"));
+    }
+}
@@ -0,0 +1,618 @@
+use serde_json::Value;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::models::{ApiRequest, AuthDetails, AuthType, BasicAuth, KeyValue, RequestType};
+use super::query;
+
+/// A Postman folder, flattened into a ratquest group. Nested folders become
+/// `Parent / Child`.
+#[derive(Clone, Debug)]
+pub struct ImportedGroup {
+    pub name: String,
+    pub requests: Vec<ApiRequest>,
+}
+
+/// Everything that could be carried over from a collection, plus a note for each construct
+/// that couldn't.
+#[derive(Clone, Debug)]
+pub struct PostmanImport {
+    pub groups: Vec<ImportedGroup>,
+    pub warnings: Vec<String>,
+}
+
+/// What an import added to the workspace, shown in the import popup.
+#[derive(Clone, Debug)]
+pub struct ImportSummary {
+    pub groups: usize,
+    pub requests: usize,
+    pub warnings: Vec<String>,
+}
+
+impl PostmanImport {
+    pub fn request_count(&self) -> usize {
+        self.groups.iter().map(|g| g.requests.len()).sum()
+    }
+}
+
+pub fn import_collection(path: &Path) -> io::Result<PostmanImport> {
+    parse_collection(&fs::read_to_string(path)?)
+}
+
+/// Parses a Postman Collection (v2.1, and v2.0 which differs only in how auth is stored).
+pub fn parse_collection(json: &str) -> io::Result<PostmanImport> {
+    let collection: Value = serde_json::from_str(json)?;
+
+    let info = collection
+        .get("info")
+        .filter(|info| info.get("schema").is_some() || info.get("_postman_id").is_some())
+        .ok_or_else(|| invalid("not a Postman collection: missing \"info\""))?;
+
+    if let Some(schema) = info.get("schema").and_then(Value::as_str) {
+        if !schema.contains("v2.1") && !schema.contains("v2.0") {
+            return Err(invalid(&format!(
+                "unsupported collection schema {}",
+                schema
+            )));
+        }
+    }
+
+    let items = collection
+        .get("item")
+        .and_then(Value::as_array)
+        .ok_or_else(|| invalid("not a Postman collection: missing \"item\""))?;
+
+    let collection_name = info
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or("Imported")
+        .to_string();
+
+    let mut importer = Importer {
+        groups: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    importer.check_scripts(&collection, &collection_name);
+    if collection
+        .get("variable")
+        .and_then(Value::as_array)
+        .is_some_and(|vars| !vars.is_empty())
+    {
+        importer.warn(
+            &collection_name,
+            "collection variables not imported, define them in an environment instead",
+        );
+    }
+
+    importer.walk(
+        items,
+        None,
+        &collection_name,
+        &collection_name,
+        collection.get("auth"),
+    );
+
+    Ok(PostmanImport {
+        groups: importer.groups,
+        warnings: importer.warnings,
+    })
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+struct Importer {
+    groups: Vec<ImportedGroup>,
+    warnings: Vec<String>,
+}
+
+impl Importer {
+    fn warn(&mut self, path: &str, message: &str) {
+        self.warnings.push(format!("{}: {}", path, message));
+    }
+
+    fn group_mut(&mut self, name: &str) -> &mut ImportedGroup {
+        let idx = match self.groups.iter().position(|g| g.name == name) {
+            Some(idx) => idx,
+            None => {
+                self.groups.push(ImportedGroup {
+                    name: name.to_string(),
+                    requests: Vec::new(),
+                });
+                self.groups.len() - 1
+            }
+        };
+        &mut self.groups[idx]
+    }
+
+    /// `folder` is the group requests at this level go into; `None` means the collection
+    /// root, whose loose requests are grouped under the collection's name.
+    fn walk(
+        &mut self,
+        items: &[Value],
+        folder: Option<&str>,
+        collection_name: &str,
+        path: &str,
+        inherited_auth: Option<&Value>,
+    ) {
+        for item in items {
+            let name = item
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or("Unnamed");
+            let item_path = format!("{}/{}", path, name);
+            let auth = item.get("auth").or(inherited_auth);
+
+            self.check_scripts(item, &item_path);
+
+            if let Some(children) = item.get("item").and_then(Value::as_array) {
+                let group_name = match folder {
+                    Some(parent) => format!("{} / {}", parent, name),
+                    None => name.to_string(),
+                };
+                self.group_mut(&group_name);
+                self.walk(
+                    children,
+                    Some(&group_name),
+                    collection_name,
+                    &item_path,
+                    auth,
+                );
+            } else if let Some(request) = item.get("request") {
+                if let Some(api_request) = self.convert_request(name, request, auth, &item_path) {
+                    let group_name = folder.unwrap_or(collection_name).to_string();
+                    self.group_mut(&group_name).requests.push(api_request);
+                }
+            } else {
+                self.warn(&item_path, "item has no request, skipped");
+            }
+        }
+    }
+
+    fn check_scripts(&mut self, item: &Value, path: &str) {
+        let Some(events) = item.get("event").and_then(Value::as_array) else {
+            return;
+        };
+
+        for event in events {
+            let has_script = event
+                .pointer("/script/exec")
+                .map(|exec| match exec {
+                    Value::String(s) => !s.trim().is_empty(),
+                    Value::Array(lines) => lines
+                        .iter()
+                        .any(|l| l.as_str().is_some_and(|l| !l.trim().is_empty())),
+                    _ => false,
+                })
+                .unwrap_or(false);
+
+            if has_script {
+                let listen = event.get("listen").and_then(Value::as_str).unwrap_or("");
+                let kind = match listen {
+                    "prerequest" => "pre-request script",
+                    "test" => "test script",
+                    _ => "script",
+                };
+                self.warn(path, &format!("{} not imported", kind));
+            }
+        }
+    }
+
+    fn convert_request(
+        &mut self,
+        name: &str,
+        request: &Value,
+        auth: Option<&Value>,
+        path: &str,
+    ) -> Option<ApiRequest> {
+        // A request can be just its URL
+        if let Some(url) = request.as_str() {
+            let mut api_request = ApiRequest::new(name.to_string(), RequestType::GET);
+            let (base, query) = query::split_query(url);
+            api_request.details.url = base;
+            for (key, value) in query::parse_query(&query) {
+                api_request.details.params.push(KeyValue::new(key, value));
+            }
+            return Some(api_request);
+        }
+
+        let method = request
+            .get("method")
+            .and_then(Value::as_str)
+            .unwrap_or("GET")
+            .to_uppercase();
+        let request_type = match method.as_str() {
+            "GET" => RequestType::GET,
+            "POST" => RequestType::POST,
+            "PUT" => RequestType::PUT,
+            "DELETE" => RequestType::DELETE,
+            "PATCH" => RequestType::PATCH,
+            other => {
+                self.warn(path, &format!("method {} is not supported, skipped", other));
+                return None;
+            }
+        };
+
+        let mut api_request = ApiRequest::new(name.to_string(), request_type);
+        let details = &mut api_request.details;
+
+        // URL and query. Postman keeps query values as they appear in the URL, so they are
+        // decoded here and encoded again when sent.
+        if let Some(url) = request.get("url") {
+            let raw = match url {
+                Value::String(raw) => raw.clone(),
+                Value::Object(_) => url
+                    .get("raw")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| build_url(url)),
+                _ => String::new(),
+            };
+            let (base, raw_query) = query::split_query(&raw);
+            details.url = base;
+
+            match url.get("query").and_then(Value::as_array) {
+                Some(entries) => {
+                    for entry in entries {
+                        let key = entry.get("key").and_then(Value::as_str).unwrap_or("");
+                        if key.is_empty() {
+                            continue;
+                        }
+                        let value = entry.get("value").and_then(Value::as_str).unwrap_or("");
+                        let (key, value) = query::parse_query(&format!("{}={}", key, value))
+                            .pop()
+                            .unwrap_or_default();
+                        let mut param = KeyValue::new(key, value);
+                        param.enabled = !is_disabled(entry);
+                        details.params.push(param);
+                    }
+                }
+                None => {
+                    for (key, value) in query::parse_query(&raw_query) {
+                        details.params.push(KeyValue::new(key, value));
+                    }
+                }
+            }
+        }
+
+        // Headers
+        if let Some(headers) = request.get("header").and_then(Value::as_array) {
+            for header in headers {
+                let key = header.get("key").and_then(Value::as_str).unwrap_or("");
+                if key.is_empty() {
+                    continue;
+                }
+                let value = header.get("value").and_then(Value::as_str).unwrap_or("");
+                let mut header_value = KeyValue::new(key, value);
+                header_value.enabled = !is_disabled(header);
+                details.headers.push(header_value);
+            }
+        }
+
+        // Body
+        if let Some(body) = request.get("body").filter(|b| !is_disabled(b)) {
+            match body.get("mode").and_then(Value::as_str) {
+                Some("raw") => {
+                    details.body = body
+                        .get("raw")
+                        .and_then(Value::as_str)
+                        .unwrap_or("")
+                        .to_string();
+
+                    // Postman sends a Content-Type for JSON bodies without listing the header
+                    let language = body
+                        .pointer("/options/raw/language")
+                        .and_then(Value::as_str);
+                    if language == Some("json") && details.header("content-type").is_none() {
+                        details
+                            .headers
+                            .push(KeyValue::new("Content-Type", "application/json"));
+                    }
+                }
+                Some("urlencoded") => {
+                    let fields = body
+                        .get("urlencoded")
+                        .and_then(Value::as_array)
+                        .map(Vec::as_slice)
+                        .unwrap_or_default();
+                    // A body has no way to keep a field around switched off
+                    let mut pairs = Vec::new();
+                    for field in fields {
+                        let Some(key) = field.get("key").and_then(Value::as_str) else {
+                            continue;
+                        };
+                        if is_disabled(field) {
+                            self.warn(path, &format!("disabled form field {} not imported", key));
+                            continue;
+                        }
+                        let value = field.get("value").and_then(Value::as_str).unwrap_or("");
+                        pairs.push((key, value));
+                    }
+                    details.body = query::encode_pairs(pairs);
+                    if details.header("content-type").is_none() {
+                        details.headers.push(KeyValue::new(
+                            "Content-Type",
+                            "application/x-www-form-urlencoded",
+                        ));
+                    }
+                }
+                Some("formdata") => self.warn(path, "form-data body not imported"),
+                Some("file") => self.warn(path, "file body not imported"),
+                Some("graphql") => self.warn(path, "GraphQL body not imported"),
+                Some(other) => self.warn(path, &format!("{} body not imported", other)),
+                None => {}
+            }
+        }
+
+        // Auth, inherited from the closest folder or the collection
+        if let Some(auth) = request.get("auth").or(auth) {
+            match auth.get("type").and_then(Value::as_str) {
+                Some("basic") => {
+                    let basic = BasicAuth {
+                        username: auth_field(auth, "basic", "username"),
+                        password: auth_field(auth, "basic", "password"),
+                    };
+                    if !basic.username.is_empty() {
+                        details.set_header("Authorization", basic.authorization_header());
+                    }
+                    details.auth_type = AuthType::Basic;
+                    details.auth_details = AuthDetails::Basic(basic);
+                }
+                Some("noauth") | None => {}
+                Some(other) => self.warn(path, &format!("{} auth not imported", other)),
+            }
+        }
+
+        Some(api_request)
+    }
+}
+
+fn is_disabled(value: &Value) -> bool {
+    value
+        .get("disabled")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
+/// Reads a field of an auth block. v2.1 stores a list of `{key, value}` pairs, v2.0 a plain
+/// object.
+fn auth_field(auth: &Value, auth_type: &str, field: &str) -> String {
+    match auth.get(auth_type) {
+        Some(Value::Array(entries)) => entries
+            .iter()
+            .find(|e| e.get("key").and_then(Value::as_str) == Some(field))
+            .and_then(|e| e.get("value"))
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .to_string(),
+        Some(Value::Object(fields)) => fields
+            .get(field)
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Rebuilds a URL from its parts when the collection doesn't carry `raw`.
+fn build_url(url: &Value) -> String {
+    let join = |key: &str, sep: &str| match url.get(key) {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(sep),
+        _ => String::new(),
+    };
+
+    let mut built = String::new();
+    if let Some(protocol) = url.get("protocol").and_then(Value::as_str) {
+        built.push_str(protocol);
+        built.push_str("://");
+    }
+    built.push_str(&join("host", "."));
+    if let Some(port) = url.get("port").and_then(Value::as_str) {
+        built.push(':');
+        built.push_str(port);
+    }
+    let path = join("path", "/");
+    if !path.is_empty() {
+        built.push('/');
+        built.push_str(&path);
+    }
+    built
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLLECTION: &str = r#"{
+        "info": {
+            "_postman_id": "6f1c",
+            "name": "Pet Store",
+            "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json"
+        },
+        "auth": {
+            "type": "basic",
+            "basic": [
+                {"key": "username", "value": "admin", "type": "string"},
+                {"key": "password", "value": "secret", "type": "string"}
+            ]
+        },
+        "item": [
+            {
+                "name": "Pets",
+                "item": [
+                    {
+                        "name": "List pets",
+                        "request": {
+                            "method": "GET",
+                            "header": [
+                                {"key": "Accept", "value": "application/json"},
+                                {"key": "X-Debug", "value": "1", "disabled": true}
+                            ],
+                            "url": {
+                                "raw": "{{baseUrl}}/pets?limit=10&page=2&tag=big%20dog&debug=1",
+                                "host": ["{{baseUrl}}"],
+                                "path": ["pets"],
+                                "query": [
+                                    {"key": "limit", "value": "10"},
+                                    {"key": "page", "value": "2"},
+                                    {"key": "tag", "value": "big%20dog"},
+                                    {"key": "debug", "value": "1", "disabled": true}
+                                ]
+                            }
+                        }
+                    },
+                    {
+                        "name": "Admin",
+                        "item": [
+                            {
+                                "name": "Create pet",
+                                "event": [
+                                    {"listen": "test", "script": {"exec": ["pm.test('ok')"]}}
+                                ],
+                                "request": {
+                                    "method": "POST",
+                                    "auth": {"type": "noauth"},
+                                    "body": {
+                                        "mode": "raw",
+                                        "raw": "{\"name\": \"Rex\"}",
+                                        "options": {"raw": {"language": "json"}}
+                                    },
+                                    "url": "{{baseUrl}}/pets"
+                                }
+                            }
+                        ]
+                    }
+                ]
+            },
+            {
+                "name": "Upload photo",
+                "request": {
+                    "method": "PUT",
+                    "auth": {"type": "bearer", "bearer": [{"key": "token", "value": "t"}]},
+                    "body": {"mode": "formdata", "formdata": []},
+                    "url": "https://petstore.example.com/photo"
+                }
+            },
+            {
+                "name": "Login",
+                "request": {
+                    "method": "POST",
+                    "body": {
+                        "mode": "urlencoded",
+                        "urlencoded": [
+                            {"key": "user", "value": "rex@example.com"},
+                            {"key": "password", "value": "a&b=c d"},
+                            {"key": "remember", "value": "1", "disabled": true}
+                        ]
+                    },
+                    "url": "https://petstore.example.com/login?next=%2Fhome"
+                }
+            },
+            {
+                "name": "Probe",
+                "request": {"method": "HEAD", "url": "https://petstore.example.com"}
+            }
+        ]
+    }"#;
+
+    fn find<'a>(import: &'a PostmanImport, group: &str, name: &str) -> &'a ApiRequest {
+        import
+            .groups
+            .iter()
+            .find(|g| g.name == group)
+            .and_then(|g| g.requests.iter().find(|r| r.name == name))
+            .unwrap_or_else(|| panic!("{} / {} not imported", group, name))
+    }
+
+    #[test]
+    fn test_folders_become_groups() {
+        let import = parse_collection(COLLECTION).unwrap();
+        let names: Vec<_> = import.groups.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, vec!["Pets", "Pets / Admin", "Pet Store"]);
+        assert_eq!(import.request_count(), 4);
+    }
+
+    #[test]
+    fn test_request_fields_are_mapped() {
+        let import = parse_collection(COLLECTION).unwrap();
+
+        let list = find(&import, "Pets", "List pets");
+        assert!(matches!(list.request_type, RequestType::GET));
+        assert_eq!(list.details.url, "{{baseUrl}}/pets");
+        let mut debug = KeyValue::new("debug", "1");
+        debug.enabled = false;
+        assert_eq!(
+            list.details.params,
+            vec![
+                KeyValue::new("limit", "10"),
+                KeyValue::new("page", "2"),
+                KeyValue::new("tag", "big dog"),
+                debug,
+            ]
+        );
+        assert_eq!(list.details.header("Accept"), Some("application/json"));
+        assert_eq!(list.details.headers[1].key, "X-Debug");
+        assert!(!list.details.headers[1].enabled);
+
+        // Inherits the collection's basic auth
+        let basic = list.details.get_basic_auth().unwrap();
+        assert_eq!(basic.username, "admin");
+        assert_eq!(basic.password, "secret");
+        assert_eq!(
+            list.details.header("Authorization"),
+            Some(basic.authorization_header().as_str())
+        );
+
+        let create = find(&import, "Pets / Admin", "Create pet");
+        assert!(matches!(create.request_type, RequestType::POST));
+        assert_eq!(create.details.body, "{\"name\": \"Rex\"}");
+        assert_eq!(
+            create.details.header("Content-Type"),
+            Some("application/json")
+        );
+        assert!(matches!(create.details.auth_type, AuthType::None));
+
+        let login = find(&import, "Pet Store", "Login");
+        assert_eq!(login.details.url, "https://petstore.example.com/login");
+        assert_eq!(login.details.params, vec![KeyValue::new("next", "/home")]);
+        assert_eq!(
+            login.details.body,
+            "user=rex%40example.com&password=a%26b%3Dc+d"
+        );
+        assert_eq!(
+            login.details.header("Content-Type"),
+            Some("application/x-www-form-urlencoded")
+        );
+    }
+
+    #[test]
+    fn test_unsupported_constructs_are_reported() {
+        let import = parse_collection(COLLECTION).unwrap();
+        let warnings = import.warnings.join("\n");
+
+        assert!(warnings.contains("Pet Store/Pets/Admin/Create pet: test script not imported"));
+        assert!(warnings.contains("Pet Store/Upload photo: form-data body not imported"));
+        assert!(warnings.contains("Pet Store/Upload photo: bearer auth not imported"));
+        assert!(warnings.contains("Pet Store/Probe: method HEAD is not supported, skipped"));
+        assert!(warnings.contains("Pet Store/Login: disabled form field remember not imported"));
+        assert!(!warnings.contains("X-Debug"));
+
+        // The request with unsupported parts is still imported
+        let upload = find(&import, "Pet Store", "Upload photo");
+        assert_eq!(upload.details.url, "https://petstore.example.com/photo");
+    }
+
+    #[test]
+    fn test_rejects_non_collections() {
+        assert!(parse_collection("{\"foo\": 1}").is_err());
+        assert!(parse_collection("not json").is_err());
+    }
+}
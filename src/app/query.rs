@@ -0,0 +1,135 @@
+use url::form_urlencoded;
+
+use super::models::KeyValue;
+
+/// Splits a URL into the part before the query string and the (still encoded) query. A
+/// `#fragment` stays with the first part.
+pub fn split_query(url: &str) -> (String, String) {
+    let Some((base, rest)) = url.split_once('?') else {
+        return (url.to_string(), String::new());
+    };
+    match rest.split_once('#') {
+        Some((query, fragment)) => (format!("{}#{}", base, fragment), query.to_string()),
+        None => (base.to_string(), rest.to_string()),
+    }
+}
+
+/// Decodes a query string into key/value pairs, keeping order and repeated keys.
+pub fn parse_query(query: &str) -> Vec<(String, String)> {
+    form_urlencoded::parse(query.as_bytes())
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect()
+}
+
+/// Percent-encodes a query key or value the way it is sent. `{{variable}}` placeholders are
+/// left readable since they are replaced before sending.
+fn encode_component(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        output.extend(form_urlencoded::byte_serialize(&rest.as_bytes()[..start]));
+        output.push_str(&rest[start..start + 2 + len + 2]);
+        rest = &rest[start + 2 + len + 2..];
+    }
+
+    output.extend(form_urlencoded::byte_serialize(rest.as_bytes()));
+    output
+}
+
+/// Encodes key/value pairs as `application/x-www-form-urlencoded`, the format of both query
+/// strings and form bodies.
+pub fn encode_pairs<'a>(pairs: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+    pairs
+        .into_iter()
+        .map(|(key, value)| format!("{}={}", encode_component(key), encode_component(value)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Builds an encoded query string from the enabled params.
+pub fn encode_query(params: &[KeyValue]) -> String {
+    encode_pairs(
+        params
+            .iter()
+            .filter(|param| param.enabled)
+            .map(|param| (param.key.as_str(), param.value.as_str())),
+    )
+}
+
+/// Appends the enabled params to `url`, ahead of any `#fragment`.
+pub fn with_query(url: &str, params: &[KeyValue]) -> String {
+    let query = encode_query(params);
+    if query.is_empty() {
+        return url.to_string();
+    }
+
+    let (path, fragment) = match url.split_once('#') {
+        Some((path, fragment)) => (path, Some(fragment)),
+        None => (url, None),
+    };
+    let separator = if path.contains('?') { '&' } else { '?' };
+    match fragment {
+        Some(fragment) => format!("{}{}{}#{}", path, separator, query, fragment),
+        None => format!("{}{}{}", path, separator, query),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_and_parse() {
+        let (base, query) = split_query("https://api.example.com/x?page=2&tag=a%20b&tag=c+d#top");
+        assert_eq!(base, "https://api.example.com/x#top");
+        assert_eq!(
+            parse_query(&query),
+            vec![
+                ("page".to_string(), "2".to_string()),
+                ("tag".to_string(), "a b".to_string()),
+                ("tag".to_string(), "c d".to_string()),
+            ]
+        );
+
+        assert_eq!(
+            split_query("http://localhost"),
+            ("http://localhost".to_string(), String::new())
+        );
+        assert_eq!(
+            parse_query("flag"),
+            vec![("flag".to_string(), String::new())]
+        );
+    }
+
+    #[test]
+    fn test_encoding_round_trips() {
+        let mut disabled = KeyValue::new("debug", "1");
+        disabled.enabled = false;
+        let params = vec![
+            KeyValue::new("q", "rust & tui"),
+            disabled,
+            KeyValue::new("filter", "a=b+c/100%"),
+            KeyValue::new("token", "{{api token}}"),
+        ];
+
+        let url = with_query("https://api.example.com/search#results", &params);
+        assert_eq!(
+            url,
+            "https://api.example.com/search?q=rust+%26+tui&filter=a%3Db%2Bc%2F100%25\
+             &token={{api token}}#results"
+        );
+
+        let (base, query) = split_query(&url);
+        assert_eq!(base, "https://api.example.com/search#results");
+        let expected: Vec<_> = params
+            .iter()
+            .filter(|param| param.enabled)
+            .map(|param| (param.key.clone(), param.value.clone()))
+            .collect();
+        assert_eq!(parse_query(&query), expected);
+    }
+}
@@ -1,11 +1,519 @@
-use std::collections::HashMap;
-use std::time::Duration;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::runtime::Runtime;
+use tokio::task::AbortHandle;
+
+use super::auth::AuthCache;
+use super::aws;
+use super::digest::{Challenge, DigestCache};
+use super::error::{ErrorKind, RequestError};
+use super::hmac;
+use super::json::FormattedBody;
+use super::models::{
+    AwsSigV4Auth, DigestAuth, HmacAuth, OAuth2Auth, OAuth2Tokens, RequestType, TlsSettings,
+};
+use super::oauth::Authorization;
+use super::proxy::{self, ProxyConfig, ProxyServer};
+use super::tls::{self, Handshakes, TlsInfo};
 
 #[derive(Clone)]
 pub struct RequestResponse {
     pub status: u16,
     pub status_text: String,
-    pub headers: HashMap<String, String>,
+    /// Response headers in the order received. Repeated headers such as `Set-Cookie` appear
+    /// once per value.
+    pub headers: Vec<(String, String)>,
     pub body: String,
+    /// How the body is displayed, e.g. re-indented when it is JSON.
+    pub formatted_body: FormattedBody,
     pub time_taken: Duration,
+    /// The server's certificates and the agreed protocol, for HTTPS requests.
+    pub tls: Option<TlsInfo>,
+    /// The proxy the request went through, without credentials.
+    pub proxy: Option<String>,
+}
+
+/// A request with environment variables expanded and auth applied, ready to go on the wire.
+#[derive(Clone, Debug)]
+pub struct PreparedRequest {
+    pub request_type: RequestType,
+    pub url: String,
+    pub body: String,
+    /// Enabled headers in order, duplicates included.
+    pub headers: Vec<(String, String)>,
+    /// Enabled query params in order, duplicates included.
+    pub params: Vec<(String, String)>,
+    /// Client credentials to fetch a bearer token with, for OAuth 2.0 auth.
+    pub oauth2: Option<OAuth2Auth>,
+    /// Credentials to answer the server's challenge with, for Digest auth.
+    pub digest: Option<DigestAuth>,
+    /// Credentials to sign the request with, for AWS Signature Version 4 auth.
+    pub aws_sigv4: Option<AwsSigV4Auth>,
+    /// Secret and template to sign the request with, for HMAC auth.
+    pub hmac: Option<HmacAuth>,
+    /// Client certificate, trusted CAs and versions to connect with, request and group
+    /// settings merged.
+    pub tls: TlsSettings,
+    /// Proxies to connect through, from the innermost settings that don't inherit.
+    pub proxy: ProxyConfig,
+}
+
+pub type RequestResult = Result<RequestResponse, RequestError>;
+
+pub type AuthorizationResult = Result<OAuth2Tokens, RequestError>;
+
+/// Sends `request` and describes the TLS handshake with its server and the proxy used,
+/// whether the request succeeds or not.
+pub async fn execute(request: PreparedRequest, cache: &AuthCache) -> RequestResult {
+    let proxy = request
+        .proxy
+        .server_for(&request.url)
+        .map(ProxyServer::display);
+    let handshakes = Handshakes::default();
+    match execute_request(request, cache, &handshakes).await {
+        Ok(mut response) => {
+            response.tls = handshakes.complete(response.tls.take());
+            response.proxy = proxy;
+            Ok(response)
+        }
+        Err(mut error) => {
+            error.tls = handshakes.latest();
+            Err(error.through_proxy(proxy))
+        }
+    }
+}
+
+/// A client that connects with `tls` through `proxy`, noting its handshakes in
+/// `handshakes`. Invalid TLS and proxy settings are reported by `tls::configure` and
+/// `proxy::configure`, so a client that still can't be built is not down to either.
+fn build_client(
+    tls: &TlsSettings,
+    proxy: &ProxyConfig,
+    handshakes: &Handshakes,
+) -> Result<reqwest::Client, RequestError> {
+    let builder = tls::configure(reqwest::Client::builder(), tls, handshakes)?;
+    proxy::configure(builder, proxy)?.build().map_err(|e| {
+        RequestError::new(
+            ErrorKind::Internal,
+            format!("Could not set up the HTTP client: {}", e),
+        )
+    })
+}
+
+/// Sends `request`. OAuth 2.0 requests get a bearer token from `cache` first, and are
+/// sent once more with a new token if the server answers 401. Digest requests answer the
+/// server's challenge, see `execute_digest`. AWS and HMAC requests are signed as they are.
+async fn execute_request(
+    mut request: PreparedRequest,
+    cache: &AuthCache,
+    handshakes: &Handshakes,
+) -> RequestResult {
+    if let Some(auth) = request.aws_sigv4.take() {
+        aws::sign(&mut request, &auth, SystemTime::now())?;
+    }
+    if let Some(auth) = request.hmac.take() {
+        hmac::sign(&mut request, &auth, SystemTime::now())?;
+    }
+    let client = build_client(&request.tls, &request.proxy, handshakes)?;
+    if let Some(digest) = &request.digest {
+        return execute_digest(&client, &request, digest, &cache.digest).await;
+    }
+    let Some(oauth2) = &request.oauth2 else {
+        return send(&client, &request, None).await;
+    };
+
+    let token = cache.tokens.access_token(&client, oauth2, false).await?;
+    let bearer = |token: String| format!("Bearer {}", token);
+    let response = send(&client, &request, Some(&bearer(token))).await?;
+    if response.status != 401 {
+        return Ok(response);
+    }
+
+    // The token may have been revoked before it expired
+    let token = cache.tokens.access_token(&client, oauth2, true).await?;
+    send(&client, &request, Some(&bearer(token))).await
+}
+
+/// Sends `request` answering the challenge cached for its origin, if there is one yet. A
+/// 401 with a new challenge is answered and sent once more. A 401 for the nonce that was
+/// just answered means the credentials are wrong, so that response is returned as is and
+/// the challenge forgotten, letting corrected credentials start over.
+async fn execute_digest(
+    client: &reqwest::Client,
+    request: &PreparedRequest,
+    auth: &DigestAuth,
+    sessions: &DigestCache,
+) -> RequestResult {
+    let method = request.request_type.as_str();
+    let attempt = sessions.authorization(auth, method, &request.url, &request.params)?;
+    let header = attempt.as_ref().map(|attempt| attempt.header.as_str());
+    let response = send(client, request, header).await?;
+    if response.status != 401 {
+        return Ok(response);
+    }
+    let Some(challenge) = Challenge::find(&response.headers) else {
+        return Ok(response);
+    };
+    let rejected = attempt.is_some_and(|attempt| attempt.nonce == challenge.nonce);
+    if rejected && !challenge.stale {
+        sessions.remove(&request.url, auth);
+        return Ok(response);
+    }
+
+    sessions.insert(&request.url, auth, challenge);
+    let retry = sessions.authorization(auth, method, &request.url, &request.params)?;
+    let Some(attempt) = retry else {
+        return Ok(response);
+    };
+    let response = send(client, request, Some(&attempt.header)).await?;
+    let challenge = Challenge::find(&response.headers);
+    if response.status == 401 && challenge.is_some_and(|c| c.nonce == attempt.nonce) {
+        sessions.remove(&request.url, auth);
+    }
+    Ok(response)
+}
+
+/// Sends `request` once. `authorization` replaces any `Authorization` header it has.
+async fn send(
+    client: &reqwest::Client,
+    request: &PreparedRequest,
+    authorization: Option<&str>,
+) -> RequestResult {
+    let start = Instant::now();
+    let fail = |e: reqwest::Error| RequestError::from_reqwest(&e, start.elapsed());
+
+    let mut builder = match request.request_type {
+        RequestType::GET => client.get(&request.url),
+        RequestType::POST => client.post(&request.url),
+        RequestType::PUT => client.put(&request.url),
+        RequestType::DELETE => client.delete(&request.url),
+        RequestType::PATCH => client.patch(&request.url),
+    };
+
+    // Add headers, `header` appends so repeated names are all sent
+    for (key, value) in &request.headers {
+        builder = builder.header(key, value);
+    }
+    if let Some(authorization) = authorization {
+        builder = builder.header(reqwest::header::AUTHORIZATION, authorization);
+    }
+
+    // Add query parameters
+    if !request.params.is_empty() {
+        builder = builder.query(&request.params);
+    }
+
+    // Add body for non-GET requests
+    if !matches!(request.request_type, RequestType::GET) {
+        builder = builder.body(request.body.clone());
+    }
+
+    let response = builder.send().await.map_err(fail)?;
+    let duration = start.elapsed();
+
+    let status = response.status();
+    let headers: Vec<(String, String)> = response
+        .headers()
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+        .collect();
+    let tls = tls::connection_info(&response);
+    let body = response.text().await.map_err(fail)?;
+
+    Ok(RequestResponse {
+        status: status.as_u16(),
+        status_text: status.to_string(),
+        formatted_body: FormattedBody::new(&headers, &body),
+        headers,
+        body,
+        time_taken: duration,
+        tls,
+        proxy: None,
+    })
+}
+
+struct InFlight {
+    id: u64,
+    started: Instant,
+    abort: AbortHandle,
+}
+
+struct InFlightAuthorization {
+    id: u64,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Runs requests on a single long-lived runtime and hands results back over a channel, so
+/// the UI loop never blocks on the network. Only one request is in flight at a time.
+pub struct RequestSender {
+    runtime: Option<Runtime>,
+    tx: Sender<(u64, RequestResult)>,
+    rx: Receiver<(u64, RequestResult)>,
+    next_id: u64,
+    in_flight: Option<InFlight>,
+    auth_tx: Sender<(u64, AuthorizationResult)>,
+    auth_rx: Receiver<(u64, AuthorizationResult)>,
+    /// An OAuth 2.0 sign-in, which runs next to requests rather than in their place.
+    authorizing: Option<InFlightAuthorization>,
+    /// OAuth 2.0 tokens and Digest challenges, reused across requests.
+    pub auth_cache: AuthCache,
+}
+
+impl RequestSender {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel();
+        let (auth_tx, auth_rx) = mpsc::channel();
+        Self {
+            runtime: None,
+            tx,
+            rx,
+            next_id: 0,
+            in_flight: None,
+            auth_tx,
+            auth_rx,
+            authorizing: None,
+            auth_cache: AuthCache::default(),
+        }
+    }
+
+    pub fn is_in_flight(&self) -> bool {
+        self.in_flight.is_some()
+    }
+
+    /// Time since the in-flight request was dispatched.
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.in_flight.as_ref().map(|f| f.started.elapsed())
+    }
+
+    /// The runtime is only started once something is actually sent.
+    fn runtime(&mut self) -> io::Result<&Runtime> {
+        if self.runtime.is_none() {
+            self.runtime = Some(Runtime::new()?);
+        }
+        Ok(self.runtime.as_ref().unwrap())
+    }
+
+    pub fn dispatch(&mut self, request: PreparedRequest) -> io::Result<()> {
+        self.cancel();
+
+        self.next_id += 1;
+        let id = self.next_id;
+        let tx = self.tx.clone();
+        let cache = self.auth_cache.clone();
+        let handle = self.runtime()?.spawn(async move {
+            let result = execute(request, &cache).await;
+            let _ = tx.send((id, result));
+        });
+
+        self.in_flight = Some(InFlight {
+            id,
+            started: Instant::now(),
+            abort: handle.abort_handle(),
+        });
+        Ok(())
+    }
+
+    /// Returns the result of the in-flight request once it has finished. Results of
+    /// cancelled requests are dropped.
+    pub fn try_recv(&mut self) -> Option<RequestResult> {
+        while let Ok((id, result)) = self.rx.try_recv() {
+            if self.in_flight.as_ref().is_some_and(|f| f.id == id) {
+                self.in_flight = None;
+                return Some(result);
+            }
+        }
+        None
+    }
+
+    /// Aborts the in-flight request and returns how long it had been running.
+    pub fn cancel(&mut self) -> Option<Duration> {
+        self.in_flight.take().map(|in_flight| {
+            in_flight.abort.abort();
+            in_flight.started.elapsed()
+        })
+    }
+
+    pub fn is_authorizing(&self) -> bool {
+        self.authorizing.is_some()
+    }
+
+    /// Waits for the sign-in in the background. Its tokens are picked up by
+    /// `try_recv_authorization`. The token endpoint is reached with the request's `tls`
+    /// settings and through its `proxy`.
+    pub fn authorize(
+        &mut self,
+        authorization: Authorization,
+        tls: TlsSettings,
+        proxy: ProxyConfig,
+    ) -> io::Result<()> {
+        self.cancel_authorization();
+
+        self.next_id += 1;
+        let id = self.next_id;
+        let cancelled = authorization.cancel_flag();
+        let tx = self.auth_tx.clone();
+        let tokens = self.auth_cache.tokens.clone();
+        self.runtime()?.spawn(async move {
+            let result = match build_client(&tls, &proxy, &Handshakes::default()) {
+                Ok(client) => authorization.finish(&client, &tokens).await,
+                Err(e) => Err(e),
+            };
+            let _ = tx.send((id, result));
+        });
+
+        self.authorizing = Some(InFlightAuthorization { id, cancelled });
+        Ok(())
+    }
+
+    /// Returns the result of the sign-in once it has finished. Results of cancelled
+    /// sign-ins are dropped.
+    pub fn try_recv_authorization(&mut self) -> Option<AuthorizationResult> {
+        while let Ok((id, result)) = self.auth_rx.try_recv() {
+            if self.authorizing.as_ref().is_some_and(|a| a.id == id) {
+                self.authorizing = None;
+                return Some(result);
+            }
+        }
+        None
+    }
+
+    /// Stops waiting for the browser, which also frees the redirect port.
+    pub fn cancel_authorization(&mut self) {
+        if let Some(authorizing) = self.authorizing.take() {
+            authorizing.cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Default for RequestSender {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn get(url: String) -> PreparedRequest {
+        PreparedRequest {
+            request_type: RequestType::GET,
+            url,
+            body: String::new(),
+            headers: Vec::new(),
+            params: Vec::new(),
+            oauth2: None,
+            digest: None,
+            aws_sigv4: None,
+            hmac: None,
+            tls: TlsSettings::default(),
+            proxy: ProxyConfig::default(),
+        }
+    }
+
+    fn wait_for(sender: &mut RequestSender) -> RequestResult {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            if let Some(result) = sender.try_recv() {
+                return result;
+            }
+            assert!(Instant::now() < deadline, "request never completed");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_dispatch_delivers_response() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 1024];
+            let _ = stream.read(&mut buf);
+            let _ = stream.write_all(
+                b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello",
+            );
+        });
+
+        let mut sender = RequestSender::new();
+        sender.dispatch(get(format!("http://{}/", addr))).unwrap();
+        assert!(sender.is_in_flight());
+
+        let response = wait_for(&mut sender).unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, "hello");
+        assert!(!sender.is_in_flight());
+    }
+
+    #[test]
+    fn test_repeated_params_and_headers_keep_order() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (request_tx, request_rx) = mpsc::channel();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 4096];
+            let n = stream.read(&mut buf).unwrap();
+            let _ = request_tx.send(String::from_utf8_lossy(&buf[..n]).to_string());
+            let _ = stream.write_all(
+                b"HTTP/1.1 200 OK\r\nSet-Cookie: a=1\r\nX-Other: x\r\nSet-Cookie: b=2\r\n\
+                  Content-Length: 0\r\nConnection: close\r\n\r\n",
+            );
+        });
+
+        let mut request = get(format!("http://{}/items", addr));
+        request.params = vec![
+            ("tag".to_string(), "b".to_string()),
+            ("page".to_string(), "1".to_string()),
+            ("tag".to_string(), "a".to_string()),
+        ];
+        request.headers = vec![
+            ("Accept".to_string(), "text/plain".to_string()),
+            ("Accept".to_string(), "application/json".to_string()),
+        ];
+
+        let mut sender = RequestSender::new();
+        sender.dispatch(request).unwrap();
+        let response = wait_for(&mut sender).unwrap();
+
+        let raw = request_rx.recv().unwrap().to_lowercase();
+        assert!(raw.starts_with("get /items?tag=b&page=1&tag=a "));
+        let plain = raw.find("accept: text/plain").unwrap();
+        let json = raw.find("accept: application/json").unwrap();
+        assert!(plain < json);
+
+        let cookies: Vec<_> = response
+            .headers
+            .iter()
+            .filter(|(key, _)| key == "set-cookie")
+            .map(|(_, value)| value.as_str())
+            .collect();
+        assert_eq!(cookies, ["a=1", "b=2"]);
+    }
+
+    #[test]
+    fn test_cancel_drops_in_flight_request() {
+        // Accepts the connection but never answers
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let _conn = listener.accept();
+            thread::sleep(Duration::from_secs(5));
+        });
+
+        let mut sender = RequestSender::new();
+        sender.dispatch(get(format!("http://{}/", addr))).unwrap();
+        thread::sleep(Duration::from_millis(50));
+
+        assert!(sender.cancel().is_some());
+        assert!(!sender.is_in_flight());
+        assert!(sender.try_recv().is_none());
+        assert!(sender.cancel().is_none());
+    }
 }
@@ -0,0 +1,538 @@
+use serde_json::Value;
+
+use super::json::FormattedBody;
+use super::json_path::JsonPath;
+use super::requests::RequestResponse;
+use super::state::App;
+use super::tls::TlsInfo;
+use super::ui_state::{ActivePanel, DetailField};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineKind {
+    Plain,
+    /// A message about the body rather than part of it.
+    Notice,
+    /// A line of pretty-printed JSON.
+    Json,
+    /// A problem the user has to fix, such as an invalid filter.
+    Error,
+}
+
+/// A line of the response pane as plain text. Search runs over these, so the pane must
+/// render exactly these lines.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResponseLine {
+    pub text: String,
+    pub kind: LineKind,
+}
+
+impl ResponseLine {
+    fn new(text: impl Into<String>, kind: LineKind) -> Self {
+        Self {
+            text: text.into(),
+            kind,
+        }
+    }
+}
+
+/// Status, headers and body of `response`, one entry per line. A non-empty `filter` is
+/// applied to JSON bodies and only the values it selects are shown.
+pub fn response_lines(response: &RequestResponse, filter: &str) -> Vec<ResponseLine> {
+    let plain = |text: String| ResponseLine::new(text, LineKind::Plain);
+
+    let mut lines = vec![
+        plain(format!(
+            "Status: {} {}",
+            response.status, response.status_text
+        )),
+        plain(format!("Time: {}ms", response.time_taken.as_millis())),
+    ];
+    if let Some(proxy) = &response.proxy {
+        lines.push(plain(format!("Proxy: {}", proxy)));
+    }
+    lines.push(plain(String::new()));
+    lines.push(plain("Headers:".to_string()));
+    lines.extend(
+        response
+            .headers
+            .iter()
+            .map(|(k, v)| plain(format!("{}: {}", k, v))),
+    );
+    lines.push(plain(String::new()));
+
+    let filter = filter.trim();
+    if !filter.is_empty() {
+        match filtered_body_lines(response, filter) {
+            Ok(filtered) => {
+                lines.extend(filtered);
+                return lines;
+            }
+            Err(error) => lines.push(ResponseLine::new(error, LineKind::Error)),
+        }
+    }
+
+    lines.push(plain("Body:".to_string()));
+    match &response.formatted_body {
+        FormattedBody::Json(pretty) => {
+            lines.extend(
+                pretty
+                    .lines()
+                    .map(|line| ResponseLine::new(line, LineKind::Json)),
+            );
+        }
+        FormattedBody::Invalid(error) => {
+            lines.push(ResponseLine::new(
+                format!("Not valid JSON ({}), showing the raw body", error),
+                LineKind::Notice,
+            ));
+            lines.extend(response.body.lines().map(|line| plain(line.to_string())));
+        }
+        FormattedBody::Raw => {
+            lines.extend(response.body.lines().map(|line| plain(line.to_string())));
+        }
+    }
+    lines
+}
+
+/// The TLS tab: the handshake, then each certificate of the chain. Certificates close to
+/// expiry are flagged as notices, expired ones and verification failures as errors.
+pub fn tls_lines(tls: Option<&TlsInfo>) -> Vec<ResponseLine> {
+    let plain = |text: String| ResponseLine::new(text, LineKind::Plain);
+    let Some(tls) = tls else {
+        return vec![ResponseLine::new(
+            "No TLS details: the request wasn't sent over HTTPS, or no handshake was possible",
+            LineKind::Notice,
+        )];
+    };
+
+    let mut lines = vec![
+        plain(format!(
+            "Protocol: {}",
+            tls.protocol.as_deref().unwrap_or("unknown")
+        )),
+        plain(format!(
+            "HTTP version: {}",
+            tls.http_version.as_deref().unwrap_or("unknown")
+        )),
+        match &tls.verify_error {
+            None => plain("Verification: OK".to_string()),
+            Some(error) => {
+                ResponseLine::new(format!("Verification: failed ({})", error), LineKind::Error)
+            }
+        },
+    ];
+    for (idx, certificate) in tls.chain.iter().enumerate() {
+        lines.push(plain(String::new()));
+        lines.push(plain(if idx == 0 {
+            "Certificate 0 (server):".to_string()
+        } else {
+            format!("Certificate {}:", idx)
+        }));
+        lines.push(plain(format!("  Subject: {}", certificate.subject)));
+        if !certificate.alt_names.is_empty() {
+            lines.push(plain(format!(
+                "  Alternative names: {}",
+                certificate.alt_names.join(", ")
+            )));
+        }
+        lines.push(plain(format!("  Issuer: {}", certificate.issuer)));
+        lines.push(plain(format!("  Valid from: {}", certificate.not_before)));
+        let days = certificate.days_left;
+        lines.push(if days < 0 {
+            ResponseLine::new(
+                format!(
+                    "  Valid until: {} (expired {} days ago)",
+                    certificate.not_after, -days
+                ),
+                LineKind::Error,
+            )
+        } else if certificate.expires_soon() {
+            ResponseLine::new(
+                format!(
+                    "  Valid until: {} (expires in {} days)",
+                    certificate.not_after, days
+                ),
+                LineKind::Notice,
+            )
+        } else {
+            plain(format!(
+                "  Valid until: {} ({} days left)",
+                certificate.not_after, days
+            ))
+        });
+        lines.push(plain(format!("  Serial: {}", certificate.serial)));
+        lines.push(plain(format!("  SHA-256: {}", certificate.sha256)));
+    }
+    lines
+}
+
+/// The body section for a filtered response, or the message to show above the unfiltered
+/// body when the filter can't be applied.
+fn filtered_body_lines(
+    response: &RequestResponse,
+    filter: &str,
+) -> Result<Vec<ResponseLine>, String> {
+    let path = JsonPath::parse(filter).map_err(|e| format!("Invalid filter: {}", e))?;
+    if !matches!(response.formatted_body, FormattedBody::Json(_)) {
+        return Err("Filters only apply to JSON bodies".to_string());
+    }
+    let value: Value = serde_json::from_str(&response.body)
+        .map_err(|e| format!("Filters only apply to JSON bodies ({})", e))?;
+
+    let selected = path.select(&value);
+    let mut lines = vec![ResponseLine::new(
+        format!(
+            "Body ({}, {} {}):",
+            filter,
+            selected.len(),
+            if selected.len() == 1 {
+                "match"
+            } else {
+                "matches"
+            }
+        ),
+        LineKind::Plain,
+    )];
+    for value in selected {
+        let pretty = serde_json::to_string_pretty(value).unwrap_or_default();
+        lines.extend(
+            pretty
+                .lines()
+                .map(|line| ResponseLine::new(line, LineKind::Json)),
+        );
+    }
+    Ok(lines)
+}
+
+/// A search hit, as a byte range within one line.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchMatch {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Finds every occurrence of `query` in `lines`, ignoring ASCII case.
+pub fn find_matches(lines: &[ResponseLine], query: &str) -> Vec<SearchMatch> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let query = query.to_ascii_lowercase();
+
+    let mut matches = Vec::new();
+    for (idx, line) in lines.iter().enumerate() {
+        // ASCII lowercasing keeps byte offsets valid for the original text
+        let text = line.text.to_ascii_lowercase();
+        let mut from = 0;
+        while let Some(pos) = text[from..].find(&query) {
+            let start = from + pos;
+            matches.push(SearchMatch {
+                line: idx,
+                start,
+                end: start + query.len(),
+            });
+            from = start + query.len();
+        }
+    }
+    matches
+}
+
+/// What the response pane shows.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ResponseTab {
+    /// Status, headers and body.
+    #[default]
+    Response,
+    /// The TLS handshake and the server's certificates.
+    Tls,
+}
+
+/// Scroll position and search state of the response pane.
+#[derive(Default)]
+pub struct ResponseView {
+    pub tab: ResponseTab,
+    pub scroll: u16,
+    pub horizontal_scroll: u16,
+    /// Size of the pane inside its borders as of the last draw, used for paging.
+    pub viewport: (u16, u16),
+    /// True while the search query is being typed.
+    pub searching: bool,
+    pub search_query: String,
+    pub matches: Vec<SearchMatch>,
+    pub current_match: usize,
+    /// The filter being typed, `None` when the saved filter of the request applies.
+    pub filter_input: Option<String>,
+}
+
+impl App {
+    pub fn switch_to_response(&mut self) {
+        self.active_panel = ActivePanel::Response;
+        self.current_detail_field = DetailField::None;
+    }
+
+    /// The filter currently applied to the response: the one being typed, otherwise the
+    /// one saved on the request.
+    pub fn response_filter(&self) -> String {
+        match &self.response_view.filter_input {
+            Some(input) => input.clone(),
+            None => self
+                .get_current_request()
+                .map(|request| request.response_filter.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The lines of the response pane for the open tab, with the active filter applied.
+    pub fn current_response_lines(&self) -> Vec<ResponseLine> {
+        let Some(response) = &self.last_response else {
+            return Vec::new();
+        };
+        match self.response_view.tab {
+            ResponseTab::Response => response_lines(response, &self.response_filter()),
+            ResponseTab::Tls => tls_lines(response.tls.as_ref()),
+        }
+    }
+
+    /// Switches between the response and the TLS tab.
+    pub fn toggle_response_tab(&mut self) {
+        self.response_view.tab = match self.response_view.tab {
+            ResponseTab::Response => ResponseTab::Tls,
+            ResponseTab::Tls => ResponseTab::Response,
+        };
+        self.reset_response_view();
+    }
+
+    fn response_line_count(&self) -> usize {
+        self.current_response_lines().len()
+    }
+
+    /// Scrolls the response by `rows` lines, negative values scroll up.
+    pub fn scroll_response(&mut self, rows: i32) {
+        let max = self
+            .response_line_count()
+            .saturating_sub(self.response_view.viewport.0 as usize);
+        let scroll = (self.response_view.scroll as i32 + rows).clamp(0, max as i32);
+        self.response_view.scroll = scroll as u16;
+    }
+
+    /// Scrolls the response by a page, `pages` is usually 1 or -1.
+    pub fn page_response(&mut self, pages: i32) {
+        let page = self.response_view.viewport.0.saturating_sub(1).max(1) as i32;
+        self.scroll_response(pages * page);
+    }
+
+    pub fn scroll_response_horizontally(&mut self, columns: i32) {
+        let scroll = (self.response_view.horizontal_scroll as i32 + columns).max(0);
+        self.response_view.horizontal_scroll = scroll as u16;
+    }
+
+    /// Starts editing the filter, beginning with the one saved on the request.
+    pub fn start_response_filter(&mut self) {
+        self.response_view.filter_input = Some(self.response_filter());
+    }
+
+    pub fn push_response_filter(&mut self, c: char) {
+        if let Some(input) = &mut self.response_view.filter_input {
+            input.push(c);
+            self.response_filter_changed();
+        }
+    }
+
+    pub fn pop_response_filter(&mut self) {
+        if let Some(input) = &mut self.response_view.filter_input {
+            input.pop();
+            self.response_filter_changed();
+        }
+    }
+
+    /// Saves the filter being typed on the request so it applies to later responses too.
+    pub fn finish_response_filter(&mut self) {
+        let Some(input) = self.response_view.filter_input.take() else {
+            return;
+        };
+        if let Some(request) = self.get_selected_request_mut() {
+            request.response_filter = input.trim().to_string();
+            self.persist_workspace();
+        }
+        self.response_filter_changed();
+    }
+
+    /// Stops editing and goes back to the saved filter.
+    pub fn cancel_response_filter(&mut self) {
+        self.response_view.filter_input = None;
+        self.response_filter_changed();
+    }
+
+    fn response_filter_changed(&mut self) {
+        self.reset_response_view();
+    }
+
+    /// Scrolls back to the top and re-runs the search, for when the displayed lines change.
+    pub fn reset_response_view(&mut self) {
+        self.response_view.scroll = 0;
+        self.response_view.horizontal_scroll = 0;
+        self.update_response_search();
+    }
+
+    pub fn start_response_search(&mut self) {
+        self.response_view.searching = true;
+        self.response_view.search_query.clear();
+        self.update_response_search();
+    }
+
+    pub fn push_response_search(&mut self, c: char) {
+        self.response_view.search_query.push(c);
+        self.update_response_search();
+    }
+
+    pub fn pop_response_search(&mut self) {
+        self.response_view.search_query.pop();
+        self.update_response_search();
+    }
+
+    /// Stops typing the query but keeps the matches for `n`/`N`.
+    pub fn finish_response_search(&mut self) {
+        self.response_view.searching = false;
+    }
+
+    pub fn clear_response_search(&mut self) {
+        self.response_view.searching = false;
+        self.response_view.search_query.clear();
+        self.response_view.matches.clear();
+        self.response_view.current_match = 0;
+    }
+
+    /// Re-runs the search, e.g. after the query or the response changed, and jumps to the
+    /// first match.
+    pub fn update_response_search(&mut self) {
+        let lines = self.current_response_lines();
+        let view = &mut self.response_view;
+        view.matches = find_matches(&lines, &view.search_query);
+        view.current_match = 0;
+        self.scroll_to_current_match();
+    }
+
+    pub fn next_response_match(&mut self) {
+        let view = &mut self.response_view;
+        if !view.matches.is_empty() {
+            view.current_match = (view.current_match + 1) % view.matches.len();
+            self.scroll_to_current_match();
+        }
+    }
+
+    pub fn previous_response_match(&mut self) {
+        let view = &mut self.response_view;
+        if !view.matches.is_empty() {
+            view.current_match = view
+                .current_match
+                .checked_sub(1)
+                .unwrap_or(view.matches.len() - 1);
+            self.scroll_to_current_match();
+        }
+    }
+
+    /// Scrolls just enough to bring the current match into view.
+    fn scroll_to_current_match(&mut self) {
+        let view = &mut self.response_view;
+        let Some(&found) = view.matches.get(view.current_match) else {
+            return;
+        };
+
+        let (height, width) = (view.viewport.0.max(1), view.viewport.1.max(1));
+        let line = found.line as u16;
+        if line < view.scroll {
+            view.scroll = line;
+        } else if line >= view.scroll + height {
+            view.scroll = line + 1 - height;
+        }
+
+        let column = self
+            .current_response_lines()
+            .get(found.line)
+            .map_or(0, |line| line.text[..found.start].chars().count()) as u16;
+        if column < view.horizontal_scroll || column >= view.horizontal_scroll + width {
+            view.horizontal_scroll = column.saturating_sub(width / 2);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_matches_ignores_case() {
+        let lines = vec![
+            ResponseLine::new("Content-Type: application/json", LineKind::Plain),
+            ResponseLine::new("  \"type\": \"TYPE\",", LineKind::Json),
+        ];
+
+        let matches = find_matches(&lines, "type");
+        assert_eq!(
+            matches,
+            vec![
+                SearchMatch {
+                    line: 0,
+                    start: 8,
+                    end: 12
+                },
+                SearchMatch {
+                    line: 1,
+                    start: 3,
+                    end: 7
+                },
+                SearchMatch {
+                    line: 1,
+                    start: 11,
+                    end: 15
+                },
+            ]
+        );
+        assert!(find_matches(&lines, "").is_empty());
+    }
+
+    #[test]
+    fn test_tls_lines_flag_expiry() {
+        use crate::app::tls::CertificateInfo;
+
+        let certificate = |subject: &str, days_left| CertificateInfo {
+            subject: subject.to_string(),
+            alt_names: vec!["DNS api.example.com".to_string()],
+            issuer: "CN=Example CA".to_string(),
+            not_before: "Jan  1 00:00:00 2026 GMT".to_string(),
+            not_after: "Dec 31 00:00:00 2026 GMT".to_string(),
+            days_left,
+            serial: "0A".to_string(),
+            sha256: "AB:CD".to_string(),
+        };
+        let tls = TlsInfo {
+            protocol: Some("TLSv1.3".to_string()),
+            http_version: Some("HTTP/2.0".to_string()),
+            verify_error: Some("certificate has expired".to_string()),
+            chain: vec![
+                certificate("CN=api.example.com", -2),
+                certificate("CN=Example Intermediate", 12),
+                certificate("CN=Example CA", 400),
+            ],
+        };
+
+        let lines = tls_lines(Some(&tls));
+        let kind_of = |prefix: &str| {
+            lines
+                .iter()
+                .filter(|line| line.text.starts_with(prefix))
+                .map(|line| line.kind)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(kind_of("HTTP version: HTTP/2.0"), [LineKind::Plain]);
+        assert_eq!(kind_of("Verification: failed"), [LineKind::Error]);
+        assert_eq!(
+            kind_of("  Valid until:"),
+            [LineKind::Error, LineKind::Notice, LineKind::Plain]
+        );
+        assert!(lines
+            .iter()
+            .any(|line| line.text == "  Alternative names: DNS api.example.com"));
+        assert_eq!(tls_lines(None)[0].kind, LineKind::Notice);
+    }
+}
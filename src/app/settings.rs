@@ -0,0 +1,463 @@
+use super::auth::{AuthField, AuthFieldKind};
+use super::models::{ProxyMode, ProxySettings, TlsSettings};
+use super::state::{App, CurrentScreen};
+
+/// What the settings popup edits.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SettingsTarget {
+    /// The proxy for every group that inherits it. TLS is only set per group and request.
+    Global,
+    /// Defaults for every request in the group with this ID.
+    Group(String),
+    /// One request, by group ID and request ID.
+    Request { group: String, id: String },
+}
+
+/// One row of the settings popup.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Field {
+    ClientCert,
+    ClientKey,
+    Password,
+    CaFiles,
+    AcceptInvalid,
+    MinVersion,
+    ProxyMode,
+    ProxyUrl,
+    ProxyUsername,
+    ProxyPassword,
+    NoProxy,
+}
+
+const TLS_FIELDS: [Field; 6] = [
+    Field::ClientCert,
+    Field::ClientKey,
+    Field::Password,
+    Field::CaFiles,
+    Field::AcceptInvalid,
+    Field::MinVersion,
+];
+
+const PROXY_FIELDS: [Field; 5] = [
+    Field::ProxyMode,
+    Field::ProxyUrl,
+    Field::ProxyUsername,
+    Field::ProxyPassword,
+    Field::NoProxy,
+];
+
+/// The settings popup while it is open. Nothing is stored until it is saved.
+#[derive(Clone, Debug)]
+pub struct SettingsForm {
+    pub target: SettingsTarget,
+    pub tls: TlsSettings,
+    pub proxy: ProxySettings,
+    /// CA files as typed, comma separated.
+    pub ca_files: String,
+    pub selected: usize,
+}
+
+impl SettingsForm {
+    fn new(target: SettingsTarget, tls: TlsSettings, proxy: ProxySettings) -> Self {
+        Self {
+            target,
+            ca_files: tls.ca_files.join(", "),
+            tls,
+            proxy,
+            selected: 0,
+        }
+    }
+
+    fn rows(&self) -> Vec<Field> {
+        let tls: &[Field] = match self.target {
+            SettingsTarget::Global => &[],
+            _ => &TLS_FIELDS,
+        };
+        tls.iter().chain(&PROXY_FIELDS).copied().collect()
+    }
+
+    fn is_request(&self) -> bool {
+        matches!(self.target, SettingsTarget::Request { .. })
+    }
+
+    fn field(&self) -> Option<Field> {
+        self.rows().get(self.selected).copied()
+    }
+
+    /// The form in display order, shown like the auth form.
+    pub fn fields(&self) -> Vec<AuthField> {
+        let field = |label, value: &str, kind| AuthField {
+            label,
+            value: value.to_string(),
+            kind,
+        };
+        self.rows()
+            .into_iter()
+            .map(|row| match row {
+                Field::ClientCert => field(
+                    "Client certificate",
+                    &self.tls.client_cert,
+                    AuthFieldKind::Text,
+                ),
+                Field::ClientKey => field("Client key", &self.tls.client_key, AuthFieldKind::Text),
+                Field::Password => field(
+                    "Certificate password",
+                    &self.tls.client_cert_password,
+                    AuthFieldKind::Secret,
+                ),
+                Field::CaFiles => field("CA files", &self.ca_files, AuthFieldKind::Text),
+                Field::AcceptInvalid => field(
+                    "Accept invalid certificates",
+                    match self.tls.accept_invalid_certs {
+                        Some(true) => "Yes",
+                        None if self.is_request() => "Inherit",
+                        Some(false) | None => "No",
+                    },
+                    AuthFieldKind::Choice,
+                ),
+                Field::MinVersion => field(
+                    "Minimum TLS version",
+                    self.tls.min_version.as_str(),
+                    AuthFieldKind::Choice,
+                ),
+                Field::ProxyMode => field("Proxy", self.proxy.mode.as_str(), AuthFieldKind::Choice),
+                Field::ProxyUrl => field("Proxy URL", &self.proxy.url, AuthFieldKind::Text),
+                Field::ProxyUsername => {
+                    field("Proxy username", &self.proxy.username, AuthFieldKind::Text)
+                }
+                Field::ProxyPassword => field(
+                    "Proxy password",
+                    &self.proxy.password,
+                    AuthFieldKind::Secret,
+                ),
+                Field::NoProxy => field("No proxy for", &self.proxy.no_proxy, AuthFieldKind::Text),
+            })
+            .collect()
+    }
+
+    fn text_mut(&mut self) -> Option<&mut String> {
+        match self.field()? {
+            Field::ClientCert => Some(&mut self.tls.client_cert),
+            Field::ClientKey => Some(&mut self.tls.client_key),
+            Field::Password => Some(&mut self.tls.client_cert_password),
+            Field::CaFiles => Some(&mut self.ca_files),
+            Field::ProxyUrl => Some(&mut self.proxy.url),
+            Field::ProxyUsername => Some(&mut self.proxy.username),
+            Field::ProxyPassword => Some(&mut self.proxy.password),
+            Field::NoProxy => Some(&mut self.proxy.no_proxy),
+            Field::AcceptInvalid | Field::MinVersion | Field::ProxyMode => None,
+        }
+    }
+
+    /// The settings as edited, with the CA list split up.
+    fn settings(&self) -> TlsSettings {
+        TlsSettings {
+            ca_files: self
+                .ca_files
+                .split(',')
+                .map(str::trim)
+                .filter(|path| !path.is_empty())
+                .map(str::to_string)
+                .collect(),
+            ..self.tls.clone()
+        }
+    }
+}
+
+impl App {
+    /// Opens the settings of the request under the tree cursor, or of its group when a
+    /// group is selected.
+    pub fn open_settings(&mut self) {
+        let (target, tls, proxy) = match self.selected_tree_request() {
+            Some((group, index)) => {
+                let request = &self.list[&group][index];
+                let (id, tls, proxy) = (
+                    request.id.clone(),
+                    request.tls.clone(),
+                    request.proxy.clone(),
+                );
+                (SettingsTarget::Request { group, id }, tls, proxy)
+            }
+            None => match self.selected_tree_group() {
+                Some(group) => {
+                    let settings = self.group_settings.get(&group).cloned().unwrap_or_default();
+                    (SettingsTarget::Group(group), settings.tls, settings.proxy)
+                }
+                None => return,
+            },
+        };
+        self.settings_form = Some(SettingsForm::new(target, tls, proxy));
+        self.current_screen = CurrentScreen::Settings;
+    }
+
+    /// Opens the global proxy settings.
+    pub fn open_global_settings(&mut self) {
+        let mut proxy = self.proxy.clone();
+        if proxy.mode == ProxyMode::Inherit {
+            // There is nothing above, inheriting means following the environment
+            proxy.mode = ProxyMode::System;
+        }
+        self.settings_form = Some(SettingsForm::new(
+            SettingsTarget::Global,
+            TlsSettings::default(),
+            proxy,
+        ));
+        self.current_screen = CurrentScreen::Settings;
+    }
+
+    pub fn close_settings(&mut self) {
+        self.settings_form = None;
+        self.current_screen = CurrentScreen::Main;
+    }
+
+    pub fn settings_next(&mut self) {
+        if let Some(form) = &mut self.settings_form {
+            form.selected = (form.selected + 1) % form.rows().len();
+        }
+    }
+
+    pub fn settings_previous(&mut self) {
+        if let Some(form) = &mut self.settings_form {
+            let count = form.rows().len();
+            form.selected = (form.selected + count - 1) % count;
+        }
+    }
+
+    /// Types `c` into the selected field. Space changes the value of a choice instead.
+    pub fn push_settings_char(&mut self, c: char) {
+        let Some(form) = &mut self.settings_form else {
+            return;
+        };
+        match form.text_mut() {
+            Some(text) => text.push(c),
+            None if c == ' ' => self.cycle_setting(),
+            None => {}
+        }
+    }
+
+    /// Adds pasted `text` to the selected field, unless it is a choice.
+    pub fn paste_settings(&mut self, text: &str) {
+        if let Some(field) = self.settings_form.as_mut().and_then(SettingsForm::text_mut) {
+            field.push_str(&text.replace(['\r', '\n'], ""));
+        }
+    }
+
+    pub fn pop_settings_char(&mut self) {
+        if let Some(text) = self.settings_form.as_mut().and_then(SettingsForm::text_mut) {
+            text.pop();
+        }
+    }
+
+    /// Moves the selected choice on to its next value.
+    pub fn cycle_setting(&mut self) {
+        let Some(form) = &mut self.settings_form else {
+            return;
+        };
+        match form.field() {
+            // Only a request has a group's choice to fall back on
+            Some(Field::AcceptInvalid) => {
+                form.tls.accept_invalid_certs = match form.tls.accept_invalid_certs {
+                    None => Some(true),
+                    Some(true) if form.is_request() => Some(false),
+                    Some(_) => None,
+                }
+            }
+            Some(Field::MinVersion) => form.tls.min_version = form.tls.min_version.next(),
+            Some(Field::ProxyMode) => {
+                let global = form.target == SettingsTarget::Global;
+                form.proxy.mode = form.proxy.mode.next(global);
+            }
+            _ => {}
+        }
+    }
+
+    /// Stores the edited settings on their group or request and closes the popup.
+    pub fn save_settings(&mut self) {
+        let Some(form) = self.settings_form.take() else {
+            return;
+        };
+        let tls = form.settings();
+        let proxy = form.proxy;
+        match form.target {
+            SettingsTarget::Global => self.proxy = proxy,
+            SettingsTarget::Group(group) => {
+                if self.list.contains_key(&group) {
+                    let settings = self.group_settings.entry(group).or_default();
+                    settings.tls = tls;
+                    settings.proxy = proxy;
+                }
+            }
+            SettingsTarget::Request { group, id } => {
+                let request = self
+                    .list
+                    .get_mut(&group)
+                    .and_then(|requests| requests.iter_mut().find(|request| request.id == id));
+                if let Some(request) = request {
+                    request.tls = tls;
+                    request.proxy = proxy;
+                }
+            }
+        }
+        self.persist_workspace();
+        self.current_screen = CurrentScreen::Main;
+    }
+
+    /// The TLS settings the current request is sent with, its group's included.
+    pub fn current_tls(&self) -> Option<TlsSettings> {
+        let request = self.get_current_request()?;
+        let (group, _) = self.find_request(&request.id)?;
+        let group_tls = self
+            .group_settings
+            .get(&group)
+            .map(|settings| settings.tls.clone())
+            .unwrap_or_default();
+        Some(request.tls.over(&group_tls))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::models::{ApiRequest, RequestType, TlsVersion};
+
+    /// An app with one group, "internal", and its ID.
+    fn app() -> (App, String) {
+        let mut app = App::with_workspace(None);
+        let group = app.add_group("internal".to_string());
+        app.list
+            .get_mut(&group)
+            .unwrap()
+            .push(ApiRequest::new("health".to_string(), RequestType::GET));
+        (app, group)
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        text.chars().for_each(|c| app.push_settings_char(c));
+    }
+
+    #[test]
+    fn test_edit_group_settings() {
+        let (mut app, group) = app();
+        let tree = app.build_tree();
+        let group_node = tree.root().query(&format!("group-{}", group)).unwrap();
+        app.tree_state.select(&tree, group_node);
+
+        app.open_settings();
+        assert!(app.current_screen == CurrentScreen::Settings);
+        type_text(&mut app, "~/certs/client.p12");
+        app.settings_next();
+        app.settings_next();
+        type_text(&mut app, "secret");
+        app.settings_next();
+        type_text(&mut app, "ca.pem,, ~/extra ca.pem ");
+        app.settings_next();
+        app.push_settings_char(' ');
+        app.settings_next();
+        app.cycle_setting();
+        app.save_settings();
+
+        assert!(app.current_screen == CurrentScreen::Main);
+        let tls = &app.group_settings[&group].tls;
+        assert_eq!(tls.client_cert, "~/certs/client.p12");
+        assert_eq!(tls.client_cert_password, "secret");
+        assert_eq!(tls.ca_files, ["ca.pem", "~/extra ca.pem"]);
+        assert_eq!(tls.accept_invalid_certs, Some(true));
+        assert_eq!(tls.min_version, TlsVersion::Tls12);
+        assert_eq!(app.current_tls(), None);
+    }
+
+    #[test]
+    fn test_request_settings_and_cancel() {
+        let (mut app, group) = app();
+        let id = app.list[&group][0].id.clone();
+        app.group_settings
+            .entry(group.clone())
+            .or_default()
+            .tls
+            .ca_files = vec!["group-ca.pem".to_string()];
+        app.selected_group_index = Some(0);
+        app.selected_request_index = Some(0);
+
+        let tree = app.build_tree();
+        let group_node = tree.root().query(&format!("group-{}", group)).unwrap();
+        app.tree_state.open(&tree, group_node);
+        let request_node = tree.root().query(&format!("request-{}", id)).unwrap();
+        app.tree_state.select(&tree, request_node);
+
+        app.open_settings();
+        // Back past the proxy rows to "Accept invalid certificates"
+        (0..7).for_each(|_| app.settings_previous());
+        app.push_settings_char(' ');
+        app.close_settings();
+        assert_eq!(app.list[&group][0].tls.accept_invalid_certs, None);
+
+        app.open_settings();
+        assert_eq!(
+            app.settings_form.as_ref().unwrap().target,
+            SettingsTarget::Request { group, id }
+        );
+        (0..7).for_each(|_| app.settings_previous());
+        app.cycle_setting();
+        app.settings_previous();
+        type_text(&mut app, "request-ca.pem");
+        app.pop_settings_char();
+        app.save_settings();
+
+        let tls = app.current_tls().unwrap();
+        assert_eq!(tls.accept_invalid_certs, Some(true));
+        assert_eq!(tls.ca_files, ["group-ca.pem", "request-ca.pe"]);
+        assert_eq!(app.prepare_request().unwrap().tls, tls);
+    }
+
+    #[test]
+    fn test_proxy_settings_at_each_level() {
+        let (mut app, group) = app();
+        app.selected_group_index = Some(0);
+        app.selected_request_index = Some(0);
+
+        app.open_global_settings();
+        assert_eq!(app.settings_form.as_ref().unwrap().fields().len(), 5);
+        // System, Direct, Custom
+        app.cycle_setting();
+        app.cycle_setting();
+        app.settings_next();
+        type_text(&mut app, "socks5://proxy.corp:1080");
+        app.settings_next();
+        type_text(&mut app, "alice");
+        app.settings_next();
+        app.paste_settings("pass\nword");
+        app.settings_next();
+        type_text(&mut app, "localhost,.corp");
+        app.save_settings();
+
+        assert_eq!(app.proxy.mode, ProxyMode::Custom);
+        assert_eq!(app.proxy.password, "password");
+        let proxy = app.prepare_request().unwrap().proxy;
+        let server = proxy.server_for("https://api.example.com").unwrap();
+        assert_eq!(server.username, "alice");
+        assert_eq!(server.display(), "socks5://proxy.corp:1080");
+        assert_eq!(proxy.server_for("http://git.corp/"), None);
+
+        // The group turns the proxy off, the request inherits that
+        let tree = app.build_tree();
+        let group_node = tree.root().query(&format!("group-{}", group)).unwrap();
+        app.tree_state.select(&tree, group_node);
+        app.open_settings();
+        assert_eq!(app.settings_form.as_ref().unwrap().fields().len(), 11);
+        (0..5).for_each(|_| app.settings_previous());
+        // Inherit, System, Direct
+        app.push_settings_char(' ');
+        app.cycle_setting();
+        app.save_settings();
+        assert_eq!(app.group_settings[&group].proxy.mode, ProxyMode::Direct);
+        assert!(app.prepare_request().unwrap().proxy.is_empty());
+
+        // Globally, Custom goes back to System rather than Inherit
+        app.open_global_settings();
+        app.cycle_setting();
+        assert_eq!(
+            app.settings_form.as_ref().unwrap().proxy.mode,
+            ProxyMode::System
+        );
+    }
+}
@@ -1,6 +1,5 @@
-use base64::{prelude::BASE64_STANDARD, Engine};
-use crossterm::event::{self, Event};
-use rat_tree_view::{TreeState, TreeWidget};
+use crossterm::event::{self, Event, KeyCode};
+use rat_tree_view::{Tree, TreeState, TreeWidget};
 use ratatui::{
     layout::Rect,
     style::{Color, Style},
@@ -8,11 +7,26 @@ use ratatui::{
     Frame,
 };
 use std::collections::{HashMap, HashSet};
-use std::error::Error;
-use tui_textarea::TextArea;
-
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tui_textarea::{CursorMove, TextArea};
+
+use super::auth::{apply_auth, AuthFieldKind};
+use super::curl;
+use super::environment::{resolve_details, Environment};
+use super::error::{ErrorKind, RequestError};
+use super::history::{HistoryEntry, HistoryView, PendingRequest};
+use super::json_tree::JsonNode;
 use super::models::*;
-use super::requests::RequestResponse;
+use super::oauth::PendingAuthorization;
+use super::postman::{self, ImportSummary, PostmanImport};
+use super::proxy::ProxyConfig;
+use super::requests::{execute, PreparedRequest, RequestResponse, RequestResult, RequestSender};
+use super::response_view::ResponseView;
+use super::settings::SettingsForm;
+use super::storage::Workspace;
+use super::tree::{group_node_id, request_node_id};
 use super::ui_state::*;
 
 #[derive(PartialEq)]
@@ -24,6 +38,13 @@ pub enum CurrentScreen {
     AddingRequest,
     RequestDetail,
     Exiting,
+    Environments,
+    Importing,
+    CurlExport,
+    CurlImport,
+    ResponseTree,
+    ResponseDiff,
+    Settings,
 }
 
 pub enum Groups {
@@ -34,11 +55,21 @@ pub struct App {
     pub key_input: String,
     pub request_name_input: String,
     pub current_screen: CurrentScreen,
+    /// Requests by group ID.
     pub list: HashMap<String, Vec<ApiRequest>>,
+    /// Group names by group ID, kept in step with `list` by `update_groups_vec`. Names
+    /// don't have to be unique.
+    pub group_names: HashMap<String, String>,
+    /// Settings shared by each group's requests by group ID, kept in step like `group_names`.
+    pub group_settings: HashMap<String, GroupSettings>,
+    /// Proxy settings for groups that inherit them.
+    pub proxy: ProxySettings,
     pub groups: Option<Groups>,
     pub selected_index: usize,
+    /// Group IDs, ordered by group name.
     pub groups_vec: Vec<String>,
     pub selected_request_type: RequestType,
+    /// ID of the group a new request is added to.
     pub selected_group: Option<String>,
     pub minimized_groups: HashSet<String>,
     pub selected_group_index: Option<usize>,
@@ -47,8 +78,8 @@ pub struct App {
     pub temp_selected_request_index: Option<usize>,
     pub url_textarea: TextArea<'static>,
     pub body_textarea: TextArea<'static>,
-    pub auth_username_textarea: TextArea<'static>,
-    pub auth_password_textarea: TextArea<'static>,
+    /// One textarea per field of the selected request's auth form.
+    pub auth_textareas: Vec<TextArea<'static>>,
     pub tree_state: TreeState,
     pub active_panel: ActivePanel,
     pub password_visible: bool,
@@ -60,29 +91,78 @@ pub struct App {
     pub params_value_input: String,
     pub adding_params: bool,
     pub params_input_mode: ParameterInputMode,
+    pub selected_param_index: usize,
+    /// Index of the param being edited in the params popup, `None` when adding.
+    pub editing_param: Option<usize>,
+    pub selected_header_index: usize,
+    /// Index of the header being edited in the header popup, `None` when adding.
+    pub editing_header: Option<usize>,
     pub is_sending: bool,
+    pub sender: RequestSender,
+    /// The response shown in the response pane, always one of the current request's.
     pub last_response: Option<RequestResponse>,
+    pub last_error: Option<RequestError>,
+    pub response_view: ResponseView,
+    /// Recent responses per request ID, newest first.
+    pub history: HashMap<String, Vec<HistoryEntry>>,
+    pub history_view: HistoryView,
+    /// Where the response of the in-flight request will be filed.
+    pending_request: Option<PendingRequest>,
+    /// OAuth 2.0 sign-in waiting for the browser.
+    pub authorization: Option<PendingAuthorization>,
+    /// Explorer tree of the last response while it is open.
+    pub json_tree: Option<Tree<JsonNode>>,
+    pub json_tree_state: TreeState,
+    pub environments: Vec<Environment>,
+    pub active_environment: Option<usize>,
+    pub selected_environment_index: usize,
+    pub environment_name_input: String,
+    pub adding_environment: bool,
+    pub variable_key_input: String,
+    pub variable_value_input: String,
+    pub adding_variable: bool,
+    pub variable_input_mode: VariableInputMode,
+    pub import_path_input: String,
+    pub import_result: Option<Result<ImportSummary, String>>,
+    pub curl_export: Option<String>,
+    pub curl_input: String,
+    pub curl_error: Option<String>,
+    /// TLS settings being edited in the settings popup.
+    pub settings_form: Option<SettingsForm>,
+    /// Text waiting to be copied to the system clipboard by the terminal loop.
+    pub clipboard: Option<String>,
+    /// Why the last copy to the clipboard failed, shown in the status line.
+    pub clipboard_error: Option<String>,
+    /// URL waiting to be opened in the browser by the terminal loop.
+    pub browser_url: Option<String>,
+    pub workspace_path: Option<PathBuf>,
+    pub workspace_error: Option<String>,
+    last_saved_workspace: Option<String>,
 }
 
 impl App {
+    /// Creates the app backed by the workspace file in the user's data directory.
     pub fn new() -> Self {
+        Self::with_workspace(Workspace::default_path())
+    }
+
+    /// Creates the app backed by the workspace file at `workspace_path`, loading it if it
+    /// exists. `None` keeps everything in memory.
+    pub fn with_workspace(workspace_path: Option<PathBuf>) -> Self {
         let mut url_textarea = TextArea::default();
         url_textarea.set_cursor_line_style(Style::default());
 
         let mut body_textarea = TextArea::default();
         body_textarea.set_cursor_line_style(Style::default());
 
-        let mut auth_username_textarea = TextArea::default();
-        auth_username_textarea.set_cursor_line_style(Style::default());
-
-        let mut auth_password_textarea = TextArea::default();
-        auth_password_textarea.set_cursor_line_style(Style::default());
-
         let mut app = Self {
             key_input: String::new(),
             request_name_input: String::new(),
             current_screen: CurrentScreen::Main,
             list: HashMap::new(),
+            group_names: HashMap::new(),
+            group_settings: HashMap::new(),
+            proxy: ProxySettings::default(),
             groups: None,
             selected_index: 0,
             groups_vec: Vec::new(),
@@ -95,8 +175,7 @@ impl App {
             temp_selected_request_index: None,
             url_textarea,
             body_textarea,
-            auth_username_textarea,
-            auth_password_textarea,
+            auth_textareas: Vec::new(),
             tree_state: TreeState::default(),
             active_panel: ActivePanel::Tree,
             password_visible: false,
@@ -108,97 +187,344 @@ impl App {
             params_value_input: String::new(),
             adding_params: false,
             params_input_mode: ParameterInputMode::Key,
+            selected_param_index: 0,
+            editing_param: None,
+            selected_header_index: 0,
+            editing_header: None,
             is_sending: false,
+            sender: RequestSender::new(),
             last_response: None,
+            last_error: None,
+            response_view: ResponseView::default(),
+            history: HashMap::new(),
+            history_view: HistoryView::default(),
+            pending_request: None,
+            authorization: None,
+            json_tree: None,
+            json_tree_state: TreeState::default(),
+            environments: Vec::new(),
+            active_environment: None,
+            selected_environment_index: 0,
+            environment_name_input: String::new(),
+            adding_environment: false,
+            variable_key_input: String::new(),
+            variable_value_input: String::new(),
+            adding_variable: false,
+            variable_input_mode: VariableInputMode::Key,
+            import_path_input: String::new(),
+            import_result: None,
+            curl_export: None,
+            curl_input: String::new(),
+            curl_error: None,
+            settings_form: None,
+            clipboard: None,
+            clipboard_error: None,
+            browser_url: None,
+            workspace_path,
+            workspace_error: None,
+            last_saved_workspace: None,
         };
 
+        app.load_workspace();
+
         let initial_tree = app.build_tree();
         app.tree_state.select(&initial_tree, initial_tree.root());
 
         app
     }
 
-    pub async fn send_request(&mut self) -> Result<(), Box<dyn Error>> {
-        // First get all data we need
-        let request_data = if let Some(request) = self.get_selected_request() {
-            Some((
-                request.request_type.clone(),
-                request.details.url.clone(),
-                request.details.body.clone(),
-                request.details.headers.clone(),
-                request.details.params.clone(),
-            ))
-        } else {
-            None
+    fn load_workspace(&mut self) {
+        let Some(path) = self.workspace_path.clone() else {
+            return;
         };
 
-        // Then use the data to send the request
-        if let Some((request_type, url, body, headers, params)) = request_data {
-            self.is_sending = true;
+        match Workspace::load(&path) {
+            Ok(Some(mut workspace)) => {
+                self.environments = std::mem::take(&mut workspace.environments);
+                self.active_environment = workspace
+                    .active_environment
+                    .take()
+                    .and_then(|name| self.environments.iter().position(|e| e.name == name));
+                self.group_names = workspace.group_names();
+                self.group_settings = workspace.group_settings();
+                self.proxy = std::mem::take(&mut workspace.proxy);
+                self.list = workspace.into_list();
+                self.update_groups_vec();
+                self.last_saved_workspace = self.workspace_snapshot().to_json().ok();
+            }
+            Ok(None) => {}
+            Err(e) => {
+                // Move the unreadable file aside so the next save doesn't destroy it
+                let backup = path.with_extension("json.bak");
+                let _ = fs::rename(&path, &backup);
+                self.workspace_error = Some(format!(
+                    "Could not load workspace ({}), moved it to {}",
+                    e,
+                    backup.display()
+                ));
+            }
+        }
+    }
 
-            let client = reqwest::Client::new();
+    fn workspace_snapshot(&self) -> Workspace {
+        let mut workspace = Workspace::from_list(&self.list, &self.group_names);
+        for group in &mut workspace.groups {
+            if let Some(settings) = self.group_settings.get(&group.id) {
+                group.settings = settings.clone();
+            }
+        }
+        workspace.environments = self.environments.clone();
+        workspace.active_environment = self
+            .active_environment
+            .and_then(|idx| self.environments.get(idx))
+            .map(|env| env.name.clone());
+        workspace.proxy = self.proxy.clone();
+        workspace
+    }
 
-            let mut builder = match request_type {
-                RequestType::GET => client.get(&url),
-                RequestType::POST => client.post(&url),
-                RequestType::PUT => client.put(&url),
-                RequestType::DELETE => client.delete(&url),
-                RequestType::PATCH => client.patch(&url),
-            };
+    /// Writes the workspace to disk if anything changed since the last save.
+    pub fn persist_workspace(&mut self) {
+        let Some(path) = self.workspace_path.clone() else {
+            return;
+        };
+
+        let json = match self.workspace_snapshot().to_json() {
+            Ok(json) => json,
+            Err(e) => {
+                self.workspace_error = Some(format!("Could not save workspace: {}", e));
+                return;
+            }
+        };
 
-            // Add headers
-            for (key, value) in headers {
-                builder = builder.header(key, value);
+        if self.last_saved_workspace.as_deref() == Some(json.as_str()) {
+            return;
+        }
+
+        match super::storage::write_atomic(&path, json.as_bytes()) {
+            Ok(()) => {
+                self.last_saved_workspace = Some(json);
+                self.workspace_error = None;
+            }
+            Err(e) => {
+                self.workspace_error = Some(format!("Could not save workspace: {}", e));
             }
+        }
+    }
+
+    /// Resolves the selected request against the active environment. Undefined variables
+    /// are reported through `last_error` and nothing is returned.
+    pub fn prepare_request(&mut self) -> Option<PreparedRequest> {
+        let variables = self
+            .get_active_environment()
+            .map(|env| env.variables.clone())
+            .unwrap_or_default();
+
+        let request = self.get_selected_request()?;
+        match resolve_details(&request.details.enabled_only(), &variables) {
+            Ok(mut details) => {
+                apply_auth(&mut details);
+                let oauth2 = match &details.auth_details {
+                    AuthDetails::OAuth2(oauth2) => Some(oauth2.clone()),
+                    _ => None,
+                };
+                let digest = match &details.auth_details {
+                    AuthDetails::Digest(digest) if !digest.username.is_empty() => {
+                        Some(digest.clone())
+                    }
+                    _ => None,
+                };
+                let aws_sigv4 = match &details.auth_details {
+                    AuthDetails::AwsSigV4(aws) if !aws.access_key.is_empty() => Some(aws.clone()),
+                    _ => None,
+                };
+                let hmac = match &details.auth_details {
+                    AuthDetails::Hmac(hmac) if !hmac.signature_header.is_empty() => {
+                        Some(hmac.clone())
+                    }
+                    _ => None,
+                };
+                let group_settings = self
+                    .selected_group_index
+                    .and_then(|index| self.groups_vec.get(index))
+                    .and_then(|group| self.group_settings.get(group))
+                    .cloned()
+                    .unwrap_or_default();
+                let proxy = request.proxy.over(&group_settings.proxy.over(&self.proxy));
+                let pairs = |entries: Vec<KeyValue>| {
+                    entries
+                        .into_iter()
+                        .map(|entry| (entry.key, entry.value))
+                        .collect()
+                };
+                Some(PreparedRequest {
+                    request_type: request.request_type.clone(),
+                    url: details.url,
+                    body: details.body,
+                    headers: pairs(details.headers),
+                    params: pairs(details.params),
+                    oauth2,
+                    digest,
+                    aws_sigv4,
+                    hmac,
+                    tls: request.tls.over(&group_settings.tls),
+                    proxy: ProxyConfig::resolve(&proxy, |name| std::env::var(name).ok()),
+                })
+            }
+            Err(e) => {
+                self.last_response = None;
+                self.last_error = Some(RequestError::new(
+                    ErrorKind::UndefinedVariable,
+                    e.to_string(),
+                ));
+                None
+            }
+        }
+    }
+
+    /// Sends the selected request and waits for it. The TUI uses `start_request` instead
+    /// so it keeps drawing while the request is in flight.
+    pub async fn send_request(&mut self) {
+        if let Some(request) = self.prepare_request() {
+            self.is_sending = true;
+            self.pending_request = self.pending_for(&request);
+            let cache = self.sender.auth_cache.clone();
+            let result = execute(request, &cache).await;
+            self.store_result(result);
+        }
+    }
 
-            // Add query parameters
-            for (key, value) in params {
-                builder = builder.query(&[(key, value)]);
+    /// Dispatches the selected request in the background. The result is picked up by
+    /// `poll_request`.
+    pub fn start_request(&mut self) {
+        if let Some(request) = self.prepare_request() {
+            let pending = self.pending_for(&request);
+            match self.sender.dispatch(request) {
+                Ok(()) => {
+                    self.is_sending = true;
+                    self.pending_request = pending;
+                }
+                Err(e) => {
+                    self.last_response = None;
+                    self.last_error = Some(RequestError::new(
+                        ErrorKind::Internal,
+                        format!("Could not start the request runtime: {}", e),
+                    ));
+                }
             }
+        }
+    }
 
-            // Add body for non-GET requests
-            if !matches!(request_type, RequestType::GET) {
-                builder = builder.body(body);
+    /// Stores the result of the in-flight request if it has arrived. Returns true if it did.
+    pub fn poll_request(&mut self) -> bool {
+        match self.sender.try_recv() {
+            Some(result) => {
+                self.store_result(result);
+                true
             }
+            None => false,
+        }
+    }
+
+    pub fn cancel_request(&mut self) {
+        self.pending_request = None;
+        if let Some(elapsed) = self.sender.cancel() {
+            self.last_response = None;
+            self.last_error = Some(
+                RequestError::new(ErrorKind::Cancelled, "Request cancelled").with_elapsed(elapsed),
+            );
+        }
+        self.is_sending = false;
+    }
 
-            let start = std::time::Instant::now();
-            let response = builder.send().await?;
-            let duration = start.elapsed();
+    /// Remembers which request `request` was prepared from, for filing its response.
+    fn pending_for(&self, request: &PreparedRequest) -> Option<PendingRequest> {
+        Some(PendingRequest {
+            request_id: self.get_selected_request()?.id.clone(),
+            request: request.clone(),
+            sent_at: SystemTime::now(),
+        })
+    }
 
-            // Store response
-            self.last_response = Some(RequestResponse {
-                status: response.status().as_u16(),
-                status_text: response.status().to_string(),
-                headers: response
-                    .headers()
-                    .iter()
-                    .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
-                    .collect(),
-                body: response.text().await?,
-                time_taken: duration,
-            });
+    fn store_result(&mut self, result: RequestResult) {
+        // Tokens fetched or renewed on the way are saved on the request
+        if let Some((request_id, auth)) = self
+            .pending_request
+            .as_ref()
+            .and_then(|pending| Some((pending.request_id.clone(), pending.request.oauth2.clone()?)))
+        {
+            self.sync_oauth2_tokens(&request_id, &auth);
+        }
 
-            self.is_sending = false;
+        match (result, self.pending_request.take()) {
+            (Ok(response), Some(pending)) => self.record_response(pending, response),
+            (Ok(response), None) => {
+                self.last_error = None;
+                self.last_response = Some(response);
+            }
+            (Err(e), pending) => {
+                // Errors only show while the request that failed is selected
+                let is_current = match &pending {
+                    Some(pending) => self.is_current_request(&pending.request_id),
+                    None => true,
+                };
+                if is_current {
+                    self.last_response = None;
+                    self.last_error = Some(e);
+                }
+            }
         }
-        Ok(())
+        self.is_sending = false;
+
+        // A new response starts at the top, with the search run again over it
+        self.reset_response_view();
     }
 
     pub fn start_adding_params(&mut self) {
         self.adding_params = true;
+        self.editing_param = None;
         self.params_key_input.clear();
         self.params_value_input.clear();
         self.params_input_mode = ParameterInputMode::Key;
     }
 
+    /// Opens the params popup pre-filled with the selected param.
+    pub fn start_editing_param(&mut self) {
+        let index = self.selected_param_index;
+        let Some(param) = self
+            .get_selected_request()
+            .and_then(|request| request.details.params.get(index))
+            .cloned()
+        else {
+            return;
+        };
+
+        self.adding_params = true;
+        self.params_key_input = param.key;
+        self.params_value_input = param.value;
+        self.params_input_mode = ParameterInputMode::Value;
+        self.editing_param = Some(index);
+    }
+
     pub fn save_params(&mut self) {
         // Clone values first to avoid borrow conflicts
         let key = self.params_key_input.clone();
         let value = self.params_value_input.clone();
+        let editing = self.editing_param.take();
 
         if !key.is_empty() && !value.is_empty() {
             if let Some(request) = self.get_selected_request_mut() {
-                request.details.params.insert(key, value);
+                let params = &mut request.details.params;
+                // An edited param keeps its place and enabled state
+                match editing.and_then(|index| params.get_mut(index)) {
+                    Some(param) => {
+                        param.key = key;
+                        param.value = value;
+                    }
+                    None => params.push(KeyValue::new(key, value)),
+                }
             }
+            self.refresh_url_textarea();
+            self.persist_workspace();
         }
 
         self.adding_params = false;
@@ -206,6 +532,40 @@ impl App {
         self.params_value_input.clear();
     }
 
+    pub fn delete_selected_param(&mut self) {
+        let index = self.selected_param_index;
+        if let Some(request) = self.get_selected_request_mut() {
+            let params = &mut request.details.params;
+            if index < params.len() {
+                params.remove(index);
+            }
+            let remaining = params.len();
+            self.selected_param_index = index.min(remaining.saturating_sub(1));
+        }
+        self.refresh_url_textarea();
+        self.persist_workspace();
+    }
+
+    /// Enables or disables the selected param. Disabled params are kept but not sent.
+    pub fn toggle_selected_param(&mut self) {
+        let index = self.selected_param_index;
+        if let Some(param) = self
+            .get_selected_request_mut()
+            .and_then(|request| request.details.params.get_mut(index))
+        {
+            param.enabled = !param.enabled;
+        }
+        self.refresh_url_textarea();
+        self.persist_workspace();
+    }
+
+    /// Rewrites the URL field from the params, which are encoded into its query string.
+    fn refresh_url_textarea(&mut self) {
+        if let Some(request) = self.get_selected_request() {
+            self.url_textarea = TextArea::from(vec![request.details.url_with_query()]);
+        }
+    }
+
     pub fn toggle_params_input_mode(&mut self) {
         self.params_input_mode = match self.params_input_mode {
             ParameterInputMode::Key => ParameterInputMode::Value,
@@ -215,22 +575,51 @@ impl App {
 
     pub fn start_adding_header(&mut self) {
         self.adding_header = true;
+        self.editing_header = None;
         self.header_key_input.clear();
         self.header_value_input.clear();
         self.header_input_mode = HeaderInputMode::Key;
     }
 
+    /// Opens the header popup pre-filled with the selected header.
+    pub fn start_editing_header(&mut self) {
+        let index = self.selected_header_index;
+        let Some(header) = self
+            .get_selected_request()
+            .and_then(|request| request.details.headers.get(index))
+            .cloned()
+        else {
+            return;
+        };
+
+        self.adding_header = true;
+        self.header_key_input = header.key;
+        self.header_value_input = header.value;
+        self.header_input_mode = HeaderInputMode::Value;
+        self.editing_header = Some(index);
+    }
+
     pub fn save_header(&mut self) {
         // Clone values first to avoid borrow conflicts
         let key = self.header_key_input.clone();
         let value = self.header_value_input.clone();
+        let editing = self.editing_header.take();
 
         if !key.is_empty() && !value.is_empty() {
             // Check authorization outside of the mutable borrow
             if key.to_lowercase() != "authorization" {
                 if let Some(request) = self.get_selected_request_mut() {
-                    request.details.headers.insert(key, value);
+                    let headers = &mut request.details.headers;
+                    // An edited header keeps its place and enabled state
+                    match editing.and_then(|index| headers.get_mut(index)) {
+                        Some(header) => {
+                            header.key = key;
+                            header.value = value;
+                        }
+                        None => headers.push(KeyValue::new(key, value)),
+                    }
                 }
+                self.persist_workspace();
             }
         }
 
@@ -239,6 +628,31 @@ impl App {
         self.header_value_input.clear();
     }
 
+    pub fn delete_selected_header(&mut self) {
+        let index = self.selected_header_index;
+        if let Some(request) = self.get_selected_request_mut() {
+            let headers = &mut request.details.headers;
+            if index < headers.len() {
+                headers.remove(index);
+            }
+            let remaining = headers.len();
+            self.selected_header_index = index.min(remaining.saturating_sub(1));
+        }
+        self.persist_workspace();
+    }
+
+    /// Enables or disables the selected header. Disabled headers are kept but not sent.
+    pub fn toggle_selected_header(&mut self) {
+        let index = self.selected_header_index;
+        if let Some(header) = self
+            .get_selected_request_mut()
+            .and_then(|request| request.details.headers.get_mut(index))
+        {
+            header.enabled = !header.enabled;
+        }
+        self.persist_workspace();
+    }
+
     pub fn toggle_header_input_mode(&mut self) {
         self.header_input_mode = match self.header_input_mode {
             HeaderInputMode::Key => HeaderInputMode::Value,
@@ -257,12 +671,51 @@ impl App {
         match self.current_detail_field {
             DetailField::Url => self.url_textarea.cursor() == (0, 0),
             DetailField::Body => self.body_textarea.cursor() == (0, 0),
-            DetailField::AuthUsername => self.auth_username_textarea.cursor() == (0, 0),
-            DetailField::AuthPassword => self.auth_password_textarea.cursor() == (0, 0),
+            DetailField::AuthField(index) => match self.auth_textareas.get(index) {
+                Some(textarea) => textarea.cursor() == (0, 0),
+                None => true,
+            },
             _ => true, // For Headers and other fields without text areas, consider them always "at start"
         }
     }
 
+    /// Moves the cursor within a multi-line field, or the selection within the params and
+    /// headers lists. Returns false when it is already on the first (or last) row, so the key
+    /// can move focus to the neighbouring field instead.
+    pub fn handle_vertical_in_field(&mut self, key: event::KeyEvent) -> bool {
+        let (rows, selected) = match self.current_detail_field {
+            DetailField::Body => {
+                let (row, _) = self.body_textarea.cursor();
+                (self.body_textarea.lines().len(), row)
+            }
+            DetailField::Params => (
+                self.get_selected_request()
+                    .map_or(0, |request| request.details.params.len()),
+                self.selected_param_index,
+            ),
+            DetailField::Headers => (
+                self.get_selected_request()
+                    .map_or(0, |request| request.details.headers.len()),
+                self.selected_header_index,
+            ),
+            _ => return false,
+        };
+
+        let (target, cursor_move) = match key.code {
+            KeyCode::Up if selected > 0 => (selected - 1, CursorMove::Up),
+            KeyCode::Down if selected + 1 < rows => (selected + 1, CursorMove::Down),
+            _ => return false,
+        };
+
+        match self.current_detail_field {
+            DetailField::Body => self.body_textarea.move_cursor(cursor_move),
+            DetailField::Params => self.selected_param_index = target,
+            DetailField::Headers => self.selected_header_index = target,
+            _ => {}
+        }
+        true
+    }
+
     // We can also add a method to handle the left arrow key specifically
     pub fn handle_left_in_textarea(&mut self, key: event::KeyEvent) -> bool {
         // If we're at the start of the text and press left, switch to tree view
@@ -274,13 +727,20 @@ impl App {
             match self.current_detail_field {
                 DetailField::Url => self.url_textarea.input(Event::Key(key)),
                 DetailField::Body => self.body_textarea.input(Event::Key(key)),
-                DetailField::AuthUsername => self.auth_username_textarea.input(Event::Key(key)),
-                DetailField::AuthPassword => self.auth_password_textarea.input(Event::Key(key)),
+                DetailField::AuthField(index) => self.auth_field_input(index, key),
                 _ => false,
             }
         }
     }
 
+    /// Passes `key` to the textarea of the auth field at `index`. Returns false if there is
+    /// none.
+    pub fn auth_field_input(&mut self, index: usize, key: event::KeyEvent) -> bool {
+        self.auth_textareas
+            .get_mut(index)
+            .is_some_and(|textarea| textarea.input(Event::Key(key)))
+    }
+
     pub fn render_tree_view(&mut self, frame: &mut Frame, area: Rect) {
         let tree = self.build_tree();
         let widget = TreeWidget::new(&tree)
@@ -292,32 +752,48 @@ impl App {
         frame.render_stateful_widget(widget, area, &mut self.tree_state);
     }
 
+    /// Finds the request with ID `request_id`, as its group ID and index in the group.
+    pub fn find_request(&self, request_id: &str) -> Option<(String, usize)> {
+        self.list.iter().find_map(|(group_id, requests)| {
+            requests
+                .iter()
+                .position(|request| request.id == request_id)
+                .map(|idx| (group_id.clone(), idx))
+        })
+    }
+
+    /// Name of the group with ID `group_id`.
+    pub fn group_name(&self, group_id: &str) -> Option<&str> {
+        self.group_names.get(group_id).map(String::as_str)
+    }
+
+    /// ID of the first group called `name`, in the order groups are listed.
+    fn find_group_named(&self, name: &str) -> Option<String> {
+        self.groups_vec
+            .iter()
+            .find(|group_id| self.group_name(group_id) == Some(name))
+            .cloned()
+    }
+
+    /// The request under the tree cursor, as its group ID and index in the group.
+    pub fn selected_tree_request(&self) -> Option<(String, usize)> {
+        let selected_id = self.tree_state.selected()?;
+        let request_id = selected_id.strip_prefix("request-")?;
+        self.find_request(request_id)
+    }
+
     // Handle selecting a request from the tree
     pub fn handle_tree_selection(&mut self) -> Option<(String, usize)> {
         let selected_id = self.tree_state.selected()?;
 
-        // Check if it's a request node
-        if selected_id.starts_with("request-") {
-            let parts: Vec<&str> = selected_id.splitn(3, '-').collect();
-            if parts.len() == 3 {
-                let group_name = parts[1].to_string();
-
-                if let Some(requests) = self.list.get(&group_name) {
-                    for (idx, request) in requests.iter().enumerate() {
-                        if request.name == parts[2] {
-                            // Update selected group index
-                            self.selected_group_index =
-                                self.groups_vec.iter().position(|g| g == &group_name);
-                            return Some((group_name, idx));
-                        }
-                    }
-                }
-            }
-        } else if selected_id.starts_with("group-") {
-            let group_name = selected_id.strip_prefix("group-")?.to_string();
-            self.selected_group_index = self.groups_vec.iter().position(|g| g == &group_name);
+        if let Some(group_id) = selected_id.strip_prefix("group-") {
+            self.selected_group_index = self.groups_vec.iter().position(|g| g == group_id);
+            return None;
         }
-        None
+
+        let (group_id, idx) = self.selected_tree_request()?;
+        self.selected_group_index = self.groups_vec.iter().position(|g| g == &group_id);
+        Some((group_id, idx))
     }
 
     pub fn switch_to_tree(&mut self) {
@@ -334,11 +810,11 @@ impl App {
 
     // Add this to handle opening request details when Enter is pressed
     pub fn handle_tree_enter(&mut self) {
-        if let Some((group_name, request_idx)) = self.handle_tree_selection() {
+        if let Some((group_id, request_idx)) = self.handle_tree_selection() {
             self.selected_group_index = Some(
                 self.groups_vec
                     .iter()
-                    .position(|g| g == &group_name)
+                    .position(|g| g == &group_id)
                     .unwrap_or(0),
             );
             self.selected_request_index = Some(request_idx);
@@ -350,8 +826,8 @@ impl App {
         if let (Some(group_idx), Some(request_idx)) =
             (self.selected_group_index, self.selected_request_index)
         {
-            if let Some(group_name) = self.groups_vec.get(group_idx) {
-                if let Some(requests) = self.list.get(group_name) {
+            if let Some(group_id) = self.groups_vec.get(group_idx) {
+                if let Some(requests) = self.list.get(group_id) {
                     if let Some(request) = requests.get(request_idx) {
                         return request.details.auth_type.as_str().to_string();
                     }
@@ -368,36 +844,33 @@ impl App {
         }
 
         // If no selected request, check the tree selection
-        if let Some(selected_id) = self.tree_state.selected() {
-            if selected_id.starts_with("request-") {
-                let parts: Vec<&str> = selected_id.splitn(3, '-').collect();
-                if parts.len() == 3 {
-                    let group_name = parts[1].to_string();
-                    if let Some(requests) = self.list.get(&group_name) {
-                        return requests.iter().find(|r| r.name == parts[2]);
-                    }
-                }
-            }
-        }
-        None
+        let (group_id, request_idx) = self.selected_tree_request()?;
+        self.list.get(&group_id)?.get(request_idx)
+    }
+
+    pub fn is_current_request(&self, request_id: &str) -> bool {
+        self.get_current_request()
+            .is_some_and(|request| request.id == request_id)
     }
 
     fn update_selection_from_tree(&mut self) {
+        let previous = self.get_current_request().map(|request| request.id.clone());
+        self.select_request_from_tree();
+
+        // The response pane follows the selection
+        if self.get_current_request().map(|request| &request.id) != previous.as_ref() {
+            self.show_current_history();
+        }
+    }
+
+    fn select_request_from_tree(&mut self) {
         if let Some(selected_id) = self.tree_state.selected() {
             if selected_id.starts_with("request-") {
-                let parts: Vec<&str> = selected_id.splitn(3, '-').collect();
-                if parts.len() == 3 {
-                    let group_name = parts[1].to_string();
-                    if let Some(group_idx) = self.groups_vec.iter().position(|g| g == &group_name) {
-                        if let Some(requests) = self.list.get(&group_name) {
-                            if let Some(request_idx) =
-                                requests.iter().position(|r| r.name == parts[2])
-                            {
-                                self.selected_group_index = Some(group_idx);
-                                self.selected_request_index = Some(request_idx);
-                                self.sync_textarea_content();
-                            }
-                        }
+                if let Some((group_id, request_idx)) = self.selected_tree_request() {
+                    if let Some(group_idx) = self.groups_vec.iter().position(|g| g == &group_id) {
+                        self.selected_group_index = Some(group_idx);
+                        self.selected_request_index = Some(request_idx);
+                        self.sync_textarea_content();
                     }
                 }
             } else {
@@ -433,134 +906,172 @@ impl App {
         }
     }
 
-    pub fn add_request(&mut self, group_name: String) {
-        self.selected_group = Some(group_name);
+    pub fn add_request(&mut self, group_id: String) {
+        let node_id = group_node_id(&group_id);
+        self.selected_group = Some(group_id);
         self.current_screen = CurrentScreen::AddingRequest;
 
         // Select the group in the tree
         let tree = self.build_tree();
-        if let Some(group_node) = tree
-            .root()
-            .query(&format!("group-{}", self.selected_group.as_ref().unwrap()))
-        {
+        if let Some(group_node) = tree.root().query(&node_id) {
             self.tree_state.select(&tree, group_node);
             self.tree_state.open(&tree, group_node);
         }
     }
 
-    // combine next_auth and previous_auth
     pub fn next_auth_type(&mut self) {
-        if let Some(request) = self.get_selected_request_mut() {
-            request.details.auth_type = request.details.auth_type.next();
-            match request.details.auth_type {
-                AuthType::Basic => {
-                    request.details.auth_details = AuthDetails::Basic(BasicAuth {
-                        username: String::new(),
-                        password: String::new(),
-                    });
-                }
-                AuthType::None => {
-                    request.details.auth_details = AuthDetails::None;
-                    // Remove Authorization header when switching to None
-                    request.details.headers.remove("Authorization");
-                }
-            }
+        if let Some(auth_type) = self
+            .get_selected_request()
+            .map(|request| request.details.auth_type.next())
+        {
+            self.set_auth_type(auth_type);
         }
     }
 
     pub fn previous_auth_type(&mut self) {
+        if let Some(auth_type) = self
+            .get_selected_request()
+            .map(|request| request.details.auth_type.previous())
+        {
+            self.set_auth_type(auth_type);
+        }
+    }
+
+    /// Switches the selected request to `auth_type` with empty credentials.
+    fn set_auth_type(&mut self, auth_type: AuthType) {
         if let Some(request) = self.get_selected_request_mut() {
-            request.details.auth_type = request.details.auth_type.previous();
-            match request.details.auth_type {
-                AuthType::Basic => {
-                    request.details.auth_details = AuthDetails::Basic(BasicAuth {
-                        username: String::new(),
-                        password: String::new(),
-                    });
-                }
-                AuthType::None => {
-                    request.details.auth_details = AuthDetails::None;
-                    // Remove Authorization header when switching to None
-                    request.details.headers.remove("Authorization");
-                }
+            // Only Basic auth is stored as a header, the others are added when sending
+            if matches!(request.details.auth_type, AuthType::Basic) {
+                request.details.remove_header("Authorization");
             }
+            request.details.auth_type = auth_type;
+            request.details.auth_details = auth_type.default_details();
+        }
+        self.sync_auth_textareas();
+        self.persist_workspace();
+    }
+
+    /// Number of fields in the selected request's auth form.
+    pub fn auth_field_count(&self) -> usize {
+        self.get_current_request()
+            .map_or(0, |request| request.details.auth_details.fields().len())
+    }
+
+    /// True if `field` is a choice of the auth form, changed with ←/→ instead of typed.
+    pub fn is_auth_choice(&self, field: &DetailField) -> bool {
+        let DetailField::AuthField(index) = field else {
+            return false;
+        };
+        self.get_current_request().is_some_and(|request| {
+            request
+                .details
+                .auth_details
+                .fields()
+                .get(*index)
+                .is_some_and(|field| field.kind == AuthFieldKind::Choice)
+        })
+    }
+
+    /// Moves the auth choice at `index` to its next value.
+    pub fn cycle_auth_choice(&mut self, index: usize) {
+        if let Some(request) = self.get_selected_request_mut() {
+            request.details.auth_details.cycle_choice(index);
         }
+        self.sync_auth_textareas();
+        self.persist_workspace();
+    }
+
+    /// Loads the auth form of the selected request into `auth_textareas`.
+    fn sync_auth_textareas(&mut self) {
+        let fields = self
+            .get_selected_request()
+            .map(|request| request.details.auth_details.fields())
+            .unwrap_or_default();
+        self.auth_textareas = fields
+            .into_iter()
+            .map(|field| {
+                let mut textarea = TextArea::from(vec![field.value]);
+                textarea.set_cursor_line_style(Style::default());
+                textarea
+            })
+            .collect();
     }
 
     pub fn sync_textarea_content(&mut self) {
         if let Some(request) = self.get_selected_request() {
-            let url = request.details.url.clone();
+            let url = request.details.url_with_query();
             let body = request.details.body.clone();
-            let auth_username = request
-                .details
-                .get_basic_auth()
-                .map(|auth| auth.username.clone());
-            let auth_password = request
-                .details
-                .get_basic_auth()
-                .map(|auth| auth.password.clone());
 
             self.url_textarea = TextArea::from(vec![url]);
-            self.body_textarea = TextArea::from(vec![body]);
-
-            if let Some(username) = auth_username {
-                self.auth_username_textarea = TextArea::from(vec![username]);
-            } else {
-                self.auth_username_textarea = TextArea::default();
-            }
+            self.selected_param_index = 0;
+            self.selected_header_index = 0;
 
-            if let Some(password) = auth_password {
-                self.auth_password_textarea = TextArea::from(vec![password]);
-            } else {
-                self.auth_password_textarea = TextArea::default();
-            }
+            // split keeps a trailing empty line, unlike str::lines
+            self.body_textarea = TextArea::from(body.split('\n'));
+            self.sync_auth_textareas();
         }
     }
 
     pub fn save_textarea_content(&mut self) {
         // Get all text values first to avoid borrowing conflicts
         let url = self.url_textarea.lines()[0].to_string();
-        let body = self.body_textarea.lines()[0].to_string();
-        let username = self.auth_username_textarea.lines()[0].to_string();
-        let password = self.auth_password_textarea.lines()[0].to_string();
+        let body = self.body_textarea.lines().join("\n");
+        let auth_values: Vec<String> = self
+            .auth_textareas
+            .iter()
+            .map(|textarea| textarea.lines()[0].to_string())
+            .collect();
 
         if let Some(request) = self.get_selected_request_mut() {
-            request.details.url = url;
+            request.details.set_url_with_query(&url);
             request.details.body = body;
 
-            if let Some(basic_auth) = request.details.get_basic_auth_mut() {
-                basic_auth.username = username.clone();
-                basic_auth.password = password.clone();
+            // Choices have textareas too, `set_field` skips them
+            if auth_values.len() == request.details.auth_details.fields().len() {
+                for (index, value) in auth_values.into_iter().enumerate() {
+                    request.details.auth_details.set_field(index, value);
+                }
+            }
 
+            if let Some(basic_auth) = request.details.get_basic_auth() {
                 // Update the Authorization header for Basic Auth
-                if !username.is_empty() {
-                    let auth_string = format!("{}:{}", username, password);
-                    let encoded = BASE64_STANDARD.encode(auth_string.as_bytes());
-                    request
-                        .details
-                        .headers
-                        .insert("Authorization".to_string(), format!("Basic {}", encoded));
+                if !basic_auth.username.is_empty() {
+                    let header = basic_auth.authorization_header();
+                    request.details.set_header("Authorization", header);
                 } else {
                     // Remove the Authorization header if username is empty
-                    request.details.headers.remove("Authorization");
+                    request.details.remove_header("Authorization");
                 }
             }
         }
+        self.persist_workspace();
     }
 
+    /// Adds an empty group called `name` and returns its ID. Names may repeat.
+    pub fn add_group(&mut self, name: String) -> String {
+        let group_id = new_id();
+        self.list.insert(group_id.clone(), Vec::new());
+        self.group_names.insert(group_id.clone(), name);
+        self.update_groups_vec();
+        group_id
+    }
+
+    /// Adds a group named after `key_input` and closes the popup.
     pub fn save_group(&mut self) {
-        if !self.key_input.is_empty() {
-            self.list.insert(self.key_input.clone(), Vec::new());
-            self.key_input.clear();
-            self.groups = None;
-            self.update_groups_vec();
+        if self.key_input.is_empty() {
+            return;
         }
+        let name = std::mem::take(&mut self.key_input);
+        self.add_group(name);
+        self.groups = None;
+        self.current_screen = CurrentScreen::Main;
+        self.persist_workspace();
     }
 
     pub fn save_request(&mut self) {
-        if let Some(group_name) = &self.selected_group {
+        if let Some(group_id) = &self.selected_group {
             if !self.request_name_input.is_empty() {
-                if let Some(requests) = self.list.get_mut(group_name) {
+                if let Some(requests) = self.list.get_mut(group_id) {
                     // Create new request with empty details
                     let mut new_request = ApiRequest::new(
                         self.request_name_input.clone(),
@@ -570,30 +1081,27 @@ impl App {
                     // Initialize empty text areas
                     self.url_textarea = TextArea::default();
                     self.body_textarea = TextArea::default();
-                    self.auth_username_textarea = TextArea::default();
-                    self.auth_password_textarea = TextArea::default();
+                    self.auth_textareas.clear();
 
                     // Save the empty text areas to the request
                     new_request.details.url = String::new();
                     new_request.details.body = String::new();
 
                     // Add the new request
+                    let request_id = request_node_id(&new_request.id);
                     requests.push(new_request);
 
                     // After adding the request, update the tree state
                     let tree = self.build_tree();
 
                     // First, find and open the parent group
-                    if let Some(parent) = tree.root().query(&format!("group-{}", group_name)) {
+                    if let Some(parent) = tree.root().query(&group_node_id(group_id)) {
                         // Select the parent group and open it
                         self.tree_state.select(&tree, parent);
                         self.tree_state.open(&tree, parent);
 
                         // Then try to find and select the newly added request
-                        if let Some(request_node) = tree.root().query(&format!(
-                            "request-{}-{}",
-                            group_name, self.request_name_input
-                        )) {
+                        if let Some(request_node) = tree.root().query(&request_id) {
                             self.tree_state.select(&tree, request_node);
                         }
                     }
@@ -603,12 +1111,13 @@ impl App {
                 }
             }
         }
+        self.persist_workspace();
     }
 
     pub fn get_selected_request(&self) -> Option<&ApiRequest> {
         if let Some(group_index) = self.selected_group_index {
-            if let Some(group_name) = self.groups_vec.get(group_index) {
-                if let Some(requests) = self.list.get(group_name) {
+            if let Some(group_id) = self.groups_vec.get(group_index) {
+                if let Some(requests) = self.list.get(group_id) {
                     if let Some(request_index) = self.selected_request_index {
                         return requests.get(request_index);
                     }
@@ -620,8 +1129,8 @@ impl App {
 
     pub fn get_selected_request_mut(&mut self) -> Option<&mut ApiRequest> {
         if let Some(group_index) = self.selected_group_index {
-            if let Some(group_name) = self.groups_vec.get(group_index) {
-                if let Some(requests) = self.list.get_mut(group_name) {
+            if let Some(group_id) = self.groups_vec.get(group_index) {
+                if let Some(requests) = self.list.get_mut(group_id) {
                     if let Some(request_index) = self.selected_request_index {
                         return requests.get_mut(request_index);
                     }
@@ -632,8 +1141,19 @@ impl App {
     }
 
     pub fn update_groups_vec(&mut self) {
-        self.groups_vec = self.list.keys().cloned().collect();
-        self.groups_vec.sort();
+        // Removed groups lose their name and settings
+        self.group_names.retain(|id, _| self.list.contains_key(id));
+        self.group_settings
+            .retain(|id, _| self.list.contains_key(id));
+
+        // Groups of the same name stay in the same order between runs
+        let mut groups: Vec<(Option<&String>, &String)> = self
+            .list
+            .keys()
+            .map(|id| (self.group_names.get(id), id))
+            .collect();
+        groups.sort();
+        self.groups_vec = groups.into_iter().map(|(_, id)| id.clone()).collect();
     }
 
     pub fn next_group(&mut self) {
@@ -667,14 +1187,306 @@ impl App {
             self.selected_index = self
                 .selected_index
                 .min(self.groups_vec.len().saturating_sub(1));
+            self.persist_workspace();
         }
     }
 
     pub fn delete_group(&mut self) {
         if !self.key_input.is_empty() {
-            self.list.remove(&self.key_input);
+            if let Some(group_id) = self.find_group_named(&self.key_input) {
+                self.list.remove(&group_id);
+                self.update_groups_vec();
+            }
             self.key_input.clear();
             self.groups = None;
+            self.persist_workspace();
+        }
+    }
+
+    pub fn get_active_environment(&self) -> Option<&Environment> {
+        self.active_environment
+            .and_then(|idx| self.environments.get(idx))
+    }
+
+    pub fn open_environments(&mut self) {
+        self.selected_environment_index = self.active_environment.unwrap_or(0);
+        self.current_screen = CurrentScreen::Environments;
+    }
+
+    pub fn next_environment(&mut self) {
+        if !self.environments.is_empty() {
+            self.selected_environment_index =
+                (self.selected_environment_index + 1) % self.environments.len();
+        }
+    }
+
+    pub fn previous_environment(&mut self) {
+        if !self.environments.is_empty() {
+            self.selected_environment_index = self
+                .selected_environment_index
+                .checked_sub(1)
+                .unwrap_or(self.environments.len() - 1);
+        }
+    }
+
+    /// Activates the highlighted environment, or deactivates it if it is already active.
+    pub fn toggle_selected_environment(&mut self) {
+        if self.selected_environment_index < self.environments.len() {
+            self.active_environment =
+                if self.active_environment == Some(self.selected_environment_index) {
+                    None
+                } else {
+                    Some(self.selected_environment_index)
+                };
+            self.persist_workspace();
+        }
+    }
+
+    pub fn start_adding_environment(&mut self) {
+        self.adding_environment = true;
+        self.environment_name_input.clear();
+    }
+
+    pub fn save_environment(&mut self) {
+        let name = self.environment_name_input.trim().to_string();
+        if !name.is_empty() && !self.environments.iter().any(|e| e.name == name) {
+            self.environments.push(Environment::new(name));
+            self.selected_environment_index = self.environments.len() - 1;
+            self.persist_workspace();
+        }
+
+        self.adding_environment = false;
+        self.environment_name_input.clear();
+    }
+
+    pub fn delete_selected_environment(&mut self) {
+        if self.selected_environment_index >= self.environments.len() {
+            return;
+        }
+
+        let removed = self.selected_environment_index;
+        self.environments.remove(removed);
+        self.active_environment = match self.active_environment {
+            Some(active) if active == removed => None,
+            Some(active) if active > removed => Some(active - 1),
+            other => other,
+        };
+        self.selected_environment_index = self
+            .selected_environment_index
+            .min(self.environments.len().saturating_sub(1));
+        self.persist_workspace();
+    }
+
+    pub fn start_adding_variable(&mut self) {
+        if self.selected_environment_index < self.environments.len() {
+            self.adding_variable = true;
+            self.variable_key_input.clear();
+            self.variable_value_input.clear();
+            self.variable_input_mode = VariableInputMode::Key;
+        }
+    }
+
+    /// Adds the variable to the highlighted environment, replacing any existing value.
+    pub fn save_variable(&mut self) {
+        let key = self.variable_key_input.trim().to_string();
+        let value = self.variable_value_input.clone();
+
+        if !key.is_empty() {
+            if let Some(env) = self.environments.get_mut(self.selected_environment_index) {
+                env.variables.insert(key, value);
+            }
+            self.persist_workspace();
+        }
+
+        self.adding_variable = false;
+        self.variable_key_input.clear();
+        self.variable_value_input.clear();
+    }
+
+    pub fn toggle_variable_input_mode(&mut self) {
+        self.variable_input_mode = match self.variable_input_mode {
+            VariableInputMode::Key => VariableInputMode::Value,
+            VariableInputMode::Value => VariableInputMode::Key,
+        };
+    }
+
+    /// Adds imported groups to the workspace. Requests for a group whose name is already
+    /// taken are appended to the first group of that name.
+    pub fn merge_import(&mut self, import: PostmanImport) -> ImportSummary {
+        let summary = ImportSummary {
+            groups: import.groups.len(),
+            requests: import.request_count(),
+            warnings: import.warnings,
+        };
+
+        for group in import.groups {
+            let group_id = match self.find_group_named(&group.name) {
+                Some(group_id) => group_id,
+                None => self.add_group(group.name),
+            };
+            self.list
+                .entry(group_id)
+                .or_default()
+                .extend(group.requests);
+        }
+
+        self.update_groups_vec();
+        self.persist_workspace();
+        summary
+    }
+
+    pub fn import_postman_collection(&mut self, path: &Path) -> Result<ImportSummary, String> {
+        postman::import_collection(path)
+            .map(|import| self.merge_import(import))
+            .map_err(|e| format!("Could not import {}: {}", path.display(), e))
+    }
+
+    pub fn open_import(&mut self) {
+        self.import_path_input.clear();
+        self.import_result = None;
+        self.current_screen = CurrentScreen::Importing;
+    }
+
+    /// Imports the collection named in the import popup, expanding a leading `~`.
+    pub fn run_import(&mut self) {
+        let input = self.import_path_input.trim();
+        let path = match (input.strip_prefix("~/"), dirs::home_dir()) {
+            (Some(rest), Some(home)) => home.join(rest),
+            _ => PathBuf::from(input),
+        };
+        self.import_result = Some(self.import_postman_collection(&path));
+    }
+
+    /// ID of the group of the tree node under the cursor, whether that's a group or a
+    /// request.
+    pub fn selected_tree_group(&self) -> Option<String> {
+        let selected_id = self.tree_state.selected()?;
+        if let Some(group_id) = selected_id.strip_prefix("group-") {
+            return self
+                .list
+                .contains_key(group_id)
+                .then(|| group_id.to_string());
+        }
+        self.selected_tree_request().map(|(group_id, _)| group_id)
+    }
+
+    /// Shows the request under the cursor as a curl command and copies it to the clipboard.
+    /// Variables are expanded when the active environment defines all of them.
+    pub fn export_curl(&mut self) {
+        let Some(request) = self.get_current_request() else {
+            return;
+        };
+
+        let variables = self
+            .get_active_environment()
+            .map(|env| env.variables.clone())
+            .unwrap_or_default();
+        let mut request = request.clone();
+        request.details = request.details.enabled_only();
+        if let Ok(details) = resolve_details(&request.details, &variables) {
+            request.details = details;
+        }
+        // The token the next send would use, which may be newer than the saved one
+        if let AuthDetails::OAuth2(auth) = &mut request.details.auth_details {
+            auth.tokens = self.sender.auth_cache.tokens.get(auth);
+        }
+
+        let command = curl::to_curl(&request);
+        self.clipboard = Some(command.clone());
+        self.curl_export = Some(command);
+        self.current_screen = CurrentScreen::CurlExport;
+    }
+
+    pub fn open_curl_import(&mut self) {
+        self.curl_input.clear();
+        self.curl_error = None;
+        self.current_screen = CurrentScreen::CurlImport;
+    }
+
+    /// Parses `command` and adds the request to the group with ID `group_id`, or without
+    /// one to the group called "Imported", creating it if needed.
+    pub fn import_curl(&mut self, command: &str, group_id: Option<&str>) -> Result<(), String> {
+        let request =
+            curl::parse_curl(command).map_err(|e| format!("Invalid curl command: {}", e))?;
+        let request_id = request_node_id(&request.id);
+
+        let group_id = match group_id.map(str::to_string) {
+            Some(group_id) => group_id,
+            None => self
+                .find_group_named("Imported")
+                .unwrap_or_else(|| self.add_group("Imported".to_string())),
+        };
+        self.list.entry(group_id.clone()).or_default().push(request);
+        self.update_groups_vec();
+        self.persist_workspace();
+
+        let tree = self.build_tree();
+        if let Some(node) = tree.root().query(&group_node_id(&group_id)) {
+            self.tree_state.open(&tree, node);
+        }
+        if let Some(node) = tree.root().query(&request_id) {
+            self.tree_state.select(&tree, node);
+        }
+        Ok(())
+    }
+
+    /// Imports the command in the curl popup into the group under the tree cursor.
+    pub fn run_curl_import(&mut self) {
+        let group_id = self.selected_tree_group();
+        let command = self.curl_input.clone();
+        match self.import_curl(&command, group_id.as_deref()) {
+            Ok(()) => {
+                self.curl_input.clear();
+                self.curl_error = None;
+                self.current_screen = CurrentScreen::Main;
+            }
+            Err(e) => self.curl_error = Some(e),
+        }
+    }
+
+    /// Handles text pasted into the terminal (bracketed paste), so multi-line commands don't
+    /// arrive as a series of Enter presses.
+    pub fn handle_paste(&mut self, text: &str) {
+        match self.current_screen {
+            CurrentScreen::Main
+                if self.active_panel == ActivePanel::Response
+                    && self.response_view.filter_input.is_some() =>
+            {
+                text.chars()
+                    .filter(|c| !matches!(c, '\r' | '\n'))
+                    .for_each(|c| self.push_response_filter(c));
+            }
+            CurrentScreen::Main
+                if self.active_panel == ActivePanel::Response && self.response_view.searching =>
+            {
+                self.response_view
+                    .search_query
+                    .push_str(&text.replace(['\r', '\n'], ""));
+                self.update_response_search();
+            }
+            CurrentScreen::Main if self.active_panel == ActivePanel::Details => {
+                // Only the body keeps line breaks, the other fields are single-line
+                let single_line = || text.replace(['\r', '\n'], "");
+                match self.current_detail_field {
+                    DetailField::Url => self.url_textarea.insert_str(single_line()),
+                    DetailField::Body => self.body_textarea.insert_str(text.replace("\r\n", "\n")),
+                    DetailField::AuthField(index) => self
+                        .auth_textareas
+                        .get_mut(index)
+                        .is_some_and(|textarea| textarea.insert_str(single_line())),
+                    _ => false,
+                };
+                self.save_textarea_content();
+            }
+            CurrentScreen::CurlImport => {
+                self.curl_input.push_str(text);
+                self.curl_error = None;
+            }
+            CurrentScreen::Importing if self.import_result.is_none() => {
+                self.import_path_input.push_str(text.trim());
+            }
+            CurrentScreen::Settings => self.paste_settings(text.trim()),
+            _ => {}
         }
     }
 
@@ -691,6 +1503,12 @@ impl App {
     }
 }
 
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -710,7 +1528,14 @@ mod tests {
     fn test_auth_type_transitions() {
         let auth_type = AuthType::None;
         assert!(matches!(auth_type.next(), AuthType::Basic));
-        assert!(matches!(AuthType::Basic.next(), AuthType::None));
+        assert!(matches!(AuthType::Basic.next(), AuthType::Bearer));
+        assert!(matches!(AuthType::Bearer.next(), AuthType::ApiKey));
+        assert!(matches!(AuthType::ApiKey.next(), AuthType::OAuth2));
+        assert!(matches!(AuthType::OAuth2.next(), AuthType::Digest));
+        assert!(matches!(AuthType::Digest.next(), AuthType::AwsSigV4));
+        assert!(matches!(AuthType::AwsSigV4.next(), AuthType::Hmac));
+        assert!(matches!(AuthType::Hmac.next(), AuthType::None));
+        assert!(matches!(auth_type.previous(), AuthType::Hmac));
     }
 
     #[test]
@@ -724,6 +1549,33 @@ mod tests {
         assert_eq!(basic_auth.password, "test_pass");
     }
 
+    #[test]
+    fn test_groups_of_the_same_name_are_kept_apart() {
+        let mut app = App::with_workspace(None);
+        let users = app.add_group("users".to_string());
+        app.list
+            .get_mut(&users)
+            .unwrap()
+            .push(ApiRequest::new("list".to_string(), RequestType::GET));
+
+        app.current_screen = CurrentScreen::Editing;
+        app.groups = Some(Groups::Name);
+        app.key_input = "users".to_string();
+        app.save_group();
+        assert!(app.current_screen == CurrentScreen::Main);
+        assert_eq!(app.groups_vec.len(), 2);
+        let other = app.groups_vec.iter().find(|id| **id != users).unwrap();
+        assert_eq!(app.group_name(other), Some("users"));
+        assert!(app.list[other].is_empty());
+        assert_eq!(app.list[&users].len(), 1);
+
+        // Each group has its own node, found again by its ID
+        let tree = app.build_tree();
+        let node = tree.root().query(&group_node_id(other)).unwrap();
+        app.tree_state.select(&tree, node);
+        assert_eq!(app.selected_tree_group().as_ref(), Some(other));
+    }
+
     #[test]
     fn test_request_details_new() {
         let details = RequestDetails::new();
@@ -0,0 +1,408 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use super::environment::Environment;
+use super::models::{new_id, ApiRequest, GroupSettings, ProxySettings};
+
+/// Version written into every workspace file. Bump it whenever the layout
+/// changes in a way older builds can't read.
+///
+/// 2: params and headers are ordered lists of `{key, value, enabled}` instead of maps.
+/// 3: `accept_invalid_certs` is `null` when left to the group, and `false` overrides it.
+pub const WORKSPACE_VERSION: u32 = 3;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Workspace {
+    pub version: u32,
+    #[serde(default)]
+    pub groups: Vec<WorkspaceGroup>,
+    #[serde(default)]
+    pub environments: Vec<Environment>,
+    /// Name of the active environment, if any.
+    #[serde(default)]
+    pub active_environment: Option<String>,
+    /// Proxy settings for groups and requests that inherit them.
+    #[serde(default)]
+    pub proxy: ProxySettings,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WorkspaceGroup {
+    /// Persistent unique ID, used to tell groups apart in the tree.
+    #[serde(default = "new_id")]
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub settings: GroupSettings,
+    #[serde(default)]
+    pub requests: Vec<ApiRequest>,
+}
+
+impl Workspace {
+    /// Snapshot the in-memory group list, keyed by group ID, with names from
+    /// `group_names`. Groups are sorted by name so the file doesn't churn between saves.
+    pub fn from_list(
+        list: &HashMap<String, Vec<ApiRequest>>,
+        group_names: &HashMap<String, String>,
+    ) -> Self {
+        let mut groups: Vec<WorkspaceGroup> = list
+            .iter()
+            .map(|(id, requests)| WorkspaceGroup {
+                id: id.clone(),
+                name: group_names.get(id).cloned().unwrap_or_default(),
+                settings: GroupSettings::default(),
+                requests: requests.clone(),
+            })
+            .collect();
+        groups.sort_by(|a, b| (&a.name, &a.id).cmp(&(&b.name, &b.id)));
+
+        Self {
+            version: WORKSPACE_VERSION,
+            groups,
+            environments: Vec::new(),
+            active_environment: None,
+            proxy: ProxySettings::default(),
+        }
+    }
+
+    /// Group names by group ID.
+    pub fn group_names(&self) -> HashMap<String, String> {
+        self.groups
+            .iter()
+            .map(|group| (group.id.clone(), group.name.clone()))
+            .collect()
+    }
+
+    /// Group settings by group ID.
+    pub fn group_settings(&self) -> HashMap<String, GroupSettings> {
+        self.groups
+            .iter()
+            .map(|group| (group.id.clone(), group.settings.clone()))
+            .collect()
+    }
+
+    /// Requests by group ID.
+    pub fn into_list(self) -> HashMap<String, Vec<ApiRequest>> {
+        self.groups
+            .into_iter()
+            .map(|group| (group.id, group.requests))
+            .collect()
+    }
+
+    /// `<data dir>/ratquest/workspace.json`, e.g. `~/.local/share/ratquest/workspace.json` on Linux.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("ratquest").join("workspace.json"))
+    }
+
+    pub fn to_json(&self) -> io::Result<String> {
+        serde_json::to_string_pretty(self).map_err(io::Error::from)
+    }
+
+    pub fn from_json(contents: &str) -> io::Result<Self> {
+        let mut value: Value = serde_json::from_str(contents)?;
+        let version = value.get("version").and_then(Value::as_u64).unwrap_or(0);
+        if version > WORKSPACE_VERSION as u64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "workspace version {} is newer than the supported version {}",
+                    version, WORKSPACE_VERSION
+                ),
+            ));
+        }
+        if version < 2 {
+            migrate_key_value_maps(&mut value);
+        }
+        if version < 3 {
+            migrate_accept_invalid_certs(&mut value);
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Returns `Ok(None)` when there is no workspace file yet.
+    pub fn load(path: &Path) -> io::Result<Option<Self>> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Self::from_json(&contents).map(Some),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        write_atomic(path, self.to_json()?.as_bytes())
+    }
+}
+
+/// Up to version 2 `accept_invalid_certs` was a plain flag that a group's `true` won over,
+/// so `false` meant the same as leaving it to the group.
+fn migrate_accept_invalid_certs(workspace: &mut Value) {
+    let Some(groups) = workspace.get_mut("groups").and_then(Value::as_array_mut) else {
+        return;
+    };
+    let reset = |tls: &mut Value| {
+        if tls.get("accept_invalid_certs") == Some(&Value::Bool(false)) {
+            tls["accept_invalid_certs"] = Value::Null;
+        }
+    };
+    for group in groups {
+        if let Some(tls) = group.pointer_mut("/settings/tls") {
+            reset(tls);
+        }
+        let requests = group.get_mut("requests").and_then(Value::as_array_mut);
+        for request in requests.into_iter().flatten() {
+            if let Some(tls) = request.get_mut("tls") {
+                reset(tls);
+            }
+        }
+    }
+}
+
+/// Version 1 stored params and headers as maps, with disabled keys listed separately in
+/// `disabled_params` and `disabled_headers`. Converts them to lists, sorted by key as they
+/// used to be displayed.
+fn migrate_key_value_maps(workspace: &mut Value) {
+    let Some(groups) = workspace.get_mut("groups").and_then(Value::as_array_mut) else {
+        return;
+    };
+    let requests = groups
+        .iter_mut()
+        .filter_map(|group| group.get_mut("requests").and_then(Value::as_array_mut))
+        .flatten();
+
+    for request in requests {
+        let Some(details) = request.get_mut("details").and_then(Value::as_object_mut) else {
+            continue;
+        };
+        for (field, disabled_field) in [
+            ("params", "disabled_params"),
+            ("headers", "disabled_headers"),
+        ] {
+            let disabled = details.remove(disabled_field).unwrap_or_default();
+            let is_disabled = |key: &str| {
+                disabled
+                    .as_array()
+                    .is_some_and(|keys| keys.iter().any(|k| k.as_str() == Some(key)))
+            };
+
+            let Some(Value::Object(map)) = details.get(field) else {
+                continue;
+            };
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            let list: Vec<Value> = entries
+                .into_iter()
+                .map(|(key, value)| json!({ "key": key, "value": value, "enabled": !is_disabled(key) }))
+                .collect();
+            details.insert(field.to_string(), Value::Array(list));
+        }
+    }
+}
+
+/// Writes to a sibling temp file, syncs it and renames it over `path`, so a
+/// crash mid-write leaves either the old file or the new one, never half of each.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let parent = path.parent().filter(|p| !p.as_os_str().is_empty());
+    if let Some(parent) = parent {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    // The workspace holds credentials, so only the owner may read it. The mode only
+    // applies when the file is created, hence dropping any leftover from a crash first.
+    let _ = fs::remove_file(&tmp_path);
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(&tmp_path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    drop(file);
+
+    if let Err(e) = fs::rename(&tmp_path, path) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+
+    // Make the rename itself durable. Not every platform lets us open a directory.
+    #[cfg(unix)]
+    if let Some(parent) = parent {
+        if let Ok(dir) = File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::models::{AuthDetails, AuthType, BasicAuth, KeyValue, RequestType};
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("ratquest-storage-{}-{}", std::process::id(), name))
+            .join("workspace.json")
+    }
+
+    #[test]
+    fn test_workspace_round_trip() {
+        let path = temp_path("round-trip");
+
+        let mut request = ApiRequest::new("login".to_string(), RequestType::POST);
+        request.details.url = "http://localhost/login".to_string();
+        request.details.body = "{\n  \"a\": 1\n}".to_string();
+        request
+            .details
+            .headers
+            .push(KeyValue::new("Accept", "application/json"));
+        request.details.auth_type = AuthType::Basic;
+        request.details.auth_details = AuthDetails::Basic(BasicAuth {
+            username: "user".to_string(),
+            password: "pass".to_string(),
+        });
+
+        let mut list = HashMap::new();
+        list.insert("g1".to_string(), vec![request]);
+        list.insert("g2".to_string(), Vec::new());
+        list.insert("g3".to_string(), Vec::new());
+
+        let request_id = list["g1"][0].id.clone();
+        // Two groups may share a name
+        let group_names = HashMap::from([
+            ("g1".to_string(), "auth".to_string()),
+            ("g2".to_string(), "empty".to_string()),
+            ("g3".to_string(), "empty".to_string()),
+        ]);
+
+        Workspace::from_list(&list, &group_names)
+            .save(&path)
+            .unwrap();
+        let workspace = Workspace::load(&path).unwrap().unwrap();
+        assert_eq!(workspace.group_names(), group_names);
+        let loaded = workspace.into_list();
+
+        assert_eq!(loaded.len(), 3);
+        assert!(loaded["g2"].is_empty());
+        let request = &loaded["g1"][0];
+        assert_eq!(request.id, request_id);
+        assert_eq!(request.name, "login");
+        assert_eq!(request.details.body, "{\n  \"a\": 1\n}");
+        assert_eq!(request.details.header("Accept"), Some("application/json"));
+        assert_eq!(request.details.get_basic_auth().unwrap().password, "pass");
+
+        // No temp file is left behind after the rename
+        assert!(!path.with_file_name("workspace.json.tmp").exists());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_workspace_is_only_readable_by_its_owner() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = temp_path("permissions");
+        write_atomic(&path, b"{}").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_missing_workspace_is_not_an_error() {
+        let path = temp_path("missing");
+        assert!(Workspace::load(&path).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_version_1_workspace_is_migrated() {
+        let json = r#"{
+            "version": 1,
+            "groups": [{
+                "name": "api",
+                "requests": [{
+                    "name": "list",
+                    "request_type": "GET",
+                    "details": {
+                        "url": "http://localhost/items",
+                        "body": "",
+                        "params": {"page": "2", "limit": "10"},
+                        "headers": {"Accept": "application/json", "X-Debug": "1"},
+                        "disabled_headers": ["X-Debug"],
+                        "auth_type": "None",
+                        "auth_details": "None"
+                    }
+                }]
+            }]
+        }"#;
+
+        let workspace = Workspace::from_json(json).unwrap();
+        let group_id = workspace.groups[0].id.clone();
+        assert!(!group_id.is_empty());
+        assert_eq!(workspace.group_names()[&group_id], "api");
+        let list = workspace.into_list();
+        assert!(!list[&group_id][0].id.is_empty());
+        let details = &list[&group_id][0].details;
+        assert_eq!(
+            details.params,
+            vec![KeyValue::new("limit", "10"), KeyValue::new("page", "2")]
+        );
+        assert_eq!(
+            details.headers[0],
+            KeyValue::new("Accept", "application/json")
+        );
+        assert_eq!(details.headers[1].key, "X-Debug");
+        assert!(!details.headers[1].enabled);
+    }
+
+    #[test]
+    fn test_version_2_accept_invalid_certs_is_left_to_the_group() {
+        let json = r#"{
+            "version": 2,
+            "groups": [{
+                "name": "local",
+                "settings": {"tls": {"accept_invalid_certs": true}},
+                "requests": [{
+                    "name": "health",
+                    "request_type": "GET",
+                    "tls": {"accept_invalid_certs": false},
+                    "details": {
+                        "url": "https://localhost:8443/health",
+                        "body": "",
+                        "params": [],
+                        "headers": [],
+                        "auth_type": "None",
+                        "auth_details": "None"
+                    }
+                }]
+            }]
+        }"#;
+
+        let workspace = Workspace::from_json(json).unwrap();
+        let group_id = workspace.groups[0].id.clone();
+        let group = &workspace.group_settings()[&group_id];
+        assert_eq!(group.tls.accept_invalid_certs, Some(true));
+        let list = workspace.into_list();
+        let request = &list[&group_id][0];
+        assert_eq!(request.tls.accept_invalid_certs, None);
+        assert!(request.tls.over(&group.tls).accepts_invalid_certs());
+    }
+
+    #[test]
+    fn test_newer_workspace_version_is_rejected() {
+        let json = format!("{{\"version\": {}, \"groups\": []}}", WORKSPACE_VERSION + 1);
+        assert!(Workspace::from_json(&json).is_err());
+    }
+}
@@ -0,0 +1,778 @@
+use openssl::asn1::Asn1Time;
+use openssl::hash::MessageDigest;
+use openssl::pkcs12::Pkcs12;
+use openssl::pkey::{PKey, Private};
+use openssl::x509::{GeneralNameRef, X509NameRef, X509Ref, X509};
+use reqwest::ClientBuilder;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::{CertificateDer, PrivatePkcs8KeyDer, ServerName, UnixTime};
+use rustls::{
+    ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme, SupportedProtocolVersion,
+};
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
+
+use super::error::{ErrorKind, RequestError};
+use super::models::{TlsSettings, TlsVersion};
+
+impl TlsSettings {
+    /// These request settings on top of `group`'s. The request's client certificate
+    /// replaces the group's and CA files add up. Whether invalid certificates are accepted
+    /// and the minimum version are the request's unless it leaves them to the group.
+    pub fn over(&self, group: &TlsSettings) -> TlsSettings {
+        let (client_cert, client_key, client_cert_password) = if self.client_cert.is_empty() {
+            (
+                group.client_cert.clone(),
+                group.client_key.clone(),
+                group.client_cert_password.clone(),
+            )
+        } else {
+            (
+                self.client_cert.clone(),
+                self.client_key.clone(),
+                self.client_cert_password.clone(),
+            )
+        };
+        let mut ca_files = group.ca_files.clone();
+        ca_files.extend(
+            self.ca_files
+                .iter()
+                .filter(|path| !group.ca_files.contains(path))
+                .cloned(),
+        );
+
+        TlsSettings {
+            client_cert,
+            client_key,
+            client_cert_password,
+            ca_files,
+            accept_invalid_certs: self.accept_invalid_certs.or(group.accept_invalid_certs),
+            min_version: match self.min_version {
+                TlsVersion::Default => group.min_version,
+                version => version,
+            },
+        }
+    }
+
+    /// Whether certificate checks are skipped, once nothing is left to inherit.
+    pub fn accepts_invalid_certs(&self) -> bool {
+        self.accept_invalid_certs.unwrap_or(false)
+    }
+}
+
+fn tls_error(message: String) -> RequestError {
+    RequestError::new(ErrorKind::Tls, message)
+}
+
+/// `path` with a leading `~/` pointing into the home directory.
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn read(path: &str, what: &str) -> Result<Vec<u8>, RequestError> {
+    fs::read(expand_home(path))
+        .map_err(|e| tls_error(format!("Could not read the {} {}: {}", what, path, e)))
+}
+
+fn is_pem(contents: &[u8]) -> bool {
+    String::from_utf8_lossy(contents).contains("-----BEGIN ")
+}
+
+fn is_pkcs12(path: &str, contents: &[u8]) -> bool {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    matches!(extension.as_deref(), Some("p12" | "pfx")) || !is_pem(contents)
+}
+
+/// The client certificate chain, leaf first, and its key. A PKCS#12 archive holds both,
+/// PEM files hold the key next to the certificates unless `client_key` is set.
+fn client_identity(settings: &TlsSettings) -> Result<(Vec<X509>, PKey<Private>), RequestError> {
+    let path = &settings.client_cert;
+    let contents = read(path, "client certificate")?;
+    let password = settings.client_cert_password.as_str();
+    if is_pkcs12(path, &contents) {
+        let parsed = Pkcs12::from_der(&contents)
+            .and_then(|archive| archive.parse2(password))
+            .map_err(|e| {
+                tls_error(format!(
+                    "Could not open the PKCS#12 archive {}: {}",
+                    path, e
+                ))
+            })?;
+        let (Some(certificate), Some(key)) = (parsed.cert, parsed.pkey) else {
+            return Err(tls_error(format!(
+                "The PKCS#12 archive {} has no certificate and key",
+                path
+            )));
+        };
+        let mut chain = vec![certificate];
+        chain.extend(parsed.ca.into_iter().flatten());
+        return Ok((chain, key));
+    }
+
+    let chain = X509::stack_from_pem(&contents)
+        .ok()
+        .filter(|chain| !chain.is_empty())
+        .ok_or_else(|| tls_error(format!("No certificate found in {}", path)))?;
+
+    let (key_path, key_contents) = if settings.client_key.is_empty() {
+        (path, contents)
+    } else {
+        let key_path = &settings.client_key;
+        (key_path, read(key_path, "client key")?)
+    };
+    let key = if password.is_empty() {
+        PKey::private_key_from_pem(&key_contents)
+    } else {
+        PKey::private_key_from_pem_passphrase(&key_contents, password.as_bytes())
+    }
+    .map_err(|e| tls_error(format!("No usable private key in {}: {}", key_path, e)))?;
+    Ok((chain, key))
+}
+
+/// Every certificate in a CA file, which may be a PEM bundle or a single DER certificate.
+fn ca_certificates(path: &str) -> Result<Vec<X509>, RequestError> {
+    let contents = read(path, "CA file")?;
+    if is_pem(&contents) {
+        X509::stack_from_pem(&contents)
+    } else {
+        X509::from_der(&contents).map(|certificate| vec![certificate])
+    }
+    .map_err(|e| tls_error(format!("Invalid CA file {}: {}", path, e)))
+}
+
+fn der(certificate: &X509, what: &str) -> Result<CertificateDer<'static>, RequestError> {
+    certificate
+        .to_der()
+        .map(CertificateDer::from)
+        .map_err(|e| tls_error(format!("Invalid {}: {}", what, e)))
+}
+
+static TLS13_ONLY: &[&SupportedProtocolVersion] = &[&rustls::version::TLS13];
+
+/// The system's trusted CAs, loaded once.
+fn system_roots() -> &'static [CertificateDer<'static>] {
+    static ROOTS: OnceLock<Vec<CertificateDer<'static>>> = OnceLock::new();
+    ROOTS.get_or_init(|| rustls_native_certs::load_native_certs().certs)
+}
+
+/// Makes the client connect with `settings`, noting every handshake in `handshakes`.
+/// Unreadable certificate files are reported as TLS errors before anything is sent.
+pub fn configure(
+    builder: ClientBuilder,
+    settings: &TlsSettings,
+    handshakes: &Handshakes,
+) -> Result<ClientBuilder, RequestError> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+
+    let mut roots = RootCertStore::empty();
+    // Some system stores carry certificates rustls can't parse, those are left out
+    roots.add_parsable_certificates(system_roots().iter().cloned());
+    for path in &settings.ca_files {
+        for certificate in ca_certificates(path)? {
+            roots
+                .add(der(&certificate, "CA certificate")?)
+                .map_err(|e| tls_error(format!("Invalid CA certificate in {}: {}", path, e)))?;
+        }
+    }
+    let verifier = WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+        .build()
+        .map_err(|e| tls_error(format!("No CA certificates to trust: {}", e)))?;
+    let recorder = Recorder {
+        verifier,
+        provider: provider.clone(),
+        accept_invalid: settings.accepts_invalid_certs(),
+        handshakes: handshakes.clone(),
+    };
+
+    let versions = match settings.min_version {
+        TlsVersion::Tls13 => TLS13_ONLY,
+        TlsVersion::Default | TlsVersion::Tls12 => rustls::ALL_VERSIONS,
+    };
+    let config = ClientConfig::builder_with_provider(provider)
+        .with_protocol_versions(versions)
+        .map_err(|e| tls_error(format!("Invalid TLS settings: {}", e)))?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(recorder));
+    let mut config = if settings.client_cert.is_empty() {
+        config.with_no_client_auth()
+    } else {
+        let (chain, key) = client_identity(settings)?;
+        let chain = chain
+            .iter()
+            .map(|certificate| der(certificate, "client certificate"))
+            .collect::<Result<Vec<_>, _>>()?;
+        let key = key
+            .private_key_to_pkcs8()
+            .map_err(|e| tls_error(format!("Invalid client key: {}", e)))?;
+        config
+            .with_client_auth_cert(chain, PrivatePkcs8KeyDer::from(key).into())
+            .map_err(|e| {
+                tls_error(format!(
+                    "Invalid client certificate {}: {}",
+                    settings.client_cert, e
+                ))
+            })?
+    };
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    Ok(builder.use_preconfigured_tls(config).tls_info(true))
+}
+
+/// One handshake as the server's certificates were checked.
+#[derive(Clone, Debug, Default)]
+struct Handshake {
+    /// Only known once the server's signature is checked, which a failed verification
+    /// never gets to.
+    protocol: Option<String>,
+    verify_error: Option<String>,
+    chain: Vec<CertificateInfo>,
+}
+
+/// The handshakes of one client's connections in the order they were made, shared with
+/// the certificate verifier that records them.
+#[derive(Clone, Debug, Default)]
+pub struct Handshakes(Arc<Mutex<Vec<Handshake>>>);
+
+impl Handshakes {
+    fn lock(&self) -> MutexGuard<'_, Vec<Handshake>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The details of the connection a response came in on, `connection` being what the
+    /// client tells about it, see `connection_info`. The latest handshake with the same
+    /// server certificate supplies the rest.
+    pub fn complete(&self, connection: Option<TlsInfo>) -> Option<TlsInfo> {
+        let connection = connection?;
+        let server = connection.chain.first()?;
+        let handshakes = self.lock();
+        let Some(handshake) = handshakes
+            .iter()
+            .rev()
+            .find(|handshake| handshake.chain.first().map(|c| &c.sha256) == Some(&server.sha256))
+        else {
+            return Some(connection);
+        };
+        Some(TlsInfo {
+            protocol: handshake.protocol.clone(),
+            verify_error: handshake.verify_error.clone(),
+            chain: handshake.chain.clone(),
+            ..connection
+        })
+    }
+
+    /// The latest handshake, for requests that failed before there was a response.
+    pub fn latest(&self) -> Option<TlsInfo> {
+        let handshake = self.lock().last()?.clone();
+        Some(TlsInfo {
+            protocol: handshake.protocol,
+            http_version: None,
+            verify_error: handshake.verify_error,
+            chain: handshake.chain,
+        })
+    }
+}
+
+/// Checks server certificates like rustls does and notes down what it sees. With
+/// `accept_invalid` every certificate passes, but the failed check is still noted.
+#[derive(Debug)]
+struct Recorder {
+    verifier: Arc<WebPkiServerVerifier>,
+    provider: Arc<CryptoProvider>,
+    accept_invalid: bool,
+    handshakes: Handshakes,
+}
+
+impl Recorder {
+    /// The server's signature is checked after its certificate, so this is the handshake
+    /// recorded last.
+    fn note_protocol(&self, protocol: &str) {
+        if let Some(handshake) = self.handshakes.lock().last_mut() {
+            handshake.protocol = Some(protocol.to_string());
+        }
+    }
+}
+
+impl ServerCertVerifier for Recorder {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let verified = self.verifier.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        );
+        let chain = std::iter::once(end_entity)
+            .chain(intermediates)
+            .filter_map(|certificate| X509::from_der(certificate).ok())
+            .map(|certificate| CertificateInfo::new(&certificate))
+            .collect();
+        self.handshakes.lock().push(Handshake {
+            protocol: None,
+            verify_error: verified.as_ref().err().map(ToString::to_string),
+            chain,
+        });
+        match verified {
+            Err(_) if self.accept_invalid => Ok(ServerCertVerified::assertion()),
+            verified => verified,
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.note_protocol("TLSv1.2");
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.note_protocol("TLSv1.3");
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+/// What the client tells about the connection `response` came in on: the server's
+/// certificate and the HTTP version spoken. `Handshakes::complete` adds the rest. `None`
+/// for plain HTTP.
+pub fn connection_info(response: &reqwest::Response) -> Option<TlsInfo> {
+    let certificate = response
+        .extensions()
+        .get::<reqwest::tls::TlsInfo>()?
+        .peer_certificate()?;
+    let certificate = X509::from_der(certificate).ok()?;
+    Some(TlsInfo {
+        protocol: None,
+        http_version: Some(format!("{:?}", response.version())),
+        verify_error: None,
+        chain: vec![CertificateInfo::new(&certificate)],
+    })
+}
+
+/// Certificates expiring sooner than this are highlighted.
+pub const EXPIRY_WARNING_DAYS: i32 = 30;
+
+/// One certificate the server presented.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CertificateInfo {
+    pub subject: String,
+    /// Subject alternative names, e.g. `DNS example.com` or `IP 127.0.0.1`.
+    pub alt_names: Vec<String>,
+    pub issuer: String,
+    pub not_before: String,
+    pub not_after: String,
+    /// Whole days from the handshake until the certificate expires, negative once expired.
+    pub days_left: i32,
+    pub serial: String,
+    pub sha256: String,
+}
+
+impl CertificateInfo {
+    fn new(certificate: &X509Ref) -> Self {
+        let days_left = Asn1Time::days_from_now(0)
+            .and_then(|now| now.diff(certificate.not_after()))
+            // Days and seconds share the sign of the difference, round down to whole days
+            .map_or(0, |diff| diff.days - i32::from(diff.secs < 0));
+        let alt_names = certificate
+            .subject_alt_names()
+            .map(|names| names.iter().filter_map(alt_name).collect())
+            .unwrap_or_default();
+        let serial = certificate
+            .serial_number()
+            .to_bn()
+            .and_then(|serial| serial.to_hex_str().map(|hex| hex.to_string()))
+            .unwrap_or_default();
+        let sha256 = certificate
+            .digest(MessageDigest::sha256())
+            .map(|digest| {
+                digest
+                    .iter()
+                    .map(|byte| format!("{:02X}", byte))
+                    .collect::<Vec<_>>()
+                    .join(":")
+            })
+            .unwrap_or_default();
+
+        Self {
+            subject: name(certificate.subject_name()),
+            alt_names,
+            issuer: name(certificate.issuer_name()),
+            not_before: certificate.not_before().to_string(),
+            not_after: certificate.not_after().to_string(),
+            days_left,
+            serial,
+            sha256,
+        }
+    }
+
+    pub fn expires_soon(&self) -> bool {
+        self.days_left < EXPIRY_WARNING_DAYS
+    }
+}
+
+/// What was agreed in the TLS handshake with a server.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TlsInfo {
+    /// E.g. `TLSv1.3`, `None` when the handshake stopped before it was known.
+    pub protocol: Option<String>,
+    /// E.g. `HTTP/2.0`, `None` when the request failed before there was a response.
+    pub http_version: Option<String>,
+    /// Why the certificate chain doesn't verify, `None` when it does.
+    pub verify_error: Option<String>,
+    /// The chain as sent by the server, its own certificate first.
+    pub chain: Vec<CertificateInfo>,
+}
+
+/// `CN=example.com, O=Example` style.
+fn name(name: &X509NameRef) -> String {
+    name.entries()
+        .map(|entry| {
+            let key = entry.object().nid().short_name().unwrap_or("?");
+            let value = entry
+                .data()
+                .as_utf8()
+                .map(|value| value.to_string())
+                .unwrap_or_default();
+            format!("{}={}", key, value)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn alt_name(name: &GeneralNameRef) -> Option<String> {
+    if let Some(dns) = name.dnsname() {
+        return Some(format!("DNS {}", dns));
+    }
+    if let Some(ip) = name.ipaddress() {
+        let ip = match ip.len() {
+            4 => IpAddr::from(<[u8; 4]>::try_from(ip).ok()?),
+            16 => IpAddr::from(<[u8; 16]>::try_from(ip).ok()?),
+            _ => return None,
+        };
+        return Some(format!("IP {}", ip));
+    }
+    if let Some(email) = name.email() {
+        return Some(format!("Email {}", email));
+    }
+    name.uri().map(|uri| format!("URI {}", uri))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::auth::AuthCache;
+    use crate::app::models::RequestType;
+    use crate::app::proxy::ProxyConfig;
+    use crate::app::requests::{execute, PreparedRequest, RequestResult};
+    use openssl::bn::BigNum;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::nid::Nid;
+    use openssl::ssl::{SslAcceptor, SslMethod, SslVerifyMode};
+    use openssl::x509::extension::{BasicConstraints, SubjectAlternativeName};
+    use openssl::x509::{X509Builder, X509NameBuilder};
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    struct Issued {
+        certificate: X509,
+        key: PKey<Private>,
+    }
+
+    /// A CA with a certificate for 127.0.0.1 and a client certificate, all fresh per test.
+    struct Pki {
+        ca: Issued,
+        server: Issued,
+        client: Issued,
+    }
+
+    fn issue(name: &str, serial: u32, issuer: Option<&Issued>, server: bool) -> Issued {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let mut subject = X509NameBuilder::new().unwrap();
+        subject.append_entry_by_text("CN", name).unwrap();
+        let subject = subject.build();
+
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        let serial = BigNum::from_u32(serial).unwrap().to_asn1_integer().unwrap();
+        builder.set_serial_number(&serial).unwrap();
+        builder.set_subject_name(&subject).unwrap();
+        builder
+            .set_issuer_name(issuer.map_or(&*subject, |issuer| issuer.certificate.subject_name()))
+            .unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(30).unwrap())
+            .unwrap();
+        if issuer.is_none() {
+            let constraints = BasicConstraints::new().critical().ca().build().unwrap();
+            builder.append_extension(constraints).unwrap();
+        }
+        if server {
+            let names = SubjectAlternativeName::new()
+                .ip("127.0.0.1")
+                .build(&builder.x509v3_context(issuer.map(|issuer| &*issuer.certificate), None))
+                .unwrap();
+            builder.append_extension(names).unwrap();
+        }
+        let signer = issuer.map_or(&key, |issuer| &issuer.key);
+        builder.sign(signer, MessageDigest::sha256()).unwrap();
+
+        Issued {
+            certificate: builder.build(),
+            key,
+        }
+    }
+
+    fn pki() -> Pki {
+        let ca = issue("ratquest test CA", 1, None, false);
+        let server = issue("127.0.0.1", 2, Some(&ca), true);
+        let client = issue("ratquest client", 3, Some(&ca), false);
+        Pki { ca, server, client }
+    }
+
+    fn write_file(test: &str, name: &str, contents: &[u8]) -> String {
+        let dir =
+            std::env::temp_dir().join(format!("ratquest-tls-{}-{}", std::process::id(), test));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    /// Answers every connection over TLS, asking for a client certificate signed by the CA
+    /// when `mutual` is set.
+    fn serve(pki: &Pki, mutual: bool) -> String {
+        let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+        acceptor.set_private_key(&pki.server.key).unwrap();
+        acceptor.set_certificate(&pki.server.certificate).unwrap();
+        if mutual {
+            acceptor
+                .cert_store_mut()
+                .add_cert(pki.ca.certificate.clone())
+                .unwrap();
+            acceptor.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+        }
+        let acceptor = acceptor.build();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("https://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = acceptor.accept(stream.unwrap()) else {
+                    continue;
+                };
+                let mut buf = [0; 4096];
+                let _ = stream.read(&mut buf);
+                let _ = stream.write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Length: 6\r\nConnection: close\r\n\r\nsecure",
+                );
+                let _ = stream.shutdown();
+            }
+        });
+        base
+    }
+
+    fn send(base: &str, tls: TlsSettings) -> RequestResult {
+        let request = PreparedRequest {
+            request_type: RequestType::GET,
+            url: format!("{}/", base),
+            body: String::new(),
+            headers: Vec::new(),
+            params: Vec::new(),
+            oauth2: None,
+            digest: None,
+            aws_sigv4: None,
+            hmac: None,
+            tls,
+            proxy: ProxyConfig::default(),
+        };
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(execute(request, &AuthCache::default()))
+    }
+
+    fn fail(base: &str, tls: TlsSettings) -> RequestError {
+        match send(base, tls) {
+            Ok(_) => panic!("expected {} to fail", base),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn test_private_ca_and_accept_invalid() {
+        let pki = pki();
+        let base = serve(&pki, false);
+        let ca_file = write_file("ca", "ca.pem", &pki.ca.certificate.to_pem().unwrap());
+
+        let error = fail(&base, TlsSettings::default());
+        assert_eq!(error.kind, ErrorKind::Tls);
+        let tls = error.tls.unwrap();
+        assert!(tls.verify_error.is_some());
+        assert_eq!(tls.chain[0].subject, "CN=127.0.0.1");
+
+        let trusted = TlsSettings {
+            ca_files: vec![ca_file],
+            ..TlsSettings::default()
+        };
+        let response = send(&base, trusted).unwrap();
+        assert_eq!(response.body, "secure");
+        let tls = response.tls.unwrap();
+        assert_eq!(tls.protocol.as_deref(), Some("TLSv1.3"));
+        assert_eq!(tls.http_version.as_deref(), Some("HTTP/1.1"));
+        assert_eq!(tls.verify_error, None);
+        let server = &tls.chain[0];
+        assert_eq!(server.alt_names, ["IP 127.0.0.1"]);
+        assert_eq!(server.issuer, "CN=ratquest test CA");
+        assert_eq!(server.serial, "02");
+        assert_eq!(server.sha256.len(), 32 * 3 - 1);
+        assert_eq!(server.days_left, 29);
+        assert!(server.expires_soon());
+
+        let insecure = TlsSettings {
+            accept_invalid_certs: Some(true),
+            ..TlsSettings::default()
+        };
+        assert_eq!(send(&base, insecure).unwrap().body, "secure");
+    }
+
+    #[test]
+    fn test_client_certificate_as_pem_and_pkcs12() {
+        let pki = pki();
+        let base = serve(&pki, true);
+        let ca_file = write_file("mtls", "ca.der", &pki.ca.certificate.to_der().unwrap());
+        let trusted = TlsSettings {
+            ca_files: vec![ca_file],
+            min_version: TlsVersion::Tls12,
+            ..TlsSettings::default()
+        };
+
+        fail(&base, trusted.clone());
+
+        // Certificate and key in one file, the key in its traditional EC form
+        let mut combined = pki.client.certificate.to_pem().unwrap();
+        combined.extend(
+            pki.client
+                .key
+                .ec_key()
+                .unwrap()
+                .private_key_to_pem()
+                .unwrap(),
+        );
+        let pem = TlsSettings {
+            client_cert: write_file("mtls", "client.pem", &combined),
+            ..trusted.clone()
+        };
+        assert_eq!(send(&base, pem).unwrap().body, "secure");
+
+        let archive = Pkcs12::builder()
+            .name("client")
+            .pkey(&pki.client.key)
+            .cert(&pki.client.certificate)
+            .build2("hunter2")
+            .unwrap();
+        let pkcs12 = TlsSettings {
+            client_cert: write_file("mtls", "client.p12", &archive.to_der().unwrap()),
+            client_cert_password: "hunter2".to_string(),
+            ..trusted.clone()
+        };
+        assert_eq!(send(&base, pkcs12.clone()).unwrap().body, "secure");
+
+        let wrong_password = TlsSettings {
+            client_cert_password: "nope".to_string(),
+            ..pkcs12
+        };
+        let error = fail(&base, wrong_password);
+        assert_eq!(error.kind, ErrorKind::Tls);
+        assert!(error.message.contains("PKCS#12"));
+    }
+
+    #[test]
+    fn test_request_settings_over_group_settings() {
+        let group = TlsSettings {
+            client_cert: "group.p12".to_string(),
+            client_cert_password: "group".to_string(),
+            ca_files: vec!["ca.pem".to_string()],
+            min_version: TlsVersion::Tls12,
+            ..TlsSettings::default()
+        };
+        let request = TlsSettings {
+            ca_files: vec!["ca.pem".to_string(), "extra.pem".to_string()],
+            accept_invalid_certs: Some(true),
+            ..TlsSettings::default()
+        };
+
+        let merged = request.over(&group);
+        assert_eq!(merged.client_cert, "group.p12");
+        assert_eq!(merged.ca_files, ["ca.pem", "extra.pem"]);
+        assert!(merged.accepts_invalid_certs());
+        assert_eq!(merged.min_version, TlsVersion::Tls12);
+
+        let own = TlsSettings {
+            client_cert: "request.pem".to_string(),
+            min_version: TlsVersion::Tls13,
+            ..TlsSettings::default()
+        };
+        let merged = own.over(&group);
+        assert_eq!(merged.client_cert, "request.pem");
+        assert!(merged.client_cert_password.is_empty());
+        assert_eq!(merged.min_version, TlsVersion::Tls13);
+        assert_eq!(TlsSettings::default().over(&group), group);
+
+        // A request can insist on checks its group skips
+        let insecure_group = TlsSettings {
+            accept_invalid_certs: Some(true),
+            ..TlsSettings::default()
+        };
+        let strict = TlsSettings {
+            accept_invalid_certs: Some(false),
+            ..TlsSettings::default()
+        };
+        assert!(!strict.over(&insecure_group).accepts_invalid_certs());
+        assert!(TlsSettings::default()
+            .over(&insecure_group)
+            .accepts_invalid_certs());
+    }
+}
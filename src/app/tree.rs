@@ -29,18 +29,29 @@ impl NodeValue for TreeNode {
     }
 }
 
+/// Tree node ID of the group with ID `group_id`.
+pub fn group_node_id(group_id: &str) -> String {
+    format!("group-{}", group_id)
+}
+
+/// Tree node ID of the request with ID `request_id`.
+pub fn request_node_id(request_id: &str) -> String {
+    format!("request-{}", request_id)
+}
+
 impl App {
     pub fn build_tree(&self) -> Tree<TreeNode> {
         let mut root = Node::new("/".to_string(), TreeNode::new("API Groups".to_string()));
 
-        for (group_name, requests) in &self.list {
-            let mut group_node = Node::new(
-                format!("group-{}", group_name),
-                TreeNode::new(group_name.clone()),
-            );
+        for group_id in &self.groups_vec {
+            let Some(requests) = self.list.get(group_id) else {
+                continue;
+            };
+            let group_name = self.group_name(group_id).unwrap_or_default().to_string();
+            let mut group_node = Node::new(group_node_id(group_id), TreeNode::new(group_name));
 
             for request in requests {
-                let request_id = format!("request-{}-{}", group_name, request.name);
+                let request_id = request_node_id(&request.id);
 
                 let (symbol, style) = match request.request_type {
                     RequestType::GET => ("○", Style::default().fg(Color::Green)),
@@ -2,6 +2,7 @@
 pub enum ActivePanel {
     Tree,
     Details,
+    Response,
 }
 
 #[derive(PartialEq, Clone)]
@@ -11,8 +12,8 @@ pub enum DetailField {
     Params,
     Headers,
     AuthType,
-    AuthUsername,
-    AuthPassword,
+    /// A field of the auth form, by its index in `AuthDetails::fields`.
+    AuthField(usize),
     None,
 }
 
@@ -27,3 +28,9 @@ pub enum ParameterInputMode {
     Key,
     Value,
 }
+
+#[derive(PartialEq)]
+pub enum VariableInputMode {
+    Key,
+    Value,
+}
@@ -4,11 +4,15 @@ pub mod ui_components;
 pub use app::models::{ApiRequest, AuthDetails, AuthType, BasicAuth, RequestDetails, RequestType};
 pub use app::state::{App, CurrentScreen};
 pub use app::ui_state::DetailField;
-use app::ui_state::{ActivePanel, HeaderInputMode};
+use app::ui_state::{ActivePanel, HeaderInputMode, VariableInputMode};
 use app::{state::Groups, ui_state::ParameterInputMode};
 
+use base64::{engine::general_purpose::STANDARD, Engine};
 use ratatui::crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode, KeyEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -16,7 +20,9 @@ use ratatui::{
     backend::{Backend, CrosstermBackend},
     Terminal,
 };
-use std::io;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::time::Duration;
 
 use ui::ui;
 
@@ -27,7 +33,12 @@ fn main() -> Result<(), io::Error> {
     enable_raw_mode()?;
     let stderr = io::stderr();
     let mut writer = BufWriter::new(stderr);
-    execute!(writer, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        writer,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
     let backend = CrosstermBackend::new(writer);
     let mut terminal = Terminal::new(backend)?;
 
@@ -40,7 +51,8 @@ fn main() -> Result<(), io::Error> {
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
 
@@ -51,8 +63,45 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<
     loop {
         terminal.draw(|f| ui(f, app))?;
 
-        if let Event::Key(key) = event::read()? {
+        if let Some(text) = app.clipboard.take() {
+            // A terminal that won't take the text is no reason to quit
+            app.clipboard_error = copy_to_clipboard(&text)
+                .err()
+                .map(|e| format!("Could not copy to the clipboard: {}", e));
+        }
+        if let Some(url) = app.browser_url.take() {
+            open_in_browser(&url);
+        }
+
+        // While a request or sign-in is in flight, wake up regularly to pick up its result
+        // and to keep the spinner and timer moving
+        if app.is_sending || app.is_authorizing() {
+            app.poll_request();
+            app.poll_authorization();
+            if !event::poll(Duration::from_millis(80))? {
+                continue;
+            }
+        }
+
+        let event = event::read()?;
+        if let Event::Paste(text) = &event {
+            app.handle_paste(text);
+        }
+
+        if let Event::Key(key) = event {
             if key.kind == KeyEventKind::Press {
+                let cancels = key.code == KeyCode::Esc
+                    || (key.code == KeyCode::Char('c')
+                        && key.modifiers.contains(event::KeyModifiers::CONTROL));
+                if cancels && app.is_sending {
+                    app.cancel_request();
+                    continue;
+                }
+                if cancels && app.is_authorizing() {
+                    app.cancel_authorization();
+                    continue;
+                }
+
                 match app.current_screen {
                     CurrentScreen::Main => {
                         match app.active_panel {
@@ -65,49 +114,28 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<
                                     app.tree_previous();
                                 }
                                 KeyCode::Right => {
-                                    if let Some(selected_id) = app.tree_state.selected() {
-                                        if selected_id.starts_with("request-") {
-                                            let parts: Vec<&str> =
-                                                selected_id.splitn(3, '-').collect();
-                                            if parts.len() == 3 {
-                                                let group_name = parts[1].to_string();
-                                                if let Some(group_idx) = app
-                                                    .groups_vec
-                                                    .iter()
-                                                    .position(|g| g == &group_name)
-                                                {
-                                                    if let Some(requests) =
-                                                        app.list.get(&group_name)
-                                                    {
-                                                        if let Some(request_idx) = requests
-                                                            .iter()
-                                                            .position(|r| r.name == parts[2])
-                                                        {
-                                                            app.selected_group_index =
-                                                                Some(group_idx);
-                                                            app.selected_request_index =
-                                                                Some(request_idx);
-                                                            app.sync_textarea_content();
-                                                            app.switch_to_details();
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                        }
-                                        app.tree_toggle();
+                                    if let Some((group_id, request_idx)) =
+                                        app.selected_tree_request()
+                                    {
+                                        app.selected_group_index =
+                                            app.groups_vec.iter().position(|g| g == &group_id);
+                                        app.selected_request_index = Some(request_idx);
+                                        app.sync_textarea_content();
+                                        app.switch_to_details();
                                     }
+                                    app.tree_toggle();
                                 }
                                 KeyCode::Left => {
                                     app.tree_toggle();
                                 }
                                 KeyCode::Enter => {
-                                    if let Some((group_name, request_idx)) =
+                                    if let Some((group_id, request_idx)) =
                                         app.handle_tree_selection()
                                     {
                                         app.selected_group_index = Some(
                                             app.groups_vec
                                                 .iter()
-                                                .position(|g| g == &group_name)
+                                                .position(|g| g == &group_id)
                                                 .unwrap_or(0),
                                         );
                                         app.selected_request_index = Some(request_idx);
@@ -122,6 +150,12 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<
                                     app.current_screen = CurrentScreen::Editing;
                                     app.groups = Some(Groups::Name);
                                 }
+                                KeyCode::Char('v') => {
+                                    app.open_environments();
+                                }
+                                KeyCode::Char('i') => {
+                                    app.open_import();
+                                }
                                 KeyCode::Char('d') => {
                                     if !app.list.is_empty() {
                                         app.update_groups_vec();
@@ -130,23 +164,88 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<
                                 }
                                 KeyCode::Char('a') => {
                                     if !app.list.is_empty() {
-                                        if let Some(selected_id) = app.tree_state.selected() {
-                                            if selected_id.starts_with("group-") {
-                                                let group_name = selected_id
-                                                    .strip_prefix("group-")
-                                                    .unwrap()
-                                                    .to_string();
-                                                app.add_request(group_name);
-                                            } else if selected_id.starts_with("request-") {
-                                                let parts: Vec<&str> =
-                                                    selected_id.splitn(3, '-').collect();
-                                                if parts.len() == 3 {
-                                                    app.add_request(parts[1].to_string());
-                                                }
-                                            }
+                                        if let Some(group_id) = app.selected_tree_group() {
+                                            app.add_request(group_id);
                                         }
                                     }
                                 }
+                                KeyCode::Char('c') => {
+                                    app.export_curl();
+                                }
+                                KeyCode::Char('p') => {
+                                    app.open_curl_import();
+                                }
+                                KeyCode::Char('x') => {
+                                    app.open_response_tree();
+                                }
+                                KeyCode::Char('t') => {
+                                    app.open_settings();
+                                }
+                                KeyCode::Char('g') => {
+                                    app.open_global_settings();
+                                }
+                                _ => {}
+                            },
+                            ActivePanel::Response if app.history_view.open => match key.code {
+                                KeyCode::Up => app.history_previous(),
+                                KeyCode::Down => app.history_next(),
+                                KeyCode::Enter => app.show_history_entry(),
+                                KeyCode::Char('m') => app.mark_history_entry(),
+                                KeyCode::Char('d') => app.diff_history(),
+                                KeyCode::Esc | KeyCode::Char('h') => app.close_history(),
+                                _ => {}
+                            },
+                            ActivePanel::Response if app.response_view.filter_input.is_some() => {
+                                match key.code {
+                                    KeyCode::Esc => app.cancel_response_filter(),
+                                    KeyCode::Enter => app.finish_response_filter(),
+                                    KeyCode::Char(c) => app.push_response_filter(c),
+                                    KeyCode::Backspace => app.pop_response_filter(),
+                                    _ => {}
+                                }
+                            }
+                            ActivePanel::Response if app.response_view.searching => {
+                                match key.code {
+                                    KeyCode::Esc => app.clear_response_search(),
+                                    KeyCode::Enter => app.finish_response_search(),
+                                    KeyCode::Char(c) => app.push_response_search(c),
+                                    KeyCode::Backspace => app.pop_response_search(),
+                                    _ => {}
+                                }
+                            }
+                            ActivePanel::Response => match key.code {
+                                KeyCode::Char('s')
+                                    if key.modifiers.contains(event::KeyModifiers::CONTROL) =>
+                                {
+                                    app.start_request();
+                                }
+                                KeyCode::Up => app.scroll_response(-1),
+                                KeyCode::Down => app.scroll_response(1),
+                                KeyCode::Left => app.scroll_response_horizontally(-4),
+                                KeyCode::Right => app.scroll_response_horizontally(4),
+                                KeyCode::PageUp => app.page_response(-1),
+                                KeyCode::PageDown | KeyCode::Char(' ') => app.page_response(1),
+                                KeyCode::Home => app.scroll_response(-i32::from(u16::MAX)),
+                                KeyCode::End => app.scroll_response(i32::from(u16::MAX)),
+                                KeyCode::Char('/') => app.start_response_search(),
+                                KeyCode::Char('f') => app.start_response_filter(),
+                                KeyCode::Char('h') => app.open_history(),
+                                KeyCode::Char('n') => app.next_response_match(),
+                                KeyCode::Char('N') => app.previous_response_match(),
+                                KeyCode::Char('x') => app.open_response_tree(),
+                                KeyCode::Char('t') => app.toggle_response_tab(),
+                                KeyCode::Tab => {
+                                    app.active_panel = ActivePanel::Details;
+                                    app.current_detail_field = DetailField::Url;
+                                }
+                                KeyCode::BackTab => {
+                                    app.active_panel = ActivePanel::Details;
+                                    app.current_detail_field = last_detail_field(app);
+                                }
+                                KeyCode::Esc if !app.response_view.search_query.is_empty() => {
+                                    app.clear_response_search();
+                                }
+                                KeyCode::Esc => app.switch_to_tree(),
                                 _ => {}
                             },
                             ActivePanel::Details => {
@@ -156,6 +255,7 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<
                                             match key.code {
                                                 KeyCode::Esc => {
                                                     app.adding_params = false;
+                                                    app.editing_param = None;
                                                     app.params_key_input.clear();
                                                     app.params_value_input.clear();
                                                 }
@@ -202,9 +302,18 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<
                                             }
                                         } else {
                                             match key.code {
-                                                KeyCode::Enter => {
+                                                KeyCode::Enter | KeyCode::Char('a') => {
                                                     app.start_adding_params();
                                                 }
+                                                KeyCode::Char('e') => {
+                                                    app.start_editing_param();
+                                                }
+                                                KeyCode::Char('d') | KeyCode::Delete => {
+                                                    app.delete_selected_param();
+                                                }
+                                                KeyCode::Char(' ') => {
+                                                    app.toggle_selected_param();
+                                                }
                                                 // Handle navigation for headers section when not adding
                                                 KeyCode::Left
                                                 | KeyCode::Right
@@ -225,6 +334,7 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<
                                             match key.code {
                                                 KeyCode::Esc => {
                                                     app.adding_header = false;
+                                                    app.editing_header = None;
                                                     app.header_key_input.clear();
                                                     app.header_value_input.clear();
                                                 }
@@ -271,9 +381,18 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<
                                             }
                                         } else {
                                             match key.code {
-                                                KeyCode::Enter => {
+                                                KeyCode::Enter | KeyCode::Char('a') => {
                                                     app.start_adding_header();
                                                 }
+                                                KeyCode::Char('e') => {
+                                                    app.start_editing_header();
+                                                }
+                                                KeyCode::Char('d') | KeyCode::Delete => {
+                                                    app.delete_selected_header();
+                                                }
+                                                KeyCode::Char(' ') => {
+                                                    app.toggle_selected_header();
+                                                }
                                                 // Handle navigation for headers section when not adding
                                                 KeyCode::Left
                                                 | KeyCode::Right
@@ -293,8 +412,7 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<
                                     DetailField::Url
                                     | DetailField::Body
                                     | DetailField::AuthType
-                                    | DetailField::AuthUsername
-                                    | DetailField::AuthPassword
+                                    | DetailField::AuthField(_)
                                     | DetailField::None => {
                                         handle_common_navigation(app, key);
                                     }
@@ -313,7 +431,6 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<
                         KeyCode::Enter => {
                             if !app.key_input.is_empty() {
                                 app.save_group();
-                                app.current_screen = CurrentScreen::Main;
                             }
                         }
                         KeyCode::Char(c) => {
@@ -384,6 +501,139 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<
                         }
                         _ => {}
                     },
+                    CurrentScreen::Environments => {
+                        if app.adding_environment {
+                            match key.code {
+                                KeyCode::Esc => {
+                                    app.adding_environment = false;
+                                    app.environment_name_input.clear();
+                                }
+                                KeyCode::Enter => app.save_environment(),
+                                KeyCode::Char(c) => app.environment_name_input.push(c),
+                                KeyCode::Backspace => {
+                                    app.environment_name_input.pop();
+                                }
+                                _ => {}
+                            }
+                        } else if app.adding_variable {
+                            match key.code {
+                                KeyCode::Esc => {
+                                    app.adding_variable = false;
+                                    app.variable_key_input.clear();
+                                    app.variable_value_input.clear();
+                                }
+                                KeyCode::Enter => match app.variable_input_mode {
+                                    VariableInputMode::Key => {
+                                        if !app.variable_key_input.is_empty() {
+                                            app.toggle_variable_input_mode();
+                                        }
+                                    }
+                                    VariableInputMode::Value => app.save_variable(),
+                                },
+                                KeyCode::Tab if !app.variable_key_input.is_empty() => {
+                                    app.toggle_variable_input_mode();
+                                }
+                                KeyCode::Char(c) => match app.variable_input_mode {
+                                    VariableInputMode::Key => app.variable_key_input.push(c),
+                                    VariableInputMode::Value => app.variable_value_input.push(c),
+                                },
+                                KeyCode::Backspace => match app.variable_input_mode {
+                                    VariableInputMode::Key => {
+                                        app.variable_key_input.pop();
+                                    }
+                                    VariableInputMode::Value => {
+                                        app.variable_value_input.pop();
+                                    }
+                                },
+                                _ => {}
+                            }
+                        } else {
+                            match key.code {
+                                KeyCode::Esc => {
+                                    app.current_screen = CurrentScreen::Main;
+                                }
+                                KeyCode::Up => app.previous_environment(),
+                                KeyCode::Down => app.next_environment(),
+                                KeyCode::Enter => app.toggle_selected_environment(),
+                                KeyCode::Char('n') => app.start_adding_environment(),
+                                KeyCode::Char('a') => app.start_adding_variable(),
+                                KeyCode::Char('d') => app.delete_selected_environment(),
+                                _ => {}
+                            }
+                        }
+                    }
+                    CurrentScreen::Importing => {
+                        if app.import_result.is_some() {
+                            if matches!(key.code, KeyCode::Enter | KeyCode::Esc) {
+                                app.import_result = None;
+                                app.current_screen = CurrentScreen::Main;
+                            }
+                        } else {
+                            match key.code {
+                                KeyCode::Esc => {
+                                    app.import_path_input.clear();
+                                    app.current_screen = CurrentScreen::Main;
+                                }
+                                KeyCode::Enter if !app.import_path_input.trim().is_empty() => {
+                                    app.run_import();
+                                }
+                                KeyCode::Char(c) => app.import_path_input.push(c),
+                                KeyCode::Backspace => {
+                                    app.import_path_input.pop();
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                    CurrentScreen::CurlExport => {
+                        if matches!(key.code, KeyCode::Enter | KeyCode::Esc) {
+                            app.curl_export = None;
+                            app.current_screen = CurrentScreen::Main;
+                        }
+                    }
+                    CurrentScreen::CurlImport => match key.code {
+                        KeyCode::Esc => {
+                            app.curl_input.clear();
+                            app.curl_error = None;
+                            app.current_screen = CurrentScreen::Main;
+                        }
+                        KeyCode::Enter if !app.curl_input.trim().is_empty() => {
+                            app.run_curl_import();
+                        }
+                        KeyCode::Char(c) => app.curl_input.push(c),
+                        KeyCode::Backspace => {
+                            app.curl_input.pop();
+                        }
+                        _ => {}
+                    },
+                    CurrentScreen::ResponseDiff => match key.code {
+                        KeyCode::Esc | KeyCode::Char('q') => app.close_history_diff(),
+                        KeyCode::Up => app.scroll_history_diff(-1),
+                        KeyCode::Down => app.scroll_history_diff(1),
+                        KeyCode::PageUp => app.scroll_history_diff(-20),
+                        KeyCode::PageDown | KeyCode::Char(' ') => app.scroll_history_diff(20),
+                        _ => {}
+                    },
+                    CurrentScreen::ResponseTree => match key.code {
+                        KeyCode::Esc | KeyCode::Char('q') => app.close_response_tree(),
+                        KeyCode::Up => app.response_tree_previous(),
+                        KeyCode::Down => app.response_tree_next(),
+                        KeyCode::Left | KeyCode::Right | KeyCode::Enter => {
+                            app.response_tree_toggle();
+                        }
+                        KeyCode::Char('y') => app.copy_response_tree_path(),
+                        _ => {}
+                    },
+                    CurrentScreen::Settings => match key.code {
+                        KeyCode::Esc => app.close_settings(),
+                        KeyCode::Enter => app.save_settings(),
+                        KeyCode::Up | KeyCode::BackTab => app.settings_previous(),
+                        KeyCode::Down | KeyCode::Tab => app.settings_next(),
+                        KeyCode::Left | KeyCode::Right => app.cycle_setting(),
+                        KeyCode::Char(c) => app.push_settings_char(c),
+                        KeyCode::Backspace => app.pop_settings_char(),
+                        _ => {}
+                    },
                     CurrentScreen::RequestDetail => match key.code {
                         KeyCode::Esc => {
                             app.current_screen = CurrentScreen::Main;
@@ -398,21 +648,65 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<
     }
 }
 
+/// Asks the terminal to put `text` on the system clipboard (OSC 52). Works over SSH, and
+/// terminals without support simply ignore it.
+fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    let mut stderr = io::stderr();
+    write!(stderr, "\x1b]52;c;{}\x07", STANDARD.encode(text))?;
+    stderr.flush()
+}
+
+/// Opens `url` in the default browser. Failures are ignored, the URL is also shown in the
+/// response pane and copied to the clipboard.
+fn open_in_browser(url: &str) {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler");
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    // Anything the opener prints would end up on top of the UI
+    let _ = command
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+}
+
+/// The fields of the details panel in Tab order. The auth form adds one per field.
+fn detail_fields(app: &App) -> Vec<DetailField> {
+    let mut fields = vec![
+        DetailField::Url,
+        DetailField::Body,
+        DetailField::Params,
+        DetailField::Headers,
+        DetailField::AuthType,
+    ];
+    fields.extend((0..app.auth_field_count()).map(DetailField::AuthField));
+    fields
+}
+
+/// The bottom field of the details panel, which depends on the auth type.
+fn last_detail_field(app: &App) -> DetailField {
+    detail_fields(app).pop().unwrap_or(DetailField::AuthType)
+}
+
 fn handle_common_navigation(app: &mut App, key: event::KeyEvent) {
     match key.code {
-        KeyCode::Char(c) => {
-            if c == 's' && key.modifiers.contains(event::KeyModifiers::CONTROL) {
-                // Create a runtime and block on the async operation
-                let rt = tokio::runtime::Runtime::new().unwrap();
-                let _ = rt.block_on(app.send_request());
-            } else {
-                let _ = match app.current_detail_field {
-                    DetailField::Url => app.url_textarea.input(Event::Key(key)),
-                    DetailField::Body => app.body_textarea.input(Event::Key(key)),
-                    DetailField::AuthUsername => app.auth_username_textarea.input(Event::Key(key)),
-                    DetailField::AuthPassword => app.auth_password_textarea.input(Event::Key(key)),
-                    _ => false,
-                };
+        KeyCode::Char('s') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+            app.start_request();
+        }
+        KeyCode::Char('o') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+            app.start_authorization();
+        }
+        // Choices in the auth form change with Left and Right
+        KeyCode::Left | KeyCode::Right if app.is_auth_choice(&app.current_detail_field) => {
+            if let DetailField::AuthField(index) = app.current_detail_field {
+                app.cycle_auth_choice(index);
             }
         }
         KeyCode::Left => {
@@ -424,63 +718,39 @@ fn handle_common_navigation(app: &mut App, key: event::KeyEvent) {
                 app.handle_left_in_textarea(key);
             }
         }
-        KeyCode::Right => {
-            if app.current_detail_field == DetailField::AuthType {
-                app.next_auth_type();
-            } else {
-                let _ = match app.current_detail_field {
-                    DetailField::Url => app.url_textarea.input(Event::Key(key)),
-                    DetailField::Body => app.body_textarea.input(Event::Key(key)),
-                    DetailField::AuthUsername => app.auth_username_textarea.input(Event::Key(key)),
-                    DetailField::AuthPassword => app.auth_password_textarea.input(Event::Key(key)),
-                    _ => false,
-                };
-            }
+        KeyCode::Right if app.current_detail_field == DetailField::AuthType => {
+            app.next_auth_type();
+        }
+        // Up and Down move within the body and the params/headers lists before moving
+        // between fields
+        KeyCode::Up | KeyCode::Down if app.handle_vertical_in_field(key) => {}
+        // Enter only makes sense in the body, the other fields are single-line
+        KeyCode::Enter if app.current_detail_field != DetailField::Body => {}
+        // Moving on from the last field focuses the response below it
+        KeyCode::Down | KeyCode::Tab
+            if app.last_response.is_some()
+                && app.current_detail_field == last_detail_field(app) =>
+        {
+            app.switch_to_response();
         }
         KeyCode::Up | KeyCode::BackTab => {
-            app.current_detail_field = if app.get_current_request_auth_type() == "None" {
-                match app.current_detail_field {
-                    DetailField::Url => DetailField::AuthType,
-                    DetailField::Body => DetailField::Url,
-                    DetailField::Params => DetailField::Body,
-                    DetailField::Headers => DetailField::Params,
-                    DetailField::AuthType => DetailField::Headers,
-                    _ => DetailField::AuthType,
-                }
-            } else {
-                match app.current_detail_field {
-                    DetailField::Url => DetailField::AuthPassword,
-                    DetailField::Body => DetailField::Url,
-                    DetailField::Params => DetailField::Body,
-                    DetailField::Headers => DetailField::Params,
-                    DetailField::AuthType => DetailField::Headers,
-                    DetailField::AuthUsername => DetailField::AuthType,
-                    DetailField::AuthPassword => DetailField::AuthUsername,
-                    _ => DetailField::AuthPassword,
-                }
+            let fields = detail_fields(app);
+            app.current_detail_field = match fields
+                .iter()
+                .position(|field| *field == app.current_detail_field)
+            {
+                Some(0) | None => fields[fields.len() - 1].clone(),
+                Some(index) => fields[index - 1].clone(),
             };
         }
         KeyCode::Down | KeyCode::Tab => {
-            app.current_detail_field = if app.get_current_request_auth_type() == "None" {
-                match app.current_detail_field {
-                    DetailField::Url => DetailField::Body,
-                    DetailField::Body => DetailField::Params,
-                    DetailField::Params => DetailField::Headers,
-                    DetailField::Headers => DetailField::AuthType,
-                    DetailField::AuthType => DetailField::Url,
-                    _ => DetailField::Url,
-                }
-            } else {
-                match app.current_detail_field {
-                    DetailField::Url => DetailField::Body,
-                    DetailField::Body => DetailField::Params,
-                    DetailField::Params => DetailField::Headers,
-                    DetailField::Headers => DetailField::AuthType,
-                    DetailField::AuthType => DetailField::AuthUsername,
-                    DetailField::AuthUsername => DetailField::AuthPassword,
-                    DetailField::AuthPassword => DetailField::Url,
-                    _ => DetailField::Url,
-                }
+            let fields = detail_fields(app);
+            app.current_detail_field = match fields
+                .iter()
+                .position(|field| *field == app.current_detail_field)
+            {
+                Some(index) if index + 1 < fields.len() => fields[index + 1].clone(),
+                _ => DetailField::Url,
             };
         }
         KeyCode::Esc => {
@@ -490,8 +760,7 @@ fn handle_common_navigation(app: &mut App, key: event::KeyEvent) {
             let _ = match app.current_detail_field {
                 DetailField::Url => app.url_textarea.input(Event::Key(key)),
                 DetailField::Body => app.body_textarea.input(Event::Key(key)),
-                DetailField::AuthUsername => app.auth_username_textarea.input(Event::Key(key)),
-                DetailField::AuthPassword => app.auth_password_textarea.input(Event::Key(key)),
+                DetailField::AuthField(index) => app.auth_field_input(index, key),
                 _ => false,
             };
         }
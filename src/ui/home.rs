@@ -6,12 +6,19 @@ use ratatui::{
 
 use crate::app::state::{App, CurrentScreen, Groups};
 use crate::ui::popups::{
-    add_request_popup, editing_popup, exiting_popup, render_header_popup, render_params_popup,
+    add_request_popup, curl_export_popup, curl_import_popup, editing_popup, environments_popup,
+    exiting_popup, import_popup, render_header_popup, render_params_popup, response_diff_popup,
+    response_tree_popup, settings_popup,
 };
 use crate::ui_components;
 use ui_components::details::*;
 use ui_components::footer::*;
 use ui_components::groups::*;
+
+/// Height of the body section, borders included.
+const BODY_MIN_HEIGHT: u16 = 6;
+const BODY_MAX_HEIGHT: u16 = 16;
+
 pub fn ui(frame: &mut Frame, app: &mut App) {
     // First render all the regular UI elements
     render_base_ui(frame, app);
@@ -51,18 +58,24 @@ pub fn render_base_ui(frame: &mut Frame, app: &mut App) {
     let inner_area = detail_view_component(app, inner_layout, frame);
 
     // Render the details view in the right panel
+    let mut response_area = None;
     if let Some(request) = app.get_current_request() {
+        // The body grows with its content up to a limit, after which the textarea scrolls
+        let body_lines = app.body_textarea.lines().len() as u16;
+        let body_height = (body_lines + 2).clamp(BODY_MIN_HEIGHT, BODY_MAX_HEIGHT);
+        let auth_height = auth_section_height(&request.details);
+
         // Create the layout for the right panel within the block
         let details_layout = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Length(3),  // URL
-                Constraint::Length(6),  // Body
-                Constraint::Length(8),  // Parameters
-                Constraint::Length(8),  // Headers
-                Constraint::Length(10), // Auth
-                Constraint::Length(3),  // Send Request Bar
-                Constraint::Min(0),     // Response Area
+                Constraint::Length(3),           // URL
+                Constraint::Length(body_height), // Body
+                Constraint::Length(8),           // Parameters
+                Constraint::Length(8),           // Headers
+                Constraint::Length(auth_height), // Auth
+                Constraint::Length(3),           // Send Request Bar
+                Constraint::Min(0),              // Response Area
             ])
             .split(inner_area);
 
@@ -73,6 +86,7 @@ pub fn render_base_ui(frame: &mut Frame, app: &mut App) {
         render_auth_section(frame, app, &request.details, details_layout[4]);
         render_send_request_section(frame, app, details_layout[5]);
         render_response_section(frame, app, details_layout[6]);
+        response_area = Some(details_layout[6]);
     } else {
         // If no request is selected, show default message centered in the block
         frame.render_widget(
@@ -81,6 +95,11 @@ pub fn render_base_ui(frame: &mut Frame, app: &mut App) {
         );
     }
 
+    // Remember the size of the response pane for paging, minus its borders
+    if let Some(area) = response_area {
+        app.response_view.viewport = (area.height.saturating_sub(2), area.width.saturating_sub(2));
+    }
+
     // Footer
     render_footer(frame, app, chunks[2]);
 
@@ -96,6 +115,34 @@ pub fn render_base_ui(frame: &mut Frame, app: &mut App) {
     if app.current_screen == CurrentScreen::AddingRequest {
         add_request_popup(frame, app);
     }
+
+    if app.current_screen == CurrentScreen::Environments {
+        environments_popup(frame, app);
+    }
+
+    if app.current_screen == CurrentScreen::Importing {
+        import_popup(frame, app);
+    }
+
+    if app.current_screen == CurrentScreen::CurlExport {
+        curl_export_popup(frame, app);
+    }
+
+    if app.current_screen == CurrentScreen::CurlImport {
+        curl_import_popup(frame, app);
+    }
+
+    if app.current_screen == CurrentScreen::ResponseTree {
+        response_tree_popup(frame, app);
+    }
+
+    if app.current_screen == CurrentScreen::ResponseDiff {
+        response_diff_popup(frame, app);
+    }
+
+    if app.current_screen == CurrentScreen::Settings {
+        settings_popup(frame, app);
+    }
 }
 
 pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
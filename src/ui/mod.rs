@@ -2,5 +2,7 @@ mod home;
 mod popups;
 pub use home::{centered_rect, ui};
 pub use popups::{
-    add_request_popup, editing_popup, exiting_popup, render_header_popup, render_params_popup,
+    add_request_popup, curl_export_popup, curl_import_popup, editing_popup, environments_popup,
+    exiting_popup, import_popup, render_header_popup, render_params_popup, response_diff_popup,
+    response_tree_popup,
 };
@@ -1,14 +1,17 @@
 use crate::{app::ui_state::HeaderInputMode, ui::centered_rect, RequestType};
 use ratatui::{
     layout::{Constraint, Direction, Layout},
-    style::{Color, Style},
-    text::Text,
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
     widgets::{Block, Borders, Clear, Paragraph, Wrap},
     Frame,
 };
 
+use crate::app::auth::AuthFieldKind;
+use crate::app::history::DiffLine;
+use crate::app::settings::SettingsTarget;
 use crate::app::state::App;
-use crate::app::ui_state::ParameterInputMode;
+use crate::app::ui_state::{ParameterInputMode, VariableInputMode};
 
 pub fn add_request_popup(frame: &mut Frame, app: &App) {
     let popup_block = Block::default()
@@ -115,7 +118,11 @@ pub fn render_header_popup(frame: &mut Frame, app: &App) {
 
     // Create and render the popup block
     let popup_block = Block::default()
-        .title("Add Header")
+        .title(if app.editing_header.is_some() {
+            "Edit Header"
+        } else {
+            "Add Header"
+        })
         .borders(Borders::ALL)
         .style(Style::default().bg(Color::DarkGray));
 
@@ -177,7 +184,11 @@ pub fn render_params_popup(frame: &mut Frame, app: &mut App) {
 
     // Create and render the popup block
     let popup_block = Block::default()
-        .title("Add Parameter")
+        .title(if app.editing_param.is_some() {
+            "Edit Parameter"
+        } else {
+            "Add Parameter"
+        })
         .borders(Borders::ALL)
         .style(Style::default().bg(Color::DarkGray));
 
@@ -235,3 +246,478 @@ pub fn render_params_popup(frame: &mut Frame, app: &mut App) {
     .style(Style::default().fg(Color::Gray));
     frame.render_widget(instructions, inner_area[2]);
 }
+
+pub fn environments_popup(frame: &mut Frame, app: &App) {
+    let area = centered_rect(70, 60, frame.area());
+    frame.render_widget(Clear, area);
+
+    let popup_block = Block::default()
+        .title("Environments")
+        .borders(Borders::ALL)
+        .style(Style::default().bg(Color::DarkGray));
+
+    frame.render_widget(popup_block, area);
+
+    let inner_area = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Percentage(40), // Environment list
+            Constraint::Min(3),         // Variables of the highlighted environment
+            Constraint::Length(3),      // Input
+            Constraint::Length(2),      // Instructions
+        ])
+        .split(area);
+
+    // Environment list
+    let environment_lines: Vec<Line> = if app.environments.is_empty() {
+        vec![Line::from(Span::styled(
+            "No environments yet, press n to create one",
+            Style::default().fg(Color::Gray),
+        ))]
+    } else {
+        app.environments
+            .iter()
+            .enumerate()
+            .map(|(idx, env)| {
+                let marker = if app.active_environment == Some(idx) {
+                    "● "
+                } else {
+                    "○ "
+                };
+                let style = if idx == app.selected_environment_index {
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::White)
+                };
+                Line::from(Span::styled(format!("{}{}", marker, env.name), style))
+            })
+            .collect()
+    };
+
+    let environment_list = Paragraph::new(environment_lines)
+        .block(Block::default().title("Name").borders(Borders::ALL));
+    frame.render_widget(environment_list, inner_area[0]);
+
+    // Variables
+    let variables_text = app
+        .environments
+        .get(app.selected_environment_index)
+        .map(|env| {
+            env.sorted_variables()
+                .iter()
+                .map(|(k, v)| format!("{} = {}", k, v))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .unwrap_or_default();
+
+    let variables = Paragraph::new(variables_text)
+        .block(Block::default().title("Variables").borders(Borders::ALL))
+        .wrap(Wrap { trim: false });
+    frame.render_widget(variables, inner_area[1]);
+
+    // Input row, only while creating an environment or a variable
+    if app.adding_environment {
+        let name_input = Paragraph::new(app.environment_name_input.as_str())
+            .block(
+                Block::default()
+                    .title("Environment Name")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Yellow)),
+            )
+            .style(Style::default().fg(Color::White));
+        frame.render_widget(name_input, inner_area[2]);
+    } else if app.adding_variable {
+        let input_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+            .split(inner_area[2]);
+
+        let key_input = Paragraph::new(app.variable_key_input.as_str())
+            .block(
+                Block::default()
+                    .title("Variable")
+                    .borders(Borders::ALL)
+                    .border_style(
+                        if matches!(app.variable_input_mode, VariableInputMode::Key) {
+                            Style::default().fg(Color::Yellow)
+                        } else {
+                            Style::default()
+                        },
+                    ),
+            )
+            .style(Style::default().fg(Color::White));
+        frame.render_widget(key_input, input_layout[0]);
+
+        let value_input = Paragraph::new(app.variable_value_input.as_str())
+            .block(
+                Block::default()
+                    .title("Value")
+                    .borders(Borders::ALL)
+                    .border_style(
+                        if matches!(app.variable_input_mode, VariableInputMode::Value) {
+                            Style::default().fg(Color::Yellow)
+                        } else {
+                            Style::default()
+                        },
+                    ),
+            )
+            .style(Style::default().fg(Color::White));
+        frame.render_widget(value_input, input_layout[1]);
+    }
+
+    // Instructions
+    let instructions = Paragraph::new(if app.adding_environment {
+        "Enter environment name (Enter to save, Esc to cancel)"
+    } else if app.adding_variable {
+        match app.variable_input_mode {
+            VariableInputMode::Key => "Enter variable name (Enter/Tab to move to value)",
+            VariableInputMode::Value => "Enter variable value (Enter to save)",
+        }
+    } else {
+        "(↑/↓) select / (Enter) activate or deactivate / (n) new environment / (a) add variable / (d) delete / (Esc) close"
+    })
+    .style(Style::default().fg(Color::Gray))
+    .wrap(Wrap { trim: true });
+    frame.render_widget(instructions, inner_area[3]);
+}
+
+pub fn import_popup(frame: &mut Frame, app: &App) {
+    let area = centered_rect(70, 50, frame.area());
+    frame.render_widget(Clear, area);
+
+    let popup_block = Block::default()
+        .title("Import Postman Collection")
+        .borders(Borders::ALL)
+        .style(Style::default().bg(Color::DarkGray));
+
+    frame.render_widget(popup_block, area);
+
+    let inner_area = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(3), // Path input
+            Constraint::Min(1),    // Result
+            Constraint::Length(2), // Instructions
+        ])
+        .split(area);
+
+    let path_input = Paragraph::new(app.import_path_input.as_str())
+        .block(
+            Block::default()
+                .title("Collection File (v2.1 JSON)")
+                .borders(Borders::ALL)
+                .border_style(if app.import_result.is_none() {
+                    Style::default().fg(Color::Yellow)
+                } else {
+                    Style::default()
+                }),
+        )
+        .style(Style::default().fg(Color::White));
+    frame.render_widget(path_input, inner_area[0]);
+
+    let result_lines: Vec<Line> = match &app.import_result {
+        None => Vec::new(),
+        Some(Err(error)) => vec![Line::from(Span::styled(
+            error.as_str(),
+            Style::default().fg(Color::Red),
+        ))],
+        Some(Ok(summary)) => {
+            let mut lines = vec![Line::from(Span::styled(
+                format!(
+                    "Imported {} requests into {} groups",
+                    summary.requests, summary.groups
+                ),
+                Style::default().fg(Color::Green),
+            ))];
+            if !summary.warnings.is_empty() {
+                lines.push(Line::from(""));
+                lines.push(Line::from(Span::styled(
+                    format!("{} items were not fully imported:", summary.warnings.len()),
+                    Style::default().fg(Color::Yellow),
+                )));
+                lines.extend(
+                    summary
+                        .warnings
+                        .iter()
+                        .map(|warning| Line::from(format!("  • {}", warning))),
+                );
+            }
+            lines
+        }
+    };
+
+    let result = Paragraph::new(result_lines).wrap(Wrap { trim: false });
+    frame.render_widget(result, inner_area[1]);
+
+    let instructions = Paragraph::new(if app.import_result.is_none() {
+        "Enter the path to the collection (Enter to import, Esc to cancel)"
+    } else {
+        "Press Enter or Esc to close"
+    })
+    .style(Style::default().fg(Color::Gray));
+    frame.render_widget(instructions, inner_area[2]);
+}
+
+pub fn curl_export_popup(frame: &mut Frame, app: &App) {
+    let area = centered_rect(70, 50, frame.area());
+    frame.render_widget(Clear, area);
+
+    let popup_block = Block::default()
+        .title("Export as curl")
+        .borders(Borders::ALL)
+        .style(Style::default().bg(Color::DarkGray));
+
+    frame.render_widget(popup_block, area);
+
+    let inner_area = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Min(1),    // Command
+            Constraint::Length(2), // Instructions
+        ])
+        .split(area);
+
+    let command = Paragraph::new(app.curl_export.as_deref().unwrap_or_default())
+        .block(Block::default().borders(Borders::ALL))
+        .style(Style::default().fg(Color::White))
+        .wrap(Wrap { trim: false });
+    frame.render_widget(command, inner_area[0]);
+
+    let instructions = Paragraph::new("Copied to the clipboard. Press Enter or Esc to close")
+        .style(Style::default().fg(Color::Gray));
+    frame.render_widget(instructions, inner_area[1]);
+}
+
+pub fn curl_import_popup(frame: &mut Frame, app: &App) {
+    let area = centered_rect(70, 50, frame.area());
+    frame.render_widget(Clear, area);
+
+    let popup_block = Block::default()
+        .title("Import curl Command")
+        .borders(Borders::ALL)
+        .style(Style::default().bg(Color::DarkGray));
+
+    frame.render_widget(popup_block, area);
+
+    let inner_area = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Min(3),    // Command input
+            Constraint::Length(2), // Error
+            Constraint::Length(2), // Instructions
+        ])
+        .split(area);
+
+    let group = app
+        .selected_tree_group()
+        .and_then(|group_id| app.group_name(&group_id).map(str::to_string))
+        .unwrap_or_else(|| "Imported".to_string());
+    let command_input = Paragraph::new(app.curl_input.as_str())
+        .block(
+            Block::default()
+                .title(format!("Paste a curl command (into {})", group))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow)),
+        )
+        .style(Style::default().fg(Color::White))
+        .wrap(Wrap { trim: false });
+    frame.render_widget(command_input, inner_area[0]);
+
+    if let Some(error) = &app.curl_error {
+        let error = Paragraph::new(error.as_str())
+            .style(Style::default().fg(Color::Red))
+            .wrap(Wrap { trim: false });
+        frame.render_widget(error, inner_area[1]);
+    }
+
+    let instructions =
+        Paragraph::new("(Enter) import / (Esc) cancel").style(Style::default().fg(Color::Gray));
+    frame.render_widget(instructions, inner_area[2]);
+}
+
+pub fn response_tree_popup(frame: &mut Frame, app: &mut App) {
+    let area = centered_rect(80, 80, frame.area());
+    frame.render_widget(Clear, area);
+
+    let popup_block = Block::default()
+        .title("Response Explorer")
+        .borders(Borders::ALL)
+        .style(Style::default().bg(Color::DarkGray));
+
+    frame.render_widget(popup_block, area);
+
+    let inner_area = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Min(1),    // Tree
+            Constraint::Length(1), // Path of the selected node
+            Constraint::Length(1), // Instructions
+        ])
+        .split(area);
+
+    let tree_block = Block::default().borders(Borders::ALL);
+    let tree_area = tree_block.inner(inner_area[0]);
+    frame.render_widget(tree_block, inner_area[0]);
+    app.render_response_tree(frame, tree_area);
+
+    let path = Paragraph::new(app.response_tree_path().unwrap_or_default())
+        .style(Style::default().fg(Color::Cyan));
+    frame.render_widget(path, inner_area[1]);
+
+    let instructions = Paragraph::new("→/← expand/collapse, y copy path, Esc close")
+        .style(Style::default().fg(Color::Gray));
+    frame.render_widget(instructions, inner_area[2]);
+}
+
+pub fn response_diff_popup(frame: &mut Frame, app: &App) {
+    let Some(diff) = &app.history_view.diff else {
+        return;
+    };
+
+    let area = centered_rect(80, 80, frame.area());
+    frame.render_widget(Clear, area);
+
+    let popup_block = Block::default()
+        .title(format!("Diff: {}", diff.title))
+        .borders(Borders::ALL)
+        .style(Style::default().bg(Color::DarkGray));
+
+    let inner_area = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Min(1),    // Diff
+            Constraint::Length(1), // Instructions
+        ])
+        .split(area);
+    frame.render_widget(popup_block, area);
+
+    let height = inner_area[0].height as usize;
+    let lines: Vec<Line> = diff
+        .lines
+        .iter()
+        .skip(diff.scroll as usize)
+        .take(height)
+        .map(|line| match line {
+            DiffLine::Same(text) => Line::from(format!("  {}", text)),
+            DiffLine::Removed(text) => {
+                Line::styled(format!("- {}", text), Style::default().fg(Color::Red))
+            }
+            DiffLine::Added(text) => {
+                Line::styled(format!("+ {}", text), Style::default().fg(Color::Green))
+            }
+            DiffLine::Skipped(count) => Line::styled(
+                format!("  … {} unchanged lines", count),
+                Style::default().fg(Color::Gray),
+            ),
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), inner_area[0]);
+
+    let instructions =
+        Paragraph::new("↑/↓ PgUp/PgDn scroll, Esc close").style(Style::default().fg(Color::Gray));
+    frame.render_widget(instructions, inner_area[1]);
+}
+
+pub fn settings_popup(frame: &mut Frame, app: &App) {
+    let Some(form) = &app.settings_form else {
+        return;
+    };
+
+    let area = centered_rect(70, 60, frame.area());
+    frame.render_widget(Clear, area);
+
+    let title = match &form.target {
+        SettingsTarget::Global => "Settings: proxy for every group".to_string(),
+        SettingsTarget::Group(group) => {
+            format!(
+                "Settings: group {}",
+                app.group_name(group).unwrap_or_default()
+            )
+        }
+        SettingsTarget::Request { group, id } => {
+            let name = app
+                .list
+                .get(group)
+                .and_then(|requests| requests.iter().find(|request| &request.id == id))
+                .map(|request| request.name.as_str())
+                .unwrap_or_default();
+            format!(
+                "Settings: {} (on top of group {})",
+                name,
+                app.group_name(group).unwrap_or_default()
+            )
+        }
+    };
+    let popup_block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .style(Style::default().bg(Color::DarkGray));
+
+    frame.render_widget(popup_block, area);
+
+    let inner_area = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Min(1),    // Fields
+            Constraint::Length(2), // Warning
+            Constraint::Length(2), // Instructions
+        ])
+        .split(area);
+
+    let lines: Vec<Line> = form
+        .fields()
+        .into_iter()
+        .enumerate()
+        .map(|(index, field)| {
+            let selected = index == form.selected;
+            let value = match field.kind {
+                AuthFieldKind::Secret if !app.password_visible => {
+                    "•".repeat(field.value.chars().count())
+                }
+                AuthFieldKind::Choice => format!("< {} >", field.value),
+                _ => field.value,
+            };
+            let label_style = if selected {
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::Gray)
+            };
+            Line::from(vec![
+                Span::styled(
+                    format!("{} {:<28}", if selected { ">" } else { " " }, field.label),
+                    label_style,
+                ),
+                Span::styled(value, Style::default().fg(Color::White)),
+            ])
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), inner_area[0]);
+
+    if form.tls.accepts_invalid_certs() {
+        let warning = Paragraph::new(
+            "⚠ Certificates and host names will NOT be checked. Anyone on the network can read and change this traffic.",
+        )
+        .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+        .wrap(Wrap { trim: false });
+        frame.render_widget(warning, inner_area[1]);
+    }
+
+    let instructions = Paragraph::new(
+        "Paths may start with ~/. CA files and no-proxy hosts are comma separated. (Enter) save / (Esc) cancel",
+    )
+    .style(Style::default().fg(Color::Gray))
+    .wrap(Wrap { trim: false });
+    frame.render_widget(instructions, inner_area[2]);
+}
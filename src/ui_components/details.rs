@@ -1,12 +1,16 @@
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
     widgets::{Block, Borders, Paragraph, Wrap},
     Frame,
 };
 
-use crate::app::models::AuthType;
-use crate::app::models::RequestDetails;
+use crate::app::auth::AuthFieldKind;
+use crate::app::history::format_time;
+use crate::app::json::{tokenize_line, JsonToken};
+use crate::app::models::{KeyValue, RequestDetails};
+use crate::app::response_view::{tls_lines, LineKind, ResponseLine, ResponseTab};
 use crate::app::state::App;
 use crate::app::ui_state::{ActivePanel, DetailField};
 
@@ -58,25 +62,14 @@ pub fn render_params_section(
     request_details: &RequestDetails,
     area: Rect,
 ) {
-    let params_text = request_details
-        .params
-        .iter()
-        .map(|(k, v)| format!("{}: {}", k, v))
-        .collect::<Vec<_>>()
-        .join("\n");
-
-    let params = Paragraph::new(params_text).block(
-        Block::default()
-            .borders(Borders::ALL)
-            .title("Parameters (Enter to add)")
-            .border_style(if app.current_detail_field == DetailField::Params {
-                Style::default().fg(Color::Yellow)
-            } else {
-                Style::default()
-            }),
+    render_key_value_list(
+        frame,
+        "Parameters",
+        &request_details.params,
+        app.selected_param_index,
+        app.current_detail_field == DetailField::Params,
+        area,
     );
-
-    frame.render_widget(params, area);
 }
 
 pub fn render_headers_section(
@@ -85,25 +78,75 @@ pub fn render_headers_section(
     request_details: &RequestDetails,
     area: Rect,
 ) {
-    let headers_text = request_details
-        .headers
-        .iter()
-        .map(|(k, v)| format!("{}: {}", k, v))
-        .collect::<Vec<_>>()
-        .join("\n");
+    render_key_value_list(
+        frame,
+        "Headers",
+        &request_details.headers,
+        app.selected_header_index,
+        app.current_detail_field == DetailField::Headers,
+        area,
+    );
+}
 
-    let headers = Paragraph::new(headers_text).block(
-        Block::default()
-            .borders(Borders::ALL)
-            .title("Headers (Enter to add)")
-            .border_style(if app.current_detail_field == DetailField::Headers {
-                Style::default().fg(Color::Yellow)
+/// Renders params or headers as a list. The selected row is highlighted while the section
+/// has focus and disabled rows are greyed out.
+fn render_key_value_list(
+    frame: &mut Frame,
+    title: &str,
+    rows: &[KeyValue],
+    selected: usize,
+    focused: bool,
+    area: Rect,
+) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(if focused {
+            format!(
+                "{} (Enter/a add, e edit, d delete, Space enable/disable)",
+                title
+            )
+        } else {
+            format!("{} (Enter to add)", title)
+        })
+        .border_style(if focused {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        });
+
+    let lines: Vec<Line> = rows
+        .iter()
+        .enumerate()
+        .map(|(idx, row)| {
+            let mut style = if row.enabled {
+                Style::default()
             } else {
                 Style::default()
-            }),
-    );
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::CROSSED_OUT)
+            };
+            if focused && idx == selected {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            Line::from(vec![
+                Span::raw(if row.enabled { "[x] " } else { "[ ] " }),
+                Span::styled(format!("{}: {}", row.key, row.value), style),
+            ])
+        })
+        .collect();
 
-    frame.render_widget(headers, area);
+    // Keep the selected row in view
+    let visible = block.inner(area).height as usize;
+    let offset = if focused && visible > 0 {
+        selected.saturating_sub(visible - 1)
+    } else {
+        0
+    };
+
+    let list = Paragraph::new(lines)
+        .block(block)
+        .scroll((offset as u16, 0));
+    frame.render_widget(list, area);
 }
 
 pub fn render_auth_section(
@@ -132,95 +175,118 @@ pub fn render_auth_section(
         ));
     frame.render_widget(auth_type, auth_layout[0]);
 
-    match request_details.auth_type {
-        AuthType::None => {
-            let no_auth = Paragraph::new("No authentication required")
-                .block(Block::default().borders(Borders::ALL));
-            frame.render_widget(no_auth, auth_layout[1]);
-        }
-        AuthType::Basic => render_basic_auth_section(frame, app, auth_layout[1]),
+    let fields = request_details.auth_details.fields();
+    if fields.is_empty() {
+        let no_auth = Paragraph::new("No authentication required")
+            .block(Block::default().borders(Borders::ALL));
+        frame.render_widget(no_auth, auth_layout[1]);
+        return;
     }
-}
 
-pub fn render_basic_auth_section(frame: &mut Frame, app: &App, area: Rect) {
-    let basic_auth_layout = Layout::default()
+    // Fields fill the rows left to right, in Tab order
+    let rows = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(3), // Username
-            Constraint::Length(3), // Password
+        .constraints(vec![
+            Constraint::Length(3);
+            fields.len().div_ceil(AUTH_COLUMNS)
         ])
-        .split(area);
+        .split(auth_layout[1]);
+    let cells: Vec<Rect> = rows
+        .iter()
+        .flat_map(|row| {
+            Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![
+                    Constraint::Ratio(1, AUTH_COLUMNS as u32);
+                    AUTH_COLUMNS
+                ])
+                .split(*row)
+                .to_vec()
+        })
+        .collect();
 
-    // Username
-    let username_block = Block::default()
-        .borders(Borders::ALL)
-        .title("Username")
-        .border_style(if app.current_detail_field == DetailField::AuthUsername {
-            Style::default().fg(Color::Yellow)
-        } else {
-            Style::default()
-        });
+    for (index, field) in fields.iter().enumerate() {
+        let focused = app.current_detail_field == DetailField::AuthField(index);
+        let title = match field.kind {
+            AuthFieldKind::Choice => format!("{} (←/→)", field.label),
+            _ => field.label.to_string(),
+        };
+        let field_block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(if focused {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            });
 
-    let username_area = username_block.inner(basic_auth_layout[0]);
-    frame.render_widget(username_block, basic_auth_layout[0]);
+        let field_area = field_block.inner(cells[index]);
+        frame.render_widget(field_block, cells[index]);
 
-    if app.current_detail_field == DetailField::AuthUsername {
-        frame.render_widget(&app.auth_username_textarea, username_area);
-    } else {
-        frame.render_widget(
-            Paragraph::new(app.auth_username_textarea.lines().join("\n"))
-                .style(Style::default())
-                .wrap(Wrap { trim: true }),
-            username_area,
-        );
+        match app.auth_textareas.get(index) {
+            Some(textarea) if focused && field.kind != AuthFieldKind::Choice => {
+                frame.render_widget(textarea, field_area);
+            }
+            _ => {
+                let text = if field.kind == AuthFieldKind::Secret && !app.password_visible {
+                    "•".repeat(field.value.chars().count())
+                } else {
+                    field.value.clone()
+                };
+                frame.render_widget(
+                    Paragraph::new(text)
+                        .style(Style::default())
+                        .wrap(Wrap { trim: true }),
+                    field_area,
+                );
+            }
+        }
     }
+}
 
-    // Password
-    let password_block = Block::default()
-        .borders(Borders::ALL)
-        .title("Password")
-        .border_style(if app.current_detail_field == DetailField::AuthPassword {
-            Style::default().fg(Color::Yellow)
-        } else {
-            Style::default()
-        });
-
-    let password_area = password_block.inner(basic_auth_layout[1]);
-    frame.render_widget(password_block, basic_auth_layout[1]);
+/// Fields of the auth form side by side.
+const AUTH_COLUMNS: usize = 2;
 
-    let password_text = if app.password_visible {
-        app.auth_password_textarea.lines().join("\n")
-    } else {
-        app.auth_password_textarea
-            .lines()
-            .iter()
-            .map(|line| "•".repeat(line.len()))
-            .collect::<Vec<_>>()
-            .join("\n")
-    };
-
-    if app.current_detail_field == DetailField::AuthPassword {
-        frame.render_widget(&app.auth_password_textarea, password_area);
-    } else {
-        frame.render_widget(
-            Paragraph::new(password_text)
-                .style(Style::default())
-                .wrap(Wrap { trim: true }),
-            password_area,
-        );
-    }
+/// Height of the auth section: the auth type plus a row of boxes per `AUTH_COLUMNS` fields.
+pub fn auth_section_height(request_details: &RequestDetails) -> u16 {
+    let fields = request_details.auth_details.fields().len().max(1);
+    3 + 3 * fields.div_ceil(AUTH_COLUMNS) as u16
 }
 
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
 pub fn render_send_request_section(frame: &mut Frame, app: &App, area: Rect) {
-    let send_text = if app.is_sending {
-        "⏳ Sending Request..."
-    } else {
-        "🚀 Press Ctrl+S to Send Request"
+    let insecure = app
+        .current_tls()
+        .is_some_and(|tls| tls.accepts_invalid_certs());
+    let mut send_text = match app.sender.elapsed() {
+        Some(elapsed) if app.is_sending => {
+            let spinner =
+                SPINNER_FRAMES[(elapsed.as_millis() / 80) as usize % SPINNER_FRAMES.len()];
+            format!(
+                "{} Sending Request... {:.1}s (Esc or Ctrl+C to cancel)",
+                spinner,
+                elapsed.as_secs_f32()
+            )
+        }
+        _ if app.is_authorizing() => {
+            "🔑 Waiting for the sign-in in the browser... (Esc or Ctrl+C to cancel)".to_string()
+        }
+        _ => match app.is_signed_in() {
+            Some(true) => "🚀 Press Ctrl+S to Send Request, Ctrl+O to sign in again".to_string(),
+            Some(false) => "🚀 Press Ctrl+O to sign in, then Ctrl+S to Send Request".to_string(),
+            None => "🚀 Press Ctrl+S to Send Request".to_string(),
+        },
     };
+    if insecure {
+        send_text.push_str("  ⚠ INSECURE: TLS certificates are not verified");
+    }
 
     let send_block = Block::default()
         .borders(Borders::ALL)
-        .style(Style::default().fg(if app.is_sending {
+        .style(Style::default().fg(if insecure {
+            Color::Red
+        } else if app.is_sending || app.is_authorizing() {
             Color::Yellow
         } else {
             Color::Green
@@ -230,38 +296,264 @@ pub fn render_send_request_section(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(send_paragraph, area);
 }
 
-pub fn render_response_section(frame: &mut Frame, app: &App, area: Rect) {
-    if let Some(response) = &app.last_response {
-        let status_color = match response.status {
-            200..=299 => Color::Green,
-            300..=399 => Color::Blue,
-            400..=499 => Color::Yellow,
-            _ => Color::Red,
-        };
+/// Colour of a status code: green for success, blue for redirects, yellow for client
+/// errors and red otherwise.
+fn status_color(status: u16) -> Color {
+    match status {
+        200..=299 => Color::Green,
+        300..=399 => Color::Blue,
+        400..=499 => Color::Yellow,
+        _ => Color::Red,
+    }
+}
+
+/// The history of the current request, newest first, in place of the response.
+fn render_history_section(frame: &mut Frame, app: &App, area: Rect) {
+    let history = app.current_history();
+    let view = &app.history_view;
+
+    let history_block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("History ({})", history.len()))
+        .border_style(Style::default().fg(Color::Yellow));
+
+    // Keep the cursor in view
+    let height = history_block.inner(area).height as usize;
+    let offset = view.selected.saturating_sub(height.saturating_sub(1));
+    let lines: Vec<Line> = history
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(height)
+        .map(|(idx, entry)| {
+            let marker = if view.marked == Some(idx) { "* " } else { "  " };
+            let mut style = Style::default().fg(status_color(entry.response.status));
+            if idx == view.selected {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            Line::from(vec![
+                Span::raw(marker),
+                Span::styled(entry.summary(), style),
+            ])
+        })
+        .collect();
+
+    frame.render_widget(Paragraph::new(lines).block(history_block), area);
+}
 
-        let response_text = format!(
-            "Status: {} {}\nTime: {}ms\n\nHeaders:\n{}\n\nBody:\n{}",
-            response.status,
-            response.status_text,
-            response.time_taken.as_millis(),
-            response
-                .headers
-                .iter()
-                .map(|(k, v)| format!("{}: {}", k, v))
-                .collect::<Vec<_>>()
-                .join("\n"),
-            response.body
+pub fn render_response_section(frame: &mut Frame, app: &App, area: Rect) {
+    if app.history_view.open {
+        render_history_section(frame, app, area);
+    } else if let Some(authorize_url) = app.current_authorize_url() {
+        let lines = vec![
+            Line::from("Waiting for you to sign in in the browser..."),
+            Line::from(""),
+            Line::from("If no browser opened, visit this URL (copied to the clipboard):"),
+            Line::from(Span::styled(
+                authorize_url,
+                Style::default().fg(Color::Cyan),
+            )),
+        ];
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Sign in")
+            .title_style(Style::default().fg(Color::Yellow));
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(block)
+                .wrap(Wrap { trim: false }),
+            area,
         );
+    } else if let Some(error) = &app.last_error {
+        let mut lines = vec![
+            Line::from(Span::styled(
+                error.kind.as_str(),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            )),
+            Line::from(Span::styled(error.hint(), Style::default().fg(Color::Gray))),
+        ];
 
-        let response_block = Block::default()
+        if let Some(elapsed) = error.elapsed {
+            lines.push(Line::from(format!(
+                "Failed after: {}ms",
+                elapsed.as_millis()
+            )));
+        }
+        if let Some(proxy) = &error.proxy {
+            lines.push(Line::from(format!("Proxy: {}", proxy)));
+        }
+
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            error.message.as_str(),
+            Style::default().fg(Color::Red),
+        )));
+
+        if !error.causes.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::from("Caused by:"));
+            for (idx, cause) in error.causes.iter().enumerate() {
+                lines.push(Line::from(format!("  {}: {}", idx, cause)));
+            }
+        }
+
+        // The certificates usually explain a TLS error
+        if let Some(tls) = &error.tls {
+            lines.push(Line::from(""));
+            lines.push(Line::from("TLS:"));
+            for line in tls_lines(Some(tls)) {
+                let spans: Vec<Span> = line_segments(&line)
+                    .into_iter()
+                    .map(|(text, style)| Span::styled(text.to_string(), style))
+                    .collect();
+                lines.push(Line::from(spans));
+            }
+        }
+
+        let error_block = Block::default()
             .borders(Borders::ALL)
             .title("Response")
-            .title_style(Style::default().fg(status_color));
+            .title_style(Style::default().fg(Color::Red));
 
-        let response_paragraph = Paragraph::new(response_text)
-            .block(response_block)
+        let error_paragraph = Paragraph::new(lines)
+            .block(error_block)
             .wrap(Wrap { trim: true });
 
+        frame.render_widget(error_paragraph, area);
+    } else if let Some(response) = &app.last_response {
+        let status_color = status_color(response.status);
+
+        let view = &app.response_view;
+        let filter = app.response_filter();
+        let mut title = match view.tab {
+            ResponseTab::Response => "[Response] TLS",
+            ResponseTab::Tls => "Response [TLS]",
+        }
+        .to_string();
+        if let Some(shown) = app.history_view.shown {
+            if let Some(entry) = app.current_history().get(shown) {
+                title.push_str(&format!(" from {}", format_time(entry.sent_at)));
+            }
+        }
+        if view.filter_input.is_some() {
+            title.push_str(&format!(" filter: {}_", filter));
+        } else if !filter.is_empty() {
+            title.push_str(&format!(" [{}]", filter));
+        }
+        if view.searching || !view.search_query.is_empty() {
+            title.push_str(&format!(" /{}", view.search_query));
+            if view.searching {
+                title.push('_');
+            }
+            if view.matches.is_empty() {
+                title.push_str(" (no matches)");
+            } else {
+                title.push_str(&format!(
+                    " ({}/{})",
+                    view.current_match + 1,
+                    view.matches.len()
+                ));
+            }
+        }
+
+        let response_block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .title_style(Style::default().fg(status_color))
+            .border_style(if app.active_panel == ActivePanel::Response {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            });
+
+        // Only the visible rows are styled, bodies can have many thousands of lines
+        let response_lines = app.current_response_lines();
+        let height = response_block.inner(area).height as usize;
+        let lines: Vec<Line> = response_lines
+            .iter()
+            .enumerate()
+            .skip(view.scroll as usize)
+            .take(height)
+            .map(|(idx, line)| {
+                let ranges: Vec<(usize, usize, bool)> = view
+                    .matches
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, found)| found.line == idx)
+                    .map(|(n, found)| (found.start, found.end, n == view.current_match))
+                    .collect();
+                highlight_matches(line_segments(line), &ranges)
+            })
+            .collect();
+
+        // No wrapping, long lines scroll horizontally instead
+        let response_paragraph = Paragraph::new(lines)
+            .block(response_block)
+            .scroll((0, view.horizontal_scroll));
+
         frame.render_widget(response_paragraph, area);
     }
 }
+
+/// Splits a response line into styled pieces, colouring JSON by token type.
+fn line_segments(line: &ResponseLine) -> Vec<(&str, Style)> {
+    match line.kind {
+        LineKind::Plain => vec![(line.text.as_str(), Style::default())],
+        LineKind::Notice => vec![(line.text.as_str(), Style::default().fg(Color::Yellow))],
+        LineKind::Error => vec![(line.text.as_str(), Style::default().fg(Color::Red))],
+        LineKind::Json => tokenize_line(&line.text)
+            .into_iter()
+            .map(|(token, text)| {
+                let style = match token {
+                    JsonToken::Key => Style::default().fg(Color::Cyan),
+                    JsonToken::String => Style::default().fg(Color::Green),
+                    JsonToken::Number => Style::default().fg(Color::Yellow),
+                    JsonToken::Boolean => Style::default().fg(Color::Magenta),
+                    JsonToken::Null => Style::default().fg(Color::DarkGray),
+                    JsonToken::Punctuation => Style::default(),
+                };
+                (text, style)
+            })
+            .collect(),
+    }
+}
+
+/// Builds a line from `segments`, highlighting the byte `ranges` of search matches. The
+/// flag marks the current match.
+fn highlight_matches<'a>(
+    segments: Vec<(&'a str, Style)>,
+    ranges: &[(usize, usize, bool)],
+) -> Line<'a> {
+    let mut spans = Vec::new();
+    let mut offset = 0;
+
+    for (text, style) in segments {
+        let end = offset + text.len();
+        let mut pos = offset;
+        for &(start, stop, current) in ranges {
+            let (start, stop) = (start.max(pos), stop.min(end));
+            if start >= stop {
+                continue;
+            }
+            if pos < start {
+                spans.push(Span::styled(&text[pos - offset..start - offset], style));
+            }
+            let match_style = if current {
+                Style::default().fg(Color::Black).bg(Color::LightRed)
+            } else {
+                Style::default().fg(Color::Black).bg(Color::Yellow)
+            };
+            spans.push(Span::styled(
+                &text[start - offset..stop - offset],
+                match_style,
+            ));
+            pos = stop;
+        }
+        if pos < end {
+            spans.push(Span::styled(&text[pos - offset..], style));
+        }
+        offset = end;
+    }
+
+    Line::from(spans)
+}
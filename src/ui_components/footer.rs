@@ -7,9 +7,10 @@ use ratatui::{
 };
 
 use crate::app::state::{App, CurrentScreen};
+use crate::app::ui_state::ActivePanel;
 
 pub fn render_footer(frame: &mut Frame, app: &App, area: Rect) {
-    let current_navigation_text = vec![
+    let mut current_navigation_text = vec![
         match app.current_screen {
             CurrentScreen::Main => Span::styled("Normal Mode", Style::default().fg(Color::Green)),
             CurrentScreen::Editing => {
@@ -26,6 +27,23 @@ pub fn render_footer(frame: &mut Frame, app: &App, area: Rect) {
             CurrentScreen::RequestDetail => {
                 Span::styled("Request Detail", Style::default().fg(Color::Blue))
             }
+            CurrentScreen::Environments => {
+                Span::styled("Environments", Style::default().fg(Color::Cyan))
+            }
+            CurrentScreen::Importing => Span::styled("Importing", Style::default().fg(Color::Cyan)),
+            CurrentScreen::CurlExport => {
+                Span::styled("Exporting curl", Style::default().fg(Color::Cyan))
+            }
+            CurrentScreen::CurlImport => {
+                Span::styled("Importing curl", Style::default().fg(Color::Cyan))
+            }
+            CurrentScreen::ResponseTree => {
+                Span::styled("Exploring Response", Style::default().fg(Color::Cyan))
+            }
+            CurrentScreen::ResponseDiff => {
+                Span::styled("Comparing Responses", Style::default().fg(Color::Cyan))
+            }
+            CurrentScreen::Settings => Span::styled("Settings", Style::default().fg(Color::Cyan)),
         },
         Span::styled(" | ", Style::default().fg(Color::White)),
         if app.groups.is_some() {
@@ -33,19 +51,69 @@ pub fn render_footer(frame: &mut Frame, app: &App, area: Rect) {
         } else {
             Span::styled("Not Editing", Style::default().fg(Color::DarkGray))
         },
+        Span::styled(" | ", Style::default().fg(Color::White)),
+        match app.get_active_environment() {
+            Some(env) => Span::styled(
+                format!("Env: {}", env.name),
+                Style::default().fg(Color::Cyan),
+            ),
+            None => Span::styled("No Environment", Style::default().fg(Color::DarkGray)),
+        },
     ];
 
+    let proxy = match (&app.last_response, &app.last_error) {
+        (Some(response), _) => response.proxy.as_ref(),
+        (None, Some(error)) => error.proxy.as_ref(),
+        (None, None) => None,
+    };
+    if let Some(proxy) = proxy {
+        current_navigation_text.push(Span::styled(" | ", Style::default().fg(Color::White)));
+        current_navigation_text.push(Span::styled(
+            format!("Proxy: {}", proxy),
+            Style::default().fg(Color::Magenta),
+        ));
+    }
+
+    for error in [&app.workspace_error, &app.clipboard_error]
+        .into_iter()
+        .flatten()
+    {
+        current_navigation_text.push(Span::styled(" | ", Style::default().fg(Color::White)));
+        current_navigation_text.push(Span::styled(
+            error.as_str(),
+            Style::default().fg(Color::Red),
+        ));
+    }
+
     let mode_footer = Paragraph::new(Line::from(current_navigation_text))
         .block(Block::default().borders(Borders::ALL));
 
     let current_keys_hint = match app.current_screen {
-        CurrentScreen::Main => "(q) quit / (e) new group / (a) add request / (↑↓) select group / (→ ←) minimize/maximize group / (→) Details Pane on Request",
+        CurrentScreen::Main if app.active_panel == ActivePanel::Response => {
+            if app.history_view.open {
+                "(↑/↓) select / (Enter) show / (m) mark / (d) diff with marked or previous / (ESC) close"
+            } else if app.response_view.filter_input.is_some() {
+                "(type) JSONPath filter, e.g. $.data[*].id or .data[].id / (Enter) save / (ESC) cancel"
+            } else if app.response_view.searching {
+                "(type) search / (Enter) done / (ESC) cancel"
+            } else {
+                "(↑↓←→) scroll / (PgUp/PgDn) page / (/) search / (n/N) next/previous match / (f) filter / (h) history / (x) explore / (t) TLS tab / (Tab) details / (ESC) back"
+            }
+        }
+        CurrentScreen::Main => "(q) quit / (e) new group / (a) add request / (v) environments / (i) import / (c) copy as curl / (p) paste curl / (x) explore response / (t) settings / (g) global proxy / (↑↓) select group / (→ ←) minimize/maximize group / (→) Details Pane on Request",
         CurrentScreen::Editing => "(ESC) cancel / (Enter) save",
         CurrentScreen::Deleting => "(↑/↓) select group / (Enter) confirm / (ESC) cancel",
         CurrentScreen::DeleteConfirm => "Are you sure you want to delete this group? (y/n)",
         CurrentScreen::Exiting => "Are you sure you want to quit? (y/n)",
         CurrentScreen::AddingRequest => "(ESC) cancel / (Enter) save / (→) change type",
         CurrentScreen::RequestDetail => "(ESC) back / (Tab) next field / (Shift+Tab) previous field",
+        CurrentScreen::Environments => "(Enter) activate / (n) new / (a) add variable / (d) delete / (ESC) close",
+        CurrentScreen::Importing => "(Enter) import / (ESC) cancel",
+        CurrentScreen::CurlExport => "(Enter/ESC) close",
+        CurrentScreen::CurlImport => "(Enter) import / (ESC) cancel",
+        CurrentScreen::ResponseTree => "(↑/↓) move / (→/←/Enter) expand/collapse / (y) copy path / (ESC) close",
+        CurrentScreen::ResponseDiff => "(↑/↓) scroll / (PgUp/PgDn) page / (ESC) close",
+        CurrentScreen::Settings => "(↑/↓) select / (type) edit / (←/→/Space) change choice / (Enter) save / (ESC) cancel",
     };
 
     let key_notes_footer = Paragraph::new(Line::from(Span::styled(
@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use ratquest::app::json::FormattedBody;
+use ratquest::app::models::{ApiRequest, RequestType};
+use ratquest::app::requests::RequestResponse;
+use ratquest::app::state::App;
+
+/// An app without a workspace file, holding a single request in the group `group` that is
+/// selected and points at `url`.
+pub fn app_with_request(request_type: RequestType, url: &str) -> App {
+    let mut app = App::with_workspace(None);
+    let group = app.add_group("group".to_string());
+    app.list
+        .get_mut(&group)
+        .unwrap()
+        .push(ApiRequest::new("request".to_string(), request_type));
+    app.selected_group_index = Some(0);
+    app.selected_request_index = Some(0);
+    if let Some(request) = app.get_selected_request_mut() {
+        request.details.url = url.to_string();
+    }
+    app
+}
+
+/// A JSON response as if it had just been received.
+pub fn json_response(status: u16, body: &str) -> RequestResponse {
+    let headers = vec![("content-type".to_string(), "application/json".to_string())];
+    RequestResponse {
+        status,
+        status_text: status.to_string(),
+        formatted_body: FormattedBody::new(&headers, body),
+        headers,
+        body: body.to_string(),
+        time_taken: Duration::from_millis(5),
+        tls: None,
+        proxy: None,
+    }
+}
+
+/// The workspace file inside a temp directory unique to this test run. Returns the directory
+/// too, so the test can remove it.
+pub fn temp_workspace(name: &str) -> (PathBuf, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("ratquest-it-{}-{}", name, std::process::id()));
+    let path = dir.join("workspace.json");
+    (dir, path)
+}
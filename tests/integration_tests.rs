@@ -1,31 +1,36 @@
 pub use ratquest::app::models::{
-    ApiRequest, AuthDetails, AuthType, BasicAuth, RequestDetails, RequestType,
+    ApiRequest, AuthDetails, AuthType, BasicAuth, RequestDetails, RequestType, TlsSettings,
 };
 pub use ratquest::app::state::{App, CurrentScreen};
 pub use ratquest::app::ui_state::DetailField;
 
+mod common;
+
 mod app_integration_tests {
+    use super::common::{app_with_request, json_response, temp_workspace};
     use super::*;
 
     #[test]
     fn test_full_request_workflow() {
-        let mut app = App::new();
+        let mut app = App::with_workspace(None);
 
         // Create a new group
         app.key_input = String::from("test_group");
         app.save_group();
 
         // Verify group was created
-        assert!(app.list.contains_key("test_group"));
+        assert_eq!(app.groups_vec.len(), 1);
+        let group = app.groups_vec[0].clone();
+        assert_eq!(app.group_name(&group), Some("test_group"));
 
         // Add a request to the group
-        app.selected_group = Some("test_group".to_string());
+        app.selected_group = Some(group.clone());
         app.request_name_input = String::from("test_request");
         app.selected_request_type = RequestType::POST;
         app.save_request();
 
         // Verify request was created
-        let requests = app.list.get("test_group").unwrap();
+        let requests = app.list.get(&group).unwrap();
         assert_eq!(requests.len(), 1);
         assert_eq!(requests[0].name, "test_request");
         assert!(matches!(requests[0].request_type, RequestType::POST));
@@ -48,14 +53,16 @@ mod app_integration_tests {
             });
         }
 
-        app.auth_username_textarea = tui_textarea::TextArea::from(vec![String::from("testuser")]);
-        app.auth_password_textarea = tui_textarea::TextArea::from(vec![String::from("testpass")]);
+        app.auth_textareas = vec![
+            tui_textarea::TextArea::from(vec![String::from("testuser")]),
+            tui_textarea::TextArea::from(vec![String::from("testpass")]),
+        ];
         app.save_textarea_content();
 
         // Verify final state
         if let Some(request) = app.get_selected_request() {
             assert_eq!(request.details.url, "http://api.example.com");
-            assert!(request.details.headers.contains_key("Authorization"));
+            assert!(request.details.header("Authorization").is_some());
             if let AuthDetails::Basic(auth) = &request.details.auth_details {
                 assert_eq!(auth.username, "testuser");
                 assert_eq!(auth.password, "testpass");
@@ -66,6 +73,615 @@ mod app_integration_tests {
             panic!("Failed to get selected request");
         }
     }
+
+    #[test]
+    fn test_bearer_api_key_and_oauth2_auth_are_sent() {
+        let mut app = app_with_request(RequestType::GET, "http://example.com");
+
+        // None -> Basic -> Bearer Token
+        app.next_auth_type();
+        app.next_auth_type();
+        assert_eq!(app.auth_textareas.len(), 1);
+        app.auth_textareas[0] = tui_textarea::TextArea::from(vec![String::from("t0ken")]);
+        app.save_textarea_content();
+        let prepared = app.prepare_request().unwrap();
+        assert!(prepared
+            .headers
+            .contains(&("Authorization".to_string(), "Bearer t0ken".to_string())));
+
+        // API keys go in a header unless moved to the query
+        app.next_auth_type();
+        assert_eq!(app.auth_textareas.len(), 3);
+        app.auth_textareas[0] = tui_textarea::TextArea::from(vec![String::from("api_key")]);
+        app.auth_textareas[1] = tui_textarea::TextArea::from(vec![String::from("s3cret")]);
+        app.save_textarea_content();
+        let prepared = app.prepare_request().unwrap();
+        assert!(prepared
+            .headers
+            .contains(&("api_key".to_string(), "s3cret".to_string())));
+        assert!(!prepared
+            .headers
+            .iter()
+            .any(|(key, _)| key == "Authorization"));
+
+        app.cycle_auth_choice(2);
+        let prepared = app.prepare_request().unwrap();
+        assert_eq!(
+            prepared.params,
+            [("api_key".to_string(), "s3cret".to_string())]
+        );
+        assert!(prepared.headers.is_empty());
+
+        // OAuth 2.0 tokens are only fetched when the request is sent
+        app.next_auth_type();
+        assert_eq!(app.auth_textareas.len(), 6);
+        app.auth_textareas[1] =
+            tui_textarea::TextArea::from(vec![String::from("http://idp.local/token")]);
+        app.auth_textareas[2] = tui_textarea::TextArea::from(vec![String::from("client")]);
+        app.save_textarea_content();
+        let prepared = app.prepare_request().unwrap();
+        let oauth2 = prepared.oauth2.unwrap();
+        assert_eq!(oauth2.token_url, "http://idp.local/token");
+        assert_eq!(oauth2.client_id, "client");
+        assert!(prepared.headers.is_empty());
+        assert!(prepared.params.is_empty());
+    }
+
+    #[test]
+    fn test_oauth2_sign_in_saves_tokens_on_the_request() {
+        use ratquest::app::models::{OAuth2Auth, OAuth2Grant};
+        use std::io::{Read, Write};
+        use std::net::{TcpListener, TcpStream};
+        use std::time::{Duration, Instant};
+
+        // The token endpoint of a fake identity provider
+        let idp = TcpListener::bind("127.0.0.1:0").unwrap();
+        let token_url = format!("http://{}/token", idp.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = idp.accept().unwrap();
+            let mut buf = [0; 4096];
+            let _ = stream.read(&mut buf);
+            let body = r#"{"access_token":"a1","refresh_token":"r1","expires_in":3600}"#;
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+                 Connection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+        });
+
+        let mut app = app_with_request(RequestType::GET, "http://api.local/me");
+        if let Some(request) = app.get_selected_request_mut() {
+            request.details.auth_type = AuthType::OAuth2;
+            request.details.auth_details = AuthDetails::OAuth2(OAuth2Auth {
+                grant: OAuth2Grant::AuthorizationCode,
+                authorize_url: "http://idp.local/authorize".to_string(),
+                token_url,
+                client_id: "ratquest".to_string(),
+                ..Default::default()
+            });
+        }
+        assert_eq!(app.is_signed_in(), Some(false));
+
+        app.start_authorization();
+        assert!(app.is_authorizing());
+        let authorize_url = url::Url::parse(app.current_authorize_url().unwrap()).unwrap();
+        assert_eq!(app.clipboard.as_deref(), Some(authorize_url.as_str()));
+        assert_eq!(app.browser_url.as_deref(), Some(authorize_url.as_str()));
+
+        // The browser comes back from the identity provider with a code
+        let param = |name: &str| {
+            authorize_url
+                .query_pairs()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned())
+                .unwrap()
+        };
+        let redirect_uri = url::Url::parse(&param("redirect_uri")).unwrap();
+        let mut browser = TcpStream::connect(("127.0.0.1", redirect_uri.port().unwrap())).unwrap();
+        write!(
+            browser,
+            "GET {}?code=c0de&state={} HTTP/1.1\r\n\r\n",
+            redirect_uri.path(),
+            param("state")
+        )
+        .unwrap();
+        let mut page = String::new();
+        browser.read_to_string(&mut page).unwrap();
+        assert!(page.contains("Signed in"));
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !app.poll_authorization() {
+            assert!(Instant::now() < deadline, "sign-in never completed");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!app.is_authorizing());
+        assert!(app.last_error.is_none());
+        assert_eq!(app.is_signed_in(), Some(true));
+        let AuthDetails::OAuth2(oauth2) = &app.get_selected_request().unwrap().details.auth_details
+        else {
+            panic!("Expected OAuth 2.0 auth details");
+        };
+        let tokens = oauth2.tokens.as_ref().unwrap();
+        assert_eq!(tokens.access_token, "a1");
+        assert_eq!(tokens.refresh_token.as_deref(), Some("r1"));
+    }
+
+    #[test]
+    fn test_multiline_body_is_kept() {
+        let mut app = app_with_request(RequestType::POST, "http://example.com");
+
+        let body = "{\n  \"name\": \"ann\",\n  \"age\": 3\n}\n";
+        app.body_textarea = tui_textarea::TextArea::from(body.split('\n'));
+        app.save_textarea_content();
+        assert_eq!(app.get_selected_request().unwrap().details.body, body);
+
+        // Reloading the textarea from the request must not lose any lines
+        app.sync_textarea_content();
+        assert_eq!(app.body_textarea.lines().len(), 5);
+        app.save_textarea_content();
+        assert_eq!(app.get_selected_request().unwrap().details.body, body);
+    }
+
+    #[test]
+    fn test_up_and_down_stay_inside_a_multiline_body() {
+        use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let mut app = app_with_request(RequestType::POST, "http://example.com");
+        app.body_textarea = tui_textarea::TextArea::from(["{", "  \"a\": 1", "}"]);
+        app.current_detail_field = DetailField::Body;
+
+        let down = KeyEvent::new(KeyCode::Down, KeyModifiers::NONE);
+        assert!(app.handle_vertical_in_field(down));
+        assert!(app.current_detail_field == DetailField::Body);
+        assert_eq!(app.body_textarea.cursor().0, 1);
+
+        // On the first line Up is left to move to the field above
+        let up = KeyEvent::new(KeyCode::Up, KeyModifiers::NONE);
+        assert!(app.handle_vertical_in_field(up));
+        assert_eq!(app.body_textarea.cursor().0, 0);
+        assert!(!app.handle_vertical_in_field(up));
+    }
+
+    #[test]
+    fn test_edit_delete_and_disable_headers_and_params() {
+        let mut app = app_with_request(RequestType::GET, "http://example.com");
+
+        for (key, value) in [("Accept", "text/plain"), ("X-Debug", "1"), ("X-Tyop", "a")] {
+            app.start_adding_header();
+            app.header_key_input = key.to_string();
+            app.header_value_input = value.to_string();
+            app.save_header();
+        }
+        app.start_adding_params();
+        app.params_key_input = "page".to_string();
+        app.params_value_input = "1".to_string();
+        app.save_params();
+
+        // Rows are listed in the order they were added
+        app.selected_header_index = 2;
+        app.start_editing_header();
+        assert_eq!(app.header_value_input, "a");
+        app.header_key_input = "X-Typo".to_string();
+        app.save_header();
+
+        app.selected_header_index = 0;
+        app.start_editing_header();
+        app.header_value_input = "application/json".to_string();
+        app.save_header();
+
+        app.selected_header_index = 1;
+        app.toggle_selected_header();
+        app.selected_param_index = 0;
+        app.toggle_selected_param();
+
+        let details = &app.get_selected_request().unwrap().details;
+        let keys: Vec<_> = details.headers.iter().map(|h| h.key.as_str()).collect();
+        assert_eq!(keys, ["Accept", "X-Debug", "X-Typo"]);
+        assert_eq!(details.header("Accept"), Some("application/json"));
+        assert_eq!(details.header("X-Typo"), Some("a"));
+        assert!(!details.headers[1].enabled);
+        assert!(!details.params[0].enabled);
+
+        // Disabled rows stay in the request but are not sent
+        let prepared = app.prepare_request().unwrap();
+        assert!(!prepared.headers.iter().any(|(key, _)| key == "X-Debug"));
+        assert!(prepared.params.is_empty());
+        assert_eq!(prepared.headers.len(), 2);
+
+        app.selected_header_index = 1;
+        app.delete_selected_header();
+        app.delete_selected_param();
+        let details = &app.get_selected_request().unwrap().details;
+        assert!(details.header("X-Debug").is_none());
+        assert!(details.params.is_empty());
+        assert_eq!(app.selected_header_index, 1);
+    }
+
+    #[test]
+    fn test_url_query_and_params_stay_in_sync() {
+        let mut app = app_with_request(RequestType::GET, "");
+
+        // A pasted URL fills the params list
+        app.url_textarea = tui_textarea::TextArea::from(vec![String::from(
+            "https://api.example.com/x?page=2&limit=10&q=a%20b",
+        )]);
+        app.save_textarea_content();
+        let details = &app.get_selected_request().unwrap().details;
+        assert_eq!(details.url, "https://api.example.com/x");
+        let params: Vec<_> = details
+            .params
+            .iter()
+            .map(|p| (p.key.as_str(), p.value.as_str()))
+            .collect();
+        assert_eq!(params, [("page", "2"), ("limit", "10"), ("q", "a b")]);
+
+        // Editing the params rewrites the URL field
+        app.start_adding_params();
+        app.params_key_input = "tag".to_string();
+        app.params_value_input = "x&y".to_string();
+        app.save_params();
+        app.selected_param_index = 0;
+        app.toggle_selected_param();
+        assert_eq!(
+            app.url_textarea.lines()[0],
+            "https://api.example.com/x?limit=10&q=a+b&tag=x%26y"
+        );
+
+        // Editing the URL keeps the disabled param and doesn't duplicate the others
+        app.url_textarea = tui_textarea::TextArea::from(vec![String::from(
+            "https://api.example.com/x?limit=20&q=a+b&tag=x%26y",
+        )]);
+        app.save_textarea_content();
+        let details = &app.get_selected_request().unwrap().details;
+        assert_eq!(details.params.len(), 4);
+        assert!(!details.params[0].enabled);
+        assert_eq!(details.param("limit"), Some("20"));
+
+        let prepared = app.prepare_request().unwrap();
+        assert_eq!(prepared.url, "https://api.example.com/x");
+        assert_eq!(prepared.params.len(), 3);
+    }
+
+    #[test]
+    fn test_curl_import_and_export() {
+        let mut app = App::with_workspace(None);
+        app.key_input = String::from("api");
+        app.save_group();
+        let group = app.groups_vec[0].clone();
+
+        app.import_curl(
+            "curl -X POST 'https://example.com/users?notify=true' -H 'Content-Type: application/json' -d '{\"name\":\"ann\"}'",
+            Some(&group),
+        )
+        .unwrap();
+        assert!(app
+            .import_curl("wget https://example.com", Some(&group))
+            .is_err());
+
+        let request = &app.list[&group][0];
+        assert_eq!(request.name, "POST /users");
+        assert_eq!(request.details.url, "https://example.com/users");
+        assert_eq!(request.details.param("notify"), Some("true"));
+        assert_eq!(request.details.body, "{\"name\":\"ann\"}");
+
+        app.export_curl();
+        assert!(app.current_screen == CurrentScreen::CurlExport);
+        assert_eq!(
+            app.curl_export.as_deref(),
+            Some(
+                "curl -X POST 'https://example.com/users?notify=true' \
+                 -H 'Content-Type: application/json' --data-raw '{\"name\":\"ann\"}'"
+            )
+        );
+        assert_eq!(app.clipboard, app.curl_export);
+    }
+
+    #[test]
+    fn test_response_search_scrolls_to_matches() {
+        let items: Vec<String> = (0..50).map(|i| format!("{{\"id\":{}}}", i)).collect();
+        let body = format!("[{}]", items.join(","));
+
+        let mut app = App::with_workspace(None);
+        app.last_response = Some(json_response(200, &body));
+        app.response_view.viewport = (10, 40);
+
+        app.start_response_search();
+        for c in "\"ID\": 4".chars() {
+            app.push_response_search(c);
+        }
+        // Matches 4 and 40 to 49, case-insensitively
+        assert_eq!(app.response_view.matches.len(), 11);
+        let first = app.response_view.matches[0].line as u16;
+        assert!(first >= app.response_view.scroll && first < app.response_view.scroll + 10);
+
+        app.finish_response_search();
+        app.previous_response_match();
+        assert_eq!(app.response_view.current_match, 10);
+        let last = app.response_view.matches[10].line as u16;
+        assert_eq!(app.response_view.scroll, last + 1 - 10);
+
+        app.page_response(-1);
+        assert_eq!(app.response_view.scroll, last + 1 - 19);
+        app.clear_response_search();
+        assert!(app.response_view.matches.is_empty());
+    }
+
+    #[test]
+    fn test_response_filter_is_saved_on_the_request() {
+        use ratquest::app::response_view::LineKind;
+
+        let response =
+            || json_response(200, r#"{"data":[{"id":1,"name":"a"},{"id":2,"name":"b"}]}"#);
+        let body_of = |app: &App| -> Vec<String> {
+            let lines = app.current_response_lines();
+            let start = lines
+                .iter()
+                .position(|line| line.text.starts_with("Body"))
+                .unwrap();
+            lines[start..]
+                .iter()
+                .map(|line| line.text.clone())
+                .collect()
+        };
+
+        let mut app = App::with_workspace(None);
+        app.key_input = String::from("api");
+        app.save_group();
+        app.selected_group = Some(app.groups_vec[0].clone());
+        app.request_name_input = String::from("list");
+        app.save_request();
+        app.selected_group_index = Some(0);
+        app.selected_request_index = Some(0);
+        app.last_response = Some(response());
+
+        app.start_response_filter();
+        for c in ".data[].id".chars() {
+            app.push_response_filter(c);
+        }
+        assert_eq!(body_of(&app), ["Body (.data[].id, 2 matches):", "1", "2"]);
+
+        // An unfinished expression reports the error above the whole body
+        app.pop_response_filter();
+        app.pop_response_filter();
+        app.pop_response_filter();
+        assert_eq!(app.response_filter(), ".data[");
+        let lines = app.current_response_lines();
+        assert!(lines
+            .iter()
+            .any(|line| line.kind == LineKind::Error && line.text.starts_with("Invalid filter")));
+        assert!(lines.iter().any(|line| line.text == "Body:"));
+
+        app.push_response_filter('0');
+        app.push_response_filter(']');
+        app.finish_response_filter();
+        assert_eq!(
+            app.get_current_request().unwrap().response_filter,
+            ".data[0]"
+        );
+
+        // The saved filter applies to the next response
+        app.last_response = Some(response());
+        assert_eq!(
+            body_of(&app),
+            [
+                "Body (.data[0], 1 match):",
+                "{",
+                "  \"id\": 1,",
+                "  \"name\": \"a\"",
+                "}"
+            ]
+        );
+
+        // Cancelling an edit keeps the saved filter
+        app.start_response_filter();
+        app.push_response_filter('x');
+        app.cancel_response_filter();
+        assert_eq!(app.response_filter(), ".data[0]");
+    }
+
+    #[test]
+    fn test_history_follows_the_selected_request() {
+        use ratquest::app::history::{DiffLine, PendingRequest, HISTORY_LIMIT};
+        use ratquest::app::proxy::ProxyConfig;
+        use ratquest::app::requests::PreparedRequest;
+        use std::time::SystemTime;
+
+        let pending = |request_id: &str| PendingRequest {
+            request_id: request_id.to_string(),
+            request: PreparedRequest {
+                request_type: RequestType::GET,
+                url: "http://localhost/items".to_string(),
+                body: String::new(),
+                headers: Vec::new(),
+                params: vec![("page".to_string(), "1".to_string())],
+                oauth2: None,
+                digest: None,
+                aws_sigv4: None,
+                hmac: None,
+                tls: TlsSettings::default(),
+                proxy: ProxyConfig::default(),
+            },
+            sent_at: SystemTime::now(),
+        };
+
+        let mut app = App::with_workspace(None);
+        let group = app.add_group("api".to_string());
+        app.list.get_mut(&group).unwrap().extend([
+            ApiRequest::new("first".to_string(), RequestType::GET),
+            ApiRequest::new("second".to_string(), RequestType::GET),
+        ]);
+        let first = app.list[&group][0].id.clone();
+        let second = app.list[&group][1].id.clone();
+
+        let tree = app.build_tree();
+        let group_node = tree.root().query(&format!("group-{}", group)).unwrap();
+        app.tree_state.open(&tree, group_node);
+        let second_node = tree.root().query(&format!("request-{}", second)).unwrap();
+        app.tree_state.select(&tree, second_node);
+        app.tree_previous();
+        assert!(app.is_current_request(&first));
+
+        app.record_response(pending(&first), json_response(200, r#"{"count":1}"#));
+        app.record_response(pending(&first), json_response(200, r#"{"count":2}"#));
+        // A response for another request is filed away without being shown
+        app.record_response(pending(&second), json_response(500, "oops"));
+        assert_eq!(app.current_history().len(), 2);
+        assert_eq!(app.last_response.as_ref().unwrap().body, r#"{"count":2}"#);
+        assert_eq!(
+            app.current_history()[0]
+                .summary()
+                .split("  ")
+                .collect::<Vec<_>>()[1..],
+            ["200", "5ms", "11 B", "GET http://localhost/items?page=1"]
+        );
+
+        app.tree_next();
+        assert_eq!(app.last_response.as_ref().unwrap().status, 500);
+        for _ in 0..HISTORY_LIMIT {
+            app.record_response(pending(&second), json_response(204, ""));
+        }
+        assert_eq!(app.current_history().len(), HISTORY_LIMIT);
+
+        app.tree_previous();
+        assert_eq!(app.last_response.as_ref().unwrap().body, r#"{"count":2}"#);
+
+        // Diffing the latest entry against the one before it
+        app.open_history();
+        app.diff_history();
+        assert!(app.current_screen == CurrentScreen::ResponseDiff);
+        let lines = &app.history_view.diff.as_ref().unwrap().lines;
+        assert!(lines.contains(&DiffLine::Removed("  \"count\": 1".to_string())));
+        assert!(lines.contains(&DiffLine::Added("  \"count\": 2".to_string())));
+        app.close_history_diff();
+
+        // Showing an older entry
+        app.history_next();
+        app.show_history_entry();
+        assert_eq!(app.last_response.as_ref().unwrap().body, r#"{"count":1}"#);
+        assert_eq!(app.history_view.shown, Some(1));
+        assert!(!app.history_view.open);
+    }
+
+    #[test]
+    fn test_workspace_survives_restart() {
+        let (dir, path) = temp_workspace("restart");
+
+        let mut app = App::with_workspace(Some(path.clone()));
+        app.key_input = String::from("user-service");
+        app.save_group();
+        let group = app.groups_vec[0].clone();
+        app.selected_group = Some(group.clone());
+        app.request_name_input = String::from("list users");
+        app.save_request();
+
+        app.selected_group_index = Some(0);
+        app.selected_request_index = Some(0);
+        app.url_textarea =
+            tui_textarea::TextArea::from(vec![String::from("http://localhost/users")]);
+        app.save_textarea_content();
+
+        let reloaded = App::with_workspace(Some(path));
+        let requests = reloaded.list.get(&group).unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].name, "list users");
+        assert_eq!(requests[0].details.url, "http://localhost/users");
+        assert_eq!(reloaded.groups_vec, vec![group.clone()]);
+        assert_eq!(reloaded.group_name(&group), Some("user-service"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_tls_settings_survive_restart_and_merge() {
+        use ratquest::app::models::TlsVersion;
+
+        let (dir, path) = temp_workspace("tls");
+
+        let mut app = App::with_workspace(Some(path.clone()));
+        let group = app.add_group("internal".to_string());
+        app.list
+            .get_mut(&group)
+            .unwrap()
+            .push(ApiRequest::new("health".to_string(), RequestType::GET));
+        app.group_settings.entry(group.clone()).or_default().tls = TlsSettings {
+            client_cert: "~/certs/internal.p12".to_string(),
+            ca_files: vec!["~/certs/internal-ca.pem".to_string()],
+            min_version: TlsVersion::Tls12,
+            ..TlsSettings::default()
+        };
+        app.list.get_mut(&group).unwrap()[0].tls = TlsSettings {
+            accept_invalid_certs: Some(true),
+            ..TlsSettings::default()
+        };
+        app.persist_workspace();
+
+        let mut reloaded = App::with_workspace(Some(path));
+        reloaded.selected_group_index = Some(0);
+        reloaded.selected_request_index = Some(0);
+        let tls = reloaded.prepare_request().unwrap().tls;
+        assert_eq!(tls.client_cert, "~/certs/internal.p12");
+        assert_eq!(tls.ca_files, ["~/certs/internal-ca.pem"]);
+        assert_eq!(tls.min_version, TlsVersion::Tls12);
+        assert_eq!(tls.accept_invalid_certs, Some(true));
+
+        // Deleting the group drops its settings
+        reloaded.list.clear();
+        reloaded.update_groups_vec();
+        assert!(reloaded.group_settings.is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_proxy_settings_survive_restart_and_inherit() {
+        use ratquest::app::models::{ProxyMode, ProxySettings};
+
+        let (dir, path) = temp_workspace("proxy");
+
+        let mut app = App::with_workspace(Some(path.clone()));
+        let group = app.add_group("internal".to_string());
+        app.list.get_mut(&group).unwrap().extend([
+            ApiRequest::new("health".to_string(), RequestType::GET),
+            ApiRequest::new("metrics".to_string(), RequestType::GET),
+        ]);
+        app.proxy = ProxySettings {
+            mode: ProxyMode::Custom,
+            url: "http://global-proxy:3128".to_string(),
+            ..ProxySettings::default()
+        };
+        app.group_settings.entry(group.clone()).or_default().proxy = ProxySettings {
+            mode: ProxyMode::Custom,
+            url: "socks5h://bastion:1080".to_string(),
+            username: "ops".to_string(),
+            password: "secret".to_string(),
+            no_proxy: "localhost".to_string(),
+        };
+        app.list.get_mut(&group).unwrap()[1].proxy.mode = ProxyMode::Direct;
+        app.persist_workspace();
+
+        let mut reloaded = App::with_workspace(Some(path));
+        assert_eq!(reloaded.proxy.url, "http://global-proxy:3128");
+        reloaded.selected_group_index = Some(0);
+        let request_index = |app: &App, name: &str| {
+            app.list[&group]
+                .iter()
+                .position(|request| request.name == name)
+        };
+
+        reloaded.selected_request_index = request_index(&reloaded, "health");
+        let proxy = reloaded.prepare_request().unwrap().proxy;
+        let server = proxy.server_for("https://api.internal/health").unwrap();
+        assert_eq!(server.display(), "socks5h://bastion:1080");
+        assert_eq!(server.password, "secret");
+        assert_eq!(proxy.server_for("http://localhost:8080/"), None);
+
+        reloaded.selected_request_index = request_index(&reloaded, "metrics");
+        assert!(reloaded.prepare_request().unwrap().proxy.is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }
 
 mod tree_integration_tests {
@@ -73,7 +689,7 @@ mod tree_integration_tests {
 
     // #[test]
     // fn test_tree_navigation() {
-    //     let mut app = App::new();
+    //     let mut app = App::with_workspace(None);
 
     //     // Set up test data
     //     app.list.insert(
@@ -122,18 +738,14 @@ mod tree_integration_tests {
 
     #[test]
     fn test_tree_structure() {
-        let mut app = App::new();
+        let mut app = App::with_workspace(None);
 
         // Add some test data
-        app.list.insert(
-            "group1".to_string(),
-            vec![
-                ApiRequest::new("request1".to_string(), RequestType::GET),
-                ApiRequest::new("request2".to_string(), RequestType::POST),
-            ],
-        );
-
-        app.update_groups_vec();
+        let group = app.add_group("group1".to_string());
+        app.list.get_mut(&group).unwrap().extend([
+            ApiRequest::new("request1".to_string(), RequestType::GET),
+            ApiRequest::new("request2".to_string(), RequestType::POST),
+        ]);
 
         // Build the tree
         let tree = app.build_tree();
@@ -159,15 +771,52 @@ mod tree_integration_tests {
         assert_eq!(group_children.len(), 2, "Group should have 2 requests");
 
         // Verify request nodes
-        assert!(
-            group_children[0].id().contains("request1"),
-            "First request should contain 'request1', got: {}",
-            group_children[0].id()
+        let requests = &app.list[&group];
+        assert_eq!(
+            group_children[0].id(),
+            format!("request-{}", requests[0].id)
         );
-        assert!(
-            group_children[1].id().contains("request2"),
-            "Second request should contain 'request2', got: {}",
-            group_children[1].id()
+        assert_eq!(
+            group_children[1].id(),
+            format!("request-{}", requests[1].id)
         );
     }
+
+    #[test]
+    fn test_tree_selection_with_hyphens_and_duplicate_names() {
+        let mut app = App::with_workspace(None);
+        let first = app.add_group("user-service".to_string());
+        app.list
+            .get_mut(&first)
+            .unwrap()
+            .push(ApiRequest::new("get-user".to_string(), RequestType::GET));
+        let second = app.add_group("user-service".to_string());
+        app.list.get_mut(&second).unwrap().extend([
+            ApiRequest::new("get-user".to_string(), RequestType::GET),
+            ApiRequest::new("get-user".to_string(), RequestType::POST),
+        ]);
+        let user = app.add_group("user".to_string());
+        app.list.get_mut(&user).unwrap().push(ApiRequest::new(
+            "service-get-user".to_string(),
+            RequestType::PUT,
+        ));
+
+        let tree = app.build_tree();
+        assert_eq!(tree.root().iter().count(), 3);
+        let group_node = tree.root().query(&format!("group-{}", second)).unwrap();
+        app.tree_state.open(&tree, group_node);
+
+        // The second of two requests with the same name, in the second of two groups with
+        // the same name, resolves to itself
+        let request_id = format!("request-{}", app.list[&second][1].id);
+        let request_node = tree.root().query(&request_id).unwrap();
+        app.tree_state.select(&tree, request_node);
+
+        assert_eq!(app.handle_tree_selection(), Some((second.clone(), 1)));
+        assert_eq!(app.selected_tree_group(), Some(second));
+        assert!(matches!(
+            app.get_current_request().unwrap().request_type,
+            RequestType::POST
+        ));
+    }
 }